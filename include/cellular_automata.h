@@ -0,0 +1,77 @@
+#ifndef CELLULAR_AUTOMATA_H
+#define CELLULAR_AUTOMATA_H
+
+/* Generated by cbindgen from src/ffi.rs, do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// How many cells a row has, and so how many bytes a row takes.
+#define CA_WIDTH 101
+
+// How many rows the grid has.
+#define CA_HEIGHT 100
+
+// An elementary automaton, stepping a row at a time.
+typedef struct CaAutomaton CaAutomaton;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Grows `rule` from `seed`, `"center"` or a number, or from the center
+// if `seed` is null. Returns null for a seed that is neither.
+//
+// # Safety
+//
+// `seed` is null or a NUL terminated string.
+struct CaAutomaton *ca_new(uint8_t rule, const char *seed);
+
+// Computes `n` rows. A full grid stays as it is, see [`ca_next_row`] for
+// going on past it.
+//
+// # Safety
+//
+// `automaton` comes from [`ca_new`] and has not been freed.
+void ca_step(struct CaAutomaton *automaton, uint64_t n);
+
+// Computes the next row, scrolling the grid once it is full, and copies
+// it into `out`, a byte for every cell, 1 for live ones. Returns the
+// number of bytes copied, which is less than [`CA_WIDTH`] if `len` is.
+//
+// # Safety
+//
+// `automaton` comes from [`ca_new`] and has not been freed, `out` is null
+// or points to `len` writable bytes. A null `out` copies nothing.
+uintptr_t ca_next_row(struct CaAutomaton *automaton, uint8_t *out, uintptr_t len);
+
+// Copies row `y` of the grid, counted from the top, into `out` like
+// [`ca_next_row`]. Returns 0 if there is no such row.
+//
+// # Safety
+//
+// `automaton` comes from [`ca_new`] and has not been freed, `out` is null
+// or points to `len` writable bytes. A null `out` copies nothing.
+uintptr_t ca_read_row(const struct CaAutomaton *automaton, uint32_t y, uint8_t *out, uintptr_t len);
+
+// The number of rows completed since the seed.
+//
+// # Safety
+//
+// `automaton` comes from [`ca_new`] and has not been freed.
+uint64_t ca_generation(const struct CaAutomaton *automaton);
+
+// Frees an automaton from [`ca_new`]. Null is fine and does nothing.
+//
+// # Safety
+//
+// `automaton` is null or comes from [`ca_new`] and has not been freed.
+void ca_free(struct CaAutomaton *automaton);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* CELLULAR_AUTOMATA_H */
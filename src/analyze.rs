@@ -0,0 +1,210 @@
+use crate::options;
+use crate::registry::{Mode, Registry};
+use crate::seed::{self, Seed};
+use crate::Error;
+use crate::{Automaton, SimContext, StepMode, GRID_X_SIZE, GRID_Y_SIZE, SEED_ROW};
+use clap::{CommandFactory, FromArgMatches, Parser};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+
+/// The arguments after `analyze`.
+#[derive(Parser)]
+#[command(
+    name = "cellular_automata analyze",
+    override_usage = "cellular_automata analyze [OPTIONS]",
+    about = "Runs an automaton without opening a window and prints statistics about
+what it computed: its population, the center column and whether it
+repeats.",
+    help_template = "{usage-heading} {usage}\n\n{about}\n\nOptions:\n{options}",
+    disable_version_flag = true,
+    args_override_self = true,
+    next_line_help = true
+)]
+struct Args {
+    /// The automaton, 'elementary', 'life' or a rule known by name
+    /// ('sierpinski', 'rule110', 'traffic'), whose rule --rule replaces
+    /// [default: elementary]
+    #[arg(verbatim_doc_comment, long, value_name = "MODE")]
+    mode: Option<String>,
+    /// The elementary rule, 0 to 255 [default: 30]
+    #[arg(long, value_name = "RULE", value_parser = options::parse_rule)]
+    rule: Option<u8>,
+    /// 'center' for a single live cell, or a number to draw a random first
+    /// row or grid from [default: center]
+    #[arg(verbatim_doc_comment, long, value_name = "SEED")]
+    seed: Option<Seed>,
+    /// How many generations to run [default: enough to fill the grid]
+    #[arg(long, value_name = "N")]
+    generations: Option<u64>,
+    /// Read defaults for these options from FILE instead of
+    /// rule30/config.toml in the platform's configuration directory
+    #[arg(verbatim_doc_comment, long, value_name = "FILE")]
+    config: Option<PathBuf>,
+}
+
+/// The command line `analyze` takes.
+pub fn command() -> clap::Command {
+    Args::command()
+}
+
+/// What the `analyze` command runs, from the arguments after `analyze`.
+pub struct Analyze {
+    pub automaton: Automaton,
+    pub rule: u8,
+    pub seed: Seed,
+    pub generations: u64,
+}
+
+impl Analyze {
+    /// `None` when the usage was asked for.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Option<Analyze>, Error> {
+        let Some(layered) = options::parse_layered(command(), args.into_iter().collect())? else {
+            return Ok(None);
+        };
+        let args =
+            Args::from_arg_matches(&layered.matches).map_err(|e| Error::Config(e.to_string()))?;
+        let mut registry = Registry::built_in();
+        for (name, rule) in layered.config.iter().flat_map(|(_, config)| &config.modes) {
+            registry.register(Mode::rule(name, *rule, "From the configuration file"))?;
+        }
+        let (mut automaton, mut rule) = (Automaton::Elementary, args.rule.unwrap_or(30));
+        // The rule of a mode gives way to a rule set in the same layer or
+        // above.
+        if let Some(name) = &args.mode {
+            let mode = registry.get(name)?;
+            automaton = mode.automaton;
+            if let (Some(mode_rule), true) =
+                (mode.rule, layered.source("rule") < layered.source("mode"))
+            {
+                rule = mode_rule;
+            }
+        }
+        Ok(Some(Analyze {
+            automaton,
+            rule,
+            seed: args.seed.unwrap_or(Seed::Center),
+            generations: args
+                .generations
+                .unwrap_or((GRID_Y_SIZE as usize - 1 - SEED_ROW) as u64),
+        }))
+    }
+
+    /// Runs the generations and describes them, one statistic a line.
+    pub fn run(&self) -> String {
+        let mut context = SimContext::seeded(self.rule, self.seed);
+        context.set_automaton(self.automaton);
+        context.step_mode = StepMode::Row;
+
+        let mut populations = Vec::new();
+        let mut center = Vec::new();
+        // When every state was first seen, by its hash, to find cycles.
+        let mut seen = HashMap::new();
+        let mut cycle = None;
+        for generation in 0..=self.generations {
+            if generation > 0 {
+                context.endless |= context.is_finished();
+                context.advance();
+            }
+            let mut hasher = DefaultHasher::new();
+            match self.automaton {
+                Automaton::Elementary => {
+                    let row = context.finished_row();
+                    populations.push(row.iter().filter(|&&alive| alive).count());
+                    center.push(center_cell(&row));
+                    row.hash(&mut hasher);
+                }
+                Automaton::Life => {
+                    populations.push(context.population());
+                    context.points.hash(&mut hasher);
+                }
+            }
+            if cycle.is_none() {
+                if let Some(first) = seen.insert(hasher.finish(), generation) {
+                    cycle = Some((first, generation - first));
+                }
+            }
+        }
+
+        let cells = match self.automaton {
+            Automaton::Elementary => GRID_X_SIZE,
+            Automaton::Life => GRID_X_SIZE * GRID_Y_SIZE,
+        };
+        let mean = populations.iter().sum::<usize>() as f64 / populations.len() as f64;
+        let mut lines = vec![
+            format!(
+                "{} from {}, {} generations of {} cells",
+                match self.automaton {
+                    Automaton::Elementary => format!("Rule {}", self.rule),
+                    Automaton::Life => "Game of Life".to_string(),
+                },
+                self.seed,
+                self.generations,
+                cells
+            ),
+            format!(
+                "Population     min {}, mean {:.1}, max {}",
+                populations.iter().min().unwrap_or(&0),
+                mean,
+                populations.iter().max().unwrap_or(&0)
+            ),
+            format!("Density        mean {:.4}", mean / cells as f64),
+        ];
+        if !center.is_empty() {
+            let alive = center.iter().filter(|&&alive| alive).count();
+            lines.push(format!(
+                "Center column  {} of {} alive ({:.4}), longest run {}",
+                alive,
+                center.len(),
+                alive as f64 / center.len() as f64,
+                longest_run(&center)
+            ));
+        }
+        lines.push(match cycle {
+            Some((first, 1)) => format!("Period         still from generation {}", first),
+            Some((first, period)) => format!(
+                "Period         {} generations, from generation {}",
+                period, first
+            ),
+            None => format!(
+                "Period         none within {} generations",
+                self.generations
+            ),
+        });
+        lines.join("\n")
+    }
+}
+
+/// The cell of `row` in the column the seed starts from.
+fn center_cell(row: &[bool]) -> bool {
+    row[seed::center(row.len())]
+}
+
+/// The most equal cells in a row.
+fn longest_run(cells: &[bool]) -> usize {
+    cells
+        .chunk_by(|a, b| a == b)
+        .map(<[bool]>::len)
+        .max()
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::center_column::center_column;
+
+    #[test]
+    fn center_matches_the_center_column() {
+        // The helix brings the edges to the middle after 50 rows.
+        let generations = 49;
+        let mut context = SimContext::new();
+        let center: Vec<bool> = context
+            .rows()
+            .take(generations + 1)
+            .map(|row| center_cell(&row))
+            .collect();
+        assert_eq!(center, center_column(30, generations));
+    }
+}
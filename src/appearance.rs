@@ -0,0 +1,50 @@
+//! Asks the operating system whether the user prefers dark or light
+//! applications. Every platform answers differently, so this shells out to
+//! whatever tool exposes the setting and gives up quietly when it fails.
+
+use std::env;
+use std::process::Command;
+
+/// Whether the system prefers dark applications, or `None` when that could
+/// not be determined.
+pub fn prefers_dark() -> Option<bool> {
+    if cfg!(target_os = "windows") {
+        // AppsUseLightTheme is 0x0 in dark mode and 0x1 in light mode.
+        let output = run(
+            "reg",
+            &[
+                "query",
+                r"HKCU\Software\Microsoft\Windows\CurrentVersion\Themes\Personalize",
+                "/v",
+                "AppsUseLightTheme",
+            ],
+        )?;
+        Some(output.contains("0x0"))
+    } else if cfg!(target_os = "macos") {
+        // Only set at all while dark mode is on, so failing means light.
+        Some(
+            run("defaults", &["read", "-g", "AppleInterfaceStyle"])
+                .is_some_and(|s| s.contains("Dark")),
+        )
+    } else {
+        let scheme = run(
+            "gsettings",
+            &["get", "org.gnome.desktop.interface", "color-scheme"],
+        );
+        match scheme {
+            Some(scheme) if scheme.contains("dark") => Some(true),
+            Some(scheme) if scheme.contains("light") => Some(false),
+            _ => env::var("GTK_THEME")
+                .ok()
+                .map(|theme| theme.to_lowercase().contains("dark")),
+        }
+    }
+}
+
+fn run(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8(output.stdout).ok()
+}
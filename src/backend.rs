@@ -0,0 +1,53 @@
+use crate::{Error, FRAMES_PER_SECOND};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+/// What shows the automata and takes the input.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BackendKind {
+    /// The window with every control, overlay and export.
+    Window,
+    /// The terminal, which only plays the automata.
+    Terminal,
+}
+
+impl FromStr for BackendKind {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "window" => Ok(BackendKind::Window),
+            "terminal" => Ok(BackendKind::Terminal),
+            _ => Err(Error::Parse(format!(
+                "unknown backend '{}', expected window or terminal",
+                s
+            ))),
+        }
+    }
+}
+
+/// A front-end for [`run`] to show the automata in, which owns them.
+pub trait Backend {
+    /// Takes the input given since the last frame, `false` once it asks
+    /// to quit.
+    fn handle_input(&mut self, frame: u64) -> Result<bool, Error>;
+    /// Steps the automata for `frame` and shows them, `false` once there
+    /// is nothing left to show.
+    fn update(&mut self, frame: u64) -> Result<bool, Error>;
+}
+
+/// Runs `backend` a frame at a time until it quits.
+pub fn run(mut backend: impl Backend) -> Result<(), Error> {
+    let frame_duration = Duration::new(0, 1_000_000_000u32 / FRAMES_PER_SECOND);
+    for frame in 0.. {
+        let frame_start = Instant::now();
+        if !backend.handle_input(frame)? || !backend.update(frame)? {
+            break;
+        }
+
+        if let Some(remaining) = frame_duration.checked_sub(frame_start.elapsed()) {
+            std::thread::sleep(remaining);
+        }
+    }
+    Ok(())
+}
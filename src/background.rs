@@ -0,0 +1,44 @@
+use crate::image::RgbaImage;
+use crate::Error;
+use std::path::Path;
+use std::str::FromStr;
+
+/// How a background image shows up with the cells.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BackgroundMode {
+    /// Behind the dead cells, with live cells drawn on top.
+    Behind,
+    /// Only through the live cells, as if they were holes in a mask.
+    Mask,
+}
+
+impl FromStr for BackgroundMode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "behind" => Ok(BackgroundMode::Behind),
+            "mask" => Ok(BackgroundMode::Mask),
+            _ => Err(Error::Parse(format!(
+                "unknown background mode '{}', expected behind or mask",
+                s
+            ))),
+        }
+    }
+}
+
+/// An image drawn together with the grid, stretched over all of it.
+#[derive(Clone)]
+pub struct Background {
+    pub image: RgbaImage,
+    pub mode: BackgroundMode,
+}
+
+impl Background {
+    /// Loads a PNG and scales it to `size`, so that uploading it every
+    /// frame stays cheap however large the file is.
+    pub fn load(path: &Path, mode: BackgroundMode, size: (u32, u32)) -> Result<Background, Error> {
+        let image = RgbaImage::load_png(path)?.resized(size.0, size.1);
+        Ok(Background { image, mode })
+    }
+}
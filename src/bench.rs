@@ -0,0 +1,132 @@
+use crate::options;
+use crate::registry::{Mode, Registry};
+use crate::seed::Seed;
+use crate::Error;
+use crate::{Automaton, SimContext, StepMode, GRID_X_SIZE, GRID_Y_SIZE};
+use clap::{value_parser, CommandFactory, FromArgMatches, Parser};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+const DEFAULT_GENERATIONS: u64 = 10_000;
+const DEFAULT_RUNS: u32 = 5;
+
+/// The arguments after `bench`.
+#[derive(Parser)]
+#[command(
+    name = "cellular_automata bench",
+    override_usage = "cellular_automata bench [OPTIONS]",
+    about = "Measures how fast generations are computed, without opening a window.",
+    help_template = "{usage-heading} {usage}\n\n{about}\n\nOptions:\n{options}",
+    disable_version_flag = true,
+    args_override_self = true,
+    next_line_help = true
+)]
+struct Args {
+    /// The automaton, 'elementary', 'life' or a rule known by name
+    /// ('sierpinski', 'rule110', 'traffic'), whose rule --rule replaces
+    /// [default: elementary]
+    #[arg(verbatim_doc_comment, long, value_name = "MODE")]
+    mode: Option<String>,
+    /// The elementary rule, 0 to 255 [default: 30]
+    #[arg(long, value_name = "RULE", value_parser = options::parse_rule)]
+    rule: Option<u8>,
+    /// 'center' for a single live cell, or a number to draw a random first
+    /// row or grid from [default: center]
+    #[arg(verbatim_doc_comment, long, value_name = "SEED")]
+    seed: Option<Seed>,
+    /// How many generations every run computes [default: 10000]
+    #[arg(long, value_name = "N", value_parser = value_parser!(u64).range(1..))]
+    generations: Option<u64>,
+    /// How many times to run, the fastest counts [default: 5]
+    #[arg(long, value_name = "N", value_parser = value_parser!(u32).range(1..))]
+    runs: Option<u32>,
+    /// Read defaults for these options from FILE instead of
+    /// rule30/config.toml in the platform's configuration directory
+    #[arg(verbatim_doc_comment, long, value_name = "FILE")]
+    config: Option<PathBuf>,
+}
+
+/// The command line `bench` takes.
+pub fn command() -> clap::Command {
+    Args::command()
+}
+
+/// What the `bench` command measures, from the arguments after `bench`.
+pub struct Bench {
+    pub automaton: Automaton,
+    pub rule: u8,
+    pub seed: Seed,
+    pub generations: u64,
+    pub runs: u32,
+}
+
+impl Bench {
+    /// `None` when the usage was asked for.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Option<Bench>, Error> {
+        let Some(layered) = options::parse_layered(command(), args.into_iter().collect())? else {
+            return Ok(None);
+        };
+        let args =
+            Args::from_arg_matches(&layered.matches).map_err(|e| Error::Config(e.to_string()))?;
+        let mut registry = Registry::built_in();
+        for (name, rule) in layered.config.iter().flat_map(|(_, config)| &config.modes) {
+            registry.register(Mode::rule(name, *rule, "From the configuration file"))?;
+        }
+        let (mut automaton, mut rule) = (Automaton::Elementary, args.rule.unwrap_or(30));
+        // The rule of a mode gives way to a rule set in the same layer or
+        // above.
+        if let Some(name) = &args.mode {
+            let mode = registry.get(name)?;
+            automaton = mode.automaton;
+            if let (Some(mode_rule), true) =
+                (mode.rule, layered.source("rule") < layered.source("mode"))
+            {
+                rule = mode_rule;
+            }
+        }
+        Ok(Some(Bench {
+            automaton,
+            rule,
+            seed: args.seed.unwrap_or(Seed::Center),
+            generations: args.generations.unwrap_or(DEFAULT_GENERATIONS),
+            runs: args.runs.unwrap_or(DEFAULT_RUNS),
+        }))
+    }
+
+    /// Computes the generations `runs` times, printing how long every run
+    /// took and then the rates of the fastest.
+    pub fn run(&self) {
+        let mut fastest = Duration::MAX;
+        for run in 1..=self.runs {
+            let elapsed = self.time();
+            println!("Run {}: {:.1} ms", run, elapsed.as_secs_f64() * 1000.0);
+            fastest = fastest.min(elapsed);
+        }
+        let cells = match self.automaton {
+            Automaton::Elementary => GRID_X_SIZE,
+            Automaton::Life => GRID_X_SIZE * GRID_Y_SIZE,
+        } as f64;
+        let per_second = self.generations as f64 / fastest.as_secs_f64();
+        println!(
+            "{} generations in {:.1} ms, {:.0} generations and {:.0} cells per second",
+            self.generations,
+            fastest.as_secs_f64() * 1000.0,
+            per_second,
+            per_second * cells
+        );
+    }
+
+    fn time(&self) -> Duration {
+        let mut context = SimContext::seeded(self.rule, self.seed);
+        context.set_automaton(self.automaton);
+        context.step_mode = StepMode::Row;
+        let start = Instant::now();
+        for _ in 0..self.generations {
+            context.endless |= context.is_finished();
+            context.advance();
+        }
+        let elapsed = start.elapsed();
+        std::hint::black_box(context.generation());
+        elapsed
+    }
+}
@@ -0,0 +1,76 @@
+use crate::Error;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+/// A generation marked as worth coming back to.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Bookmark {
+    pub generation: u64,
+    /// What is interesting about the generation, `Generation N` until
+    /// somebody edits the file.
+    pub label: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BookmarkFile {
+    #[serde(default)]
+    bookmarks: Vec<Bookmark>,
+}
+
+/// The file the bookmarks are kept in next to a state file, `state.toml`
+/// keeps them in `state.bookmarks.toml`.
+pub fn sidecar(state_file: &Path) -> PathBuf {
+    let stem = state_file.file_stem().unwrap_or_default().to_string_lossy();
+    state_file.with_file_name(format!("{}.bookmarks.toml", stem))
+}
+
+/// Saves the bookmarks next to `state_file`, removing the file that held
+/// them when there are none left.
+pub fn save(state_file: &Path, bookmarks: &[Bookmark]) -> Result<(), Error> {
+    let path = sidecar(state_file);
+    if bookmarks.is_empty() {
+        return match fs::remove_file(&path) {
+            Err(e) if e.kind() != ErrorKind::NotFound => Err(Error::io("remove", path, e)),
+            _ => Ok(()),
+        };
+    }
+    let file = BookmarkFile {
+        bookmarks: bookmarks.to_vec(),
+    };
+    let contents = toml::to_string(&file).map_err(|e| Error::Serialize(e.to_string()))?;
+    fs::write(&path, contents).map_err(|e| Error::io("write", path, e))
+}
+
+/// The bookmarks saved next to `state_file`, none if there is no such
+/// file.
+pub fn load(state_file: &Path) -> Result<Vec<Bookmark>, Error> {
+    let path = sidecar(state_file);
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(Error::io("read", path, e)),
+    };
+    let file: BookmarkFile = toml::from_str(&contents)
+        .map_err(|e| Error::Parse(format!("invalid bookmarks '{}': {}", path.display(), e)))?;
+    let mut bookmarks = file.bookmarks;
+    bookmarks.sort_by_key(|bookmark| bookmark.generation);
+    Ok(bookmarks)
+}
+
+/// Bookmarks `generation`, or removes its bookmark if it has one.
+pub fn toggle(bookmarks: &mut Vec<Bookmark>, generation: u64) {
+    match bookmarks.binary_search_by_key(&generation, |bookmark| bookmark.generation) {
+        Ok(index) => {
+            bookmarks.remove(index);
+        }
+        Err(index) => bookmarks.insert(
+            index,
+            Bookmark {
+                generation,
+                label: format!("Generation {}", generation),
+            },
+        ),
+    }
+}
@@ -0,0 +1,104 @@
+use crate::Point;
+use sdl2::rect::Rect;
+
+/// The cell sizes, in pixels, that zooming steps through.
+const ZOOM_LEVELS: [f32; 20] = [
+    0.5, 1.0, 1.5, 2.0, 2.5, 3.0, 4.0, 5.0, 6.0, 8.0, 10.0, 12.0, 16.0, 20.0, 24.0, 32.0, 40.0,
+    48.0, 56.0, 64.0,
+];
+
+/// Maps grid coordinates to window pixels. The cell size does not have to
+/// be a whole number of pixels, cells then vary by a pixel in size so
+/// that the grid as a whole still has the fractional size.
+pub struct Camera {
+    pub cell_size: f32,
+    /// Where the top left corner of the grid ends up in the window.
+    pub offset: (i32, i32),
+}
+
+impl Camera {
+    pub fn new(cell_size: f32) -> Camera {
+        Camera {
+            cell_size,
+            offset: (0, 0),
+        }
+    }
+
+    pub fn cell_rect(&self, point: &Point) -> Rect {
+        let &Point(x, y) = point;
+        let (left, top) = (self.edge(x), self.edge(y));
+        Rect::new(
+            left + self.offset.0,
+            top + self.offset.1,
+            (self.edge(x + 1) - left).max(1) as u32,
+            (self.edge(y + 1) - top).max(1) as u32,
+        )
+    }
+
+    /// How many pixels the given number of cells span, rounded to the
+    /// nearest pixel.
+    pub fn edge(&self, cells: i32) -> i32 {
+        (cells as f32 * self.cell_size).round() as i32
+    }
+
+    /// The cell under the given window position, which may lie outside
+    /// the grid.
+    pub fn cell_at(&self, (x, y): (i32, i32)) -> Point {
+        let cell =
+            |pixel: i32, offset: i32| ((pixel - offset) as f32 / self.cell_size).floor() as i32;
+        Point(cell(x, self.offset.0), cell(y, self.offset.1))
+    }
+
+    /// Moves the view by the given number of pixels.
+    pub fn pan(&mut self, dx: i32, dy: i32) {
+        self.offset = (self.offset.0 + dx, self.offset.1 + dy);
+    }
+
+    /// Moves the view so that the middle of the cell at `(x, y)` ends up
+    /// on `anchor`.
+    pub fn center_on(&mut self, (x, y): (i32, i32), anchor: (i32, i32)) {
+        let middle = |cell: i32| ((cell as f32 + 0.5) * self.cell_size).round() as i32;
+        self.offset = (anchor.0 - middle(x), anchor.1 - middle(y));
+    }
+
+    /// Picks the cell size at which `cells` just fit into `area`, within
+    /// the zoom levels, and centers them there.
+    pub fn fit(&mut self, cells: (u32, u32), area: (u32, u32)) {
+        let size = |cells: u32, area: u32| area as f32 / cells.max(1) as f32;
+        self.cell_size = size(cells.0, area.0)
+            .min(size(cells.1, area.1))
+            .clamp(ZOOM_LEVELS[0], ZOOM_LEVELS[ZOOM_LEVELS.len() - 1]);
+        let center = |cells: u32, area: u32| (area as i32 - self.edge(cells as i32)) / 2;
+        self.offset = (center(cells.0, area.0), center(cells.1, area.1));
+    }
+
+    /// Zooms in one level, keeping whatever is under `anchor` in place.
+    pub fn zoom_in(&mut self, anchor: (i32, i32)) {
+        if let Some(&size) = ZOOM_LEVELS.iter().find(|&&size| size > self.cell_size) {
+            self.set_cell_size(size, anchor);
+        }
+    }
+
+    pub fn zoom_out(&mut self, anchor: (i32, i32)) {
+        if let Some(&size) = ZOOM_LEVELS
+            .iter()
+            .rev()
+            .find(|&&size| size < self.cell_size)
+        {
+            self.set_cell_size(size, anchor);
+        }
+    }
+
+    /// Changes the cell size while the grid position under `anchor` stays
+    /// on the same pixel.
+    fn set_cell_size(&mut self, cell_size: f32, anchor: (i32, i32)) {
+        let scale = cell_size as f64 / self.cell_size as f64;
+        let rescale =
+            |offset: i32, anchor: i32| anchor - ((anchor - offset) as f64 * scale).round() as i32;
+        self.offset = (
+            rescale(self.offset.0, anchor.0),
+            rescale(self.offset.1, anchor.1),
+        );
+        self.cell_size = cell_size;
+    }
+}
@@ -0,0 +1,121 @@
+//! The center column of an elementary automaton grown from a single cell,
+//! which for Rule 30 is the classic pseudo-random sequence.
+
+use crate::Error;
+use std::str::FromStr;
+
+/// How the bits of a column are written out.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ColumnFormat {
+    /// A `0` or `1` for every generation.
+    Bits,
+    /// Four generations to a hex digit, the first in the highest bit.
+    Hex,
+}
+
+impl FromStr for ColumnFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bits" => Ok(ColumnFormat::Bits),
+            "hex" => Ok(ColumnFormat::Hex),
+            _ => Err(Error::Parse(format!(
+                "unknown column format '{}', expected bits or hex",
+                s
+            ))),
+        }
+    }
+}
+
+/// The cell below the seed in every generation from 0 to `generations`,
+/// of `rule` grown from a single live cell on an endless row. The grid
+/// cannot be used for this, its edges reach the middle after 50 rows.
+///
+/// Only the cells the last center cell depends on are computed, 64 at a
+/// time, which shrinks the row by a cell on either side every generation.
+pub fn center_column(rule: u8, generations: usize) -> Vec<bool> {
+    let width = 2 * generations + 1;
+    let center = generations;
+    // A dead word on either side stands in for the rest of the endless
+    // row, as far as it is needed.
+    let words = width.div_ceil(64) + 2;
+    let mut row = vec![0u64; words];
+    let mut next = vec![0u64; words];
+    let cell = |row: &[u64], x: usize| row[1 + x / 64] >> (x % 64) & 1 == 1;
+    row[1 + center / 64] |= 1 << (center % 64);
+
+    let mut column = Vec::with_capacity(generations + 1);
+    column.push(true);
+    for generation in 1..=generations {
+        // The cells the last center cell still depends on, and of those
+        // only the ones that can be alive yet unless dead neighborhoods
+        // come to life.
+        let mut reach = generations - generation;
+        if rule & 1 == 0 {
+            reach = reach.min(generation);
+        }
+        let (left, right) = (center - reach, center + reach);
+        for word in 1 + left / 64..=1 + right / 64 {
+            let mid = row[word];
+            let west = mid << 1 | row[word - 1] >> 63;
+            let east = mid >> 1 | row[word + 1] << 63;
+            next[word] = (0..8)
+                .filter(|neighborhood| rule >> neighborhood & 1 == 1)
+                .map(|neighborhood| {
+                    let pick = |bit: u8, cells: u64| match neighborhood >> bit & 1 {
+                        1 => cells,
+                        _ => !cells,
+                    };
+                    pick(2, west) & pick(1, mid) & pick(0, east)
+                })
+                .fold(0, |cells, matching| cells | matching);
+        }
+        std::mem::swap(&mut row, &mut next);
+        column.push(cell(&row, center));
+    }
+    column
+}
+
+/// Writes `bits` out in `format`, on a single line. Hex is padded with
+/// dead cells to a whole digit.
+pub fn format_column(bits: &[bool], format: ColumnFormat) -> String {
+    let mut text: String = match format {
+        ColumnFormat::Bits => bits
+            .iter()
+            .map(|&alive| if alive { '1' } else { '0' })
+            .collect(),
+        ColumnFormat::Hex => bits
+            .chunks(4)
+            .map(|nibble| {
+                let value = nibble
+                    .iter()
+                    .enumerate()
+                    .fold(0, |value, (i, &alive)| value | (alive as u32) << (3 - i));
+                char::from_digit(value, 16).unwrap()
+            })
+            .collect(),
+    };
+    text.push('\n');
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The first 64 generations of Rule 30's center column, the first in
+    /// the highest bit, as OEIS A051023 lists them.
+    const RULE_30: u64 = 0xdcc5_93ae_7561_95ab;
+
+    #[test]
+    fn rule_30_starts_with_the_known_bits() {
+        let column = center_column(30, 63);
+        let expected: Vec<bool> = (0..64).map(|i| RULE_30 >> (63 - i) & 1 == 1).collect();
+        assert_eq!(column, expected);
+        assert_eq!(
+            format_column(&column, ColumnFormat::Hex),
+            "dcc593ae756195ab\n"
+        );
+    }
+}
@@ -0,0 +1,33 @@
+//! The colors of themes, palettes and exports, which the window hands to
+//! SDL and the terminal to crossterm.
+
+/// A color with an alpha channel, 255 being opaque.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+#[allow(non_snake_case)]
+impl Color {
+    pub const WHITE: Color = Color::RGB(255, 255, 255);
+    pub const YELLOW: Color = Color::RGB(255, 255, 0);
+
+    /// An opaque color.
+    pub const fn RGB(r: u8, g: u8, b: u8) -> Color {
+        Color { r, g, b, a: 255 }
+    }
+
+    pub const fn RGBA(r: u8, g: u8, b: u8, a: u8) -> Color {
+        Color { r, g, b, a }
+    }
+}
+
+#[cfg(feature = "window")]
+impl From<Color> for sdl2::pixels::Color {
+    fn from(color: Color) -> sdl2::pixels::Color {
+        sdl2::pixels::Color::RGBA(color.r, color.g, color.b, color.a)
+    }
+}
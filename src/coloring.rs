@@ -0,0 +1,153 @@
+use crate::color::Color;
+use crate::Error;
+use std::str::FromStr;
+
+/// Generations after which a row has faded as far as it goes.
+const AGE_FADE_GENERATIONS: f32 = 50.0;
+/// How much of the live cell color the oldest rows keep.
+const AGE_MIN_BRIGHTNESS: f32 = 0.2;
+
+/// How live cells pick their color.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CellColoring {
+    /// Every live cell gets the theme's live cell color.
+    Flat,
+    /// Recently written rows are bright and older ones fade towards the
+    /// dead cell color.
+    Age,
+    /// Every row gets a color along the palette, from the top of the grid
+    /// to the bottom.
+    Gradient,
+    /// Every computed cell gets the color of the neighborhood that
+    /// produced it, dead ones dimmed.
+    Neighborhood,
+    /// For 2D automata, every cell is colored along the palette by how
+    /// often it has been alive.
+    Heatmap,
+}
+
+impl CellColoring {
+    pub fn next(self) -> CellColoring {
+        match self {
+            CellColoring::Flat => CellColoring::Age,
+            CellColoring::Age => CellColoring::Gradient,
+            CellColoring::Gradient => CellColoring::Neighborhood,
+            CellColoring::Neighborhood => CellColoring::Heatmap,
+            CellColoring::Heatmap => CellColoring::Flat,
+        }
+    }
+}
+
+/// A gradient for the row based coloring.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Palette {
+    Viridis,
+    /// Viridis adjusted to look the same with and without deuteranopia.
+    Cividis,
+    Magma,
+    Rainbow,
+    Grayscale,
+}
+
+impl Palette {
+    fn stops(&self) -> &'static [(u8, u8, u8)] {
+        match self {
+            Palette::Viridis => &[
+                (68, 1, 84),
+                (59, 82, 139),
+                (33, 145, 140),
+                (94, 201, 98),
+                (253, 231, 37),
+            ],
+            Palette::Cividis => &[
+                (0, 34, 78),
+                (64, 76, 107),
+                (124, 123, 120),
+                (188, 175, 111),
+                (254, 232, 56),
+            ],
+            Palette::Magma => &[
+                (0, 0, 4),
+                (81, 18, 124),
+                (183, 55, 121),
+                (252, 137, 97),
+                (252, 253, 191),
+            ],
+            Palette::Rainbow => &[
+                (110, 64, 170),
+                (40, 110, 250),
+                (30, 200, 150),
+                (175, 240, 90),
+                (255, 190, 40),
+                (240, 60, 60),
+            ],
+            Palette::Grayscale => &[(60, 60, 60), (255, 255, 255)],
+        }
+    }
+
+    /// The color at `t`, from 0.0 for the start of the gradient to 1.0.
+    pub fn sample(&self, t: f32) -> Color {
+        let stops = self.stops();
+        let position = t.clamp(0.0, 1.0) * (stops.len() - 1) as f32;
+        let index = (position as usize).min(stops.len() - 2);
+        let color = |(r, g, b): (u8, u8, u8)| Color::RGB(r, g, b);
+        lerp(
+            color(stops[index]),
+            color(stops[index + 1]),
+            position - index as f32,
+        )
+    }
+}
+
+impl FromStr for Palette {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "viridis" => Ok(Palette::Viridis),
+            "cividis" => Ok(Palette::Cividis),
+            "magma" => Ok(Palette::Magma),
+            "rainbow" => Ok(Palette::Rainbow),
+            "grayscale" => Ok(Palette::Grayscale),
+            _ => Err(Error::Parse(format!(
+                "unknown palette '{}', expected viridis, cividis, magma, rainbow or grayscale",
+                s
+            ))),
+        }
+    }
+}
+
+/// One color per neighborhood, from `000` to `111`. Apart from the gray
+/// these are the Okabe-Ito colors, which stay distinguishable with the
+/// common forms of color blindness.
+const CASE_COLORS: [(u8, u8, u8); 8] = [
+    (120, 120, 120),
+    (230, 159, 0),
+    (86, 180, 233),
+    (0, 158, 115),
+    (240, 228, 66),
+    (0, 114, 178),
+    (213, 94, 0),
+    (204, 121, 167),
+];
+
+pub fn case_color(case: u8) -> Color {
+    let (r, g, b) = CASE_COLORS[case as usize & 7];
+    Color::RGB(r, g, b)
+}
+
+pub fn age_color(live: Color, dead: Color, age: u64) -> Color {
+    let faded = (age as f32 / AGE_FADE_GENERATIONS).min(1.0);
+    lerp(dead, live, 1.0 - faded * (1.0 - AGE_MIN_BRIGHTNESS))
+}
+
+/// Mixes `from` and `to`, where `t` runs from 0.0 (only `from`) to 1.0.
+pub fn lerp(from: Color, to: Color, t: f32) -> Color {
+    let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+    Color::RGBA(
+        mix(from.r, to.r),
+        mix(from.g, to.g),
+        mix(from.b, to.b),
+        mix(from.a, to.a),
+    )
+}
@@ -0,0 +1,547 @@
+use crate::tick_rate::TickRate;
+use crate::Error;
+use cellular_automata::{SimContext, GRID_X_SIZE};
+use std::collections::HashMap;
+
+const PAN_STEP_IN_CELLS: i32 = 10;
+
+/// Everything the user can ask the application to do. The event loop
+/// translates raw key presses into commands so that they can be recorded,
+/// replayed and applied without caring where they came from.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Command {
+    TogglePause,
+    Step,
+    StepBack,
+    ToggleDirection,
+    ToggleStepMode,
+    SpeedUp,
+    SpeedDown,
+    ScannerFaster,
+    ScannerSlower,
+    ZoomIn,
+    ZoomOut,
+    ZoomToFit,
+    /// Shifts the picture by the given number of cells.
+    Pan(i32, i32),
+    ToggleFullscreen,
+    ToggleBorderless,
+    ToggleAlwaysOnTop,
+    MoreOpaque,
+    LessOpaque,
+    NextTheme,
+    InvertColors,
+    ToggleDarkLight,
+    NextCellColoring,
+    NextView,
+    NextAutomaton,
+    ToggleEndless,
+    ToggleGridLines,
+    ToggleScanner,
+    ToggleHud,
+    ToggleRates,
+    ToggleHelp,
+    ToggleInspector,
+    ToggleCrt,
+    ToggleTriangles,
+    ToggleScaleFilter,
+    ToggleLibrary,
+    /// Moves the pick in the library by the given number of patterns.
+    PickPattern(i32),
+    InsertPattern,
+    NextRule,
+    PreviousRule,
+    SetRule(u8),
+    SetTickRate(TickRate),
+    NextPreset,
+    Screenshot,
+    ExportDiagram,
+    CopyDiagram,
+    ToggleBookmark,
+    SaveCenterColumn,
+    ToggleVideo,
+    SaveState,
+    LoadState,
+    ToggleRecording,
+    PlayMacro,
+    NewWindow,
+    ReloadConfig,
+    Quit,
+}
+
+impl Command {
+    /// Applies the command to `context` if it is about the automaton, the
+    /// rest is up to the window.
+    pub fn apply_to(self, context: &mut SimContext) {
+        match self {
+            Command::TogglePause => context.toggle_pause(),
+            Command::Step => context.advance(),
+            Command::StepBack => context.rewind(),
+            Command::ToggleStepMode => context.toggle_step_mode(),
+            Command::ScannerFaster => {
+                context.cells_per_tick = (context.cells_per_tick * 2).min(GRID_X_SIZE)
+            }
+            Command::SpeedUp => context.tick_rate = context.tick_rate.faster(),
+            Command::SpeedDown => context.tick_rate = context.tick_rate.slower(),
+            Command::ScannerSlower => context.cells_per_tick = (context.cells_per_tick / 2).max(1),
+            Command::NextRule => context.rule = context.rule.wrapping_add(1),
+            Command::PreviousRule => context.rule = context.rule.wrapping_sub(1),
+            Command::SetRule(rule) => context.rule = rule,
+            Command::SetTickRate(tick_rate) => context.tick_rate = tick_rate,
+            Command::NextAutomaton => context.set_automaton(context.automaton.next()),
+            Command::ToggleEndless => context.endless = !context.endless,
+            _ => {}
+        }
+    }
+    /// A short description of what the command does, for the help.
+    pub fn description(&self) -> &'static str {
+        match self {
+            Command::TogglePause => "Pause / resume",
+            Command::Step => "Advance a single step",
+            Command::StepBack => "Undo a single step",
+            Command::ToggleDirection => "Play forwards / backwards",
+            Command::ToggleStepMode => "Cell by cell / row per tick",
+            Command::SpeedUp => "Speed up",
+            Command::SpeedDown => "Slow down",
+            Command::ScannerFaster => "More cells per tick",
+            Command::ScannerSlower => "Fewer cells per tick",
+            Command::ZoomIn => "Zoom in",
+            Command::ZoomOut => "Zoom out",
+            Command::ZoomToFit => "Fit the computed rows",
+            Command::Pan(dx, _) if *dx > 0 => "Pan left",
+            Command::Pan(dx, _) if *dx < 0 => "Pan right",
+            Command::Pan(_, dy) if *dy > 0 => "Pan up",
+            Command::Pan(..) => "Pan down",
+            Command::ToggleFullscreen => "Toggle fullscreen",
+            Command::ToggleBorderless => "Toggle the window border",
+            Command::ToggleAlwaysOnTop => "Toggle staying on top",
+            Command::MoreOpaque => "Make the window more opaque",
+            Command::LessOpaque => "Make the window more transparent",
+            Command::NextTheme => "Next theme",
+            Command::InvertColors => "Invert the colors",
+            Command::ToggleDarkLight => "Dark / light theme",
+            Command::NextCellColoring => "Next cell coloring",
+            Command::NextView => "Next view",
+            Command::NextAutomaton => "Elementary / Game of Life",
+            Command::ToggleEndless => "Stop at the last row / scroll on",
+            Command::ToggleGridLines => "Toggle grid lines",
+            Command::ToggleScanner => "Hide / show the scanner",
+            Command::ToggleHud => "Hide / show the overlay",
+            Command::ToggleRates => "Hide / show FPS and TPS",
+            Command::ToggleHelp => "Show / page through this help",
+            Command::ToggleInspector => "Inspect the cell under the mouse",
+            Command::ToggleCrt => "Toggle the CRT effect",
+            Command::ToggleTriangles => "Highlight the triangles",
+            Command::ToggleScaleFilter => "Crisp / smooth scaling",
+            Command::ToggleLibrary => "Hide / show the pattern library",
+            Command::PickPattern(by) if *by < 0 => "Previous pattern in the library",
+            Command::PickPattern(..) => "Next pattern in the library",
+            Command::InsertPattern => "Insert the picked pattern",
+            Command::NextRule => "Next rule",
+            Command::PreviousRule => "Previous rule",
+            Command::SetRule(..) => "Switch to a rule",
+            Command::SetTickRate(..) => "Run at a tick rate",
+            Command::NextPreset => "Next preset",
+            Command::Screenshot => "Save a screenshot",
+            Command::ExportDiagram => "Save every row as a picture",
+            Command::CopyDiagram => "Copy every row as text",
+            Command::SaveCenterColumn => "Save the center column",
+            Command::ToggleBookmark => "Bookmark the generation",
+            Command::ToggleVideo => "Start / stop recording a video",
+            Command::SaveState => "Save the automata",
+            Command::LoadState => "Load the saved automata",
+            Command::ToggleRecording => "Start / stop recording a macro",
+            Command::PlayMacro => "Play the macro",
+            Command::NewWindow => "Open another window",
+            Command::ReloadConfig => "Reload the configuration",
+            Command::Quit => "Quit",
+        }
+    }
+
+    /// Whether the command may end up inside a recording. Controlling the
+    /// recorder itself, opening windows or quitting from a playback makes
+    /// no sense.
+    pub fn is_recordable(&self) -> bool {
+        !matches!(
+            self,
+            Command::ToggleRecording | Command::PlayMacro | Command::NewWindow | Command::Quit
+        )
+    }
+}
+
+/// The actions keys can be bound to, by the names the config file knows
+/// them by, with their default keys in the order the help lists them.
+const ACTIONS: &[(&str, Command, &[&str])] = &[
+    ("help", Command::ToggleHelp, &["F1"]),
+    ("hud", Command::ToggleHud, &["F2"]),
+    ("rates", Command::ToggleRates, &["F3"]),
+    ("inspector", Command::ToggleInspector, &["F4"]),
+    ("crt", Command::ToggleCrt, &["F5"]),
+    ("triangles", Command::ToggleTriangles, &["F6"]),
+    ("scale-filter", Command::ToggleScaleFilter, &["F7"]),
+    ("save-state", Command::SaveState, &["F8"]),
+    ("load-state", Command::LoadState, &["F9"]),
+    ("library", Command::ToggleLibrary, &["F10"]),
+    ("previous-pattern", Command::PickPattern(-1), &["Up"]),
+    ("next-pattern", Command::PickPattern(1), &["Down"]),
+    (
+        "insert-pattern",
+        Command::InsertPattern,
+        &["Return", "Keypad Enter"],
+    ),
+    ("fullscreen", Command::ToggleFullscreen, &["F11"]),
+    ("borderless", Command::ToggleBorderless, &["B"]),
+    ("always-on-top", Command::ToggleAlwaysOnTop, &["A"]),
+    ("more-opaque", Command::MoreOpaque, &["Ctrl+]"]),
+    ("less-opaque", Command::LessOpaque, &["Ctrl+["]),
+    ("pause", Command::TogglePause, &["Space"]),
+    ("quit", Command::Quit, &["Q"]),
+    ("step", Command::Step, &["Right"]),
+    ("step-back", Command::StepBack, &["Left"]),
+    ("direction", Command::ToggleDirection, &["Backspace"]),
+    ("step-mode", Command::ToggleStepMode, &["Tab"]),
+    ("speed-up", Command::SpeedUp, &["+", "=", "Keypad +"]),
+    ("slow-down", Command::SpeedDown, &["-", "Keypad -"]),
+    (
+        "zoom-in",
+        Command::ZoomIn,
+        &["Ctrl++", "Ctrl+=", "Ctrl+Keypad +"],
+    ),
+    ("zoom-out", Command::ZoomOut, &["Ctrl+-", "Ctrl+Keypad -"]),
+    ("zoom-to-fit", Command::ZoomToFit, &["Z"]),
+    (
+        "pan-left",
+        Command::Pan(PAN_STEP_IN_CELLS, 0),
+        &["Ctrl+Left"],
+    ),
+    (
+        "pan-right",
+        Command::Pan(-PAN_STEP_IN_CELLS, 0),
+        &["Ctrl+Right"],
+    ),
+    ("pan-up", Command::Pan(0, PAN_STEP_IN_CELLS), &["Ctrl+Up"]),
+    (
+        "pan-down",
+        Command::Pan(0, -PAN_STEP_IN_CELLS),
+        &["Ctrl+Down"],
+    ),
+    ("scanner-faster", Command::ScannerFaster, &["."]),
+    ("scanner-slower", Command::ScannerSlower, &[","]),
+    ("next-rule", Command::NextRule, &["]"]),
+    ("previous-rule", Command::PreviousRule, &["["]),
+    ("next-preset", Command::NextPreset, &["Ctrl+P"]),
+    ("theme", Command::NextTheme, &["T"]),
+    ("dark-light", Command::ToggleDarkLight, &["D"]),
+    ("invert", Command::InvertColors, &["I"]),
+    ("coloring", Command::NextCellColoring, &["C"]),
+    ("view", Command::NextView, &["V"]),
+    ("grid-lines", Command::ToggleGridLines, &["G"]),
+    ("scanner", Command::ToggleScanner, &["H"]),
+    ("automaton", Command::NextAutomaton, &["L"]),
+    ("endless", Command::ToggleEndless, &["E"]),
+    ("screenshot", Command::Screenshot, &["S"]),
+    ("export-diagram", Command::ExportDiagram, &["Ctrl+S"]),
+    ("copy-diagram", Command::CopyDiagram, &["Ctrl+C"]),
+    ("center-column", Command::SaveCenterColumn, &["K"]),
+    ("bookmark", Command::ToggleBookmark, &["O"]),
+    ("video", Command::ToggleVideo, &["R"]),
+    ("macro", Command::ToggleRecording, &["M"]),
+    ("play-macro", Command::PlayMacro, &["P"]),
+    ("new-window", Command::NewWindow, &["N"]),
+    ("reload-config", Command::ReloadConfig, &["Ctrl+R"]),
+];
+
+/// A key by the name SDL gives it, so that every front-end names keys
+/// the way the config file does.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Key {
+    /// A letter, in upper case, a digit or punctuation.
+    Char(char),
+    /// One of `KEY_NAMES`.
+    Named(&'static str),
+}
+
+impl Key {
+    /// The key called `name`, ignoring case, if it can be bound.
+    pub fn from_name(name: &str) -> Option<Key> {
+        let mut chars = name.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => {
+                let c = c.to_ascii_uppercase();
+                matches!(c, '!'..='@' | 'A'..='Z' | '['..='`').then_some(Key::Char(c))
+            }
+            _ => KEY_NAMES
+                .iter()
+                .find(|key| key.eq_ignore_ascii_case(name))
+                .map(|&key| Key::Named(key)),
+        }
+    }
+
+    pub fn name(self) -> String {
+        match self {
+            Key::Char(c) => c.to_string(),
+            Key::Named(name) => name.to_string(),
+        }
+    }
+}
+
+/// A key, and whether Ctrl has to be held with it.
+type Binding = (Key, bool);
+
+/// Which command every key gives, the defaults with whatever the config
+/// file remapped. The event loop looks keys up here.
+#[derive(Clone, PartialEq)]
+pub struct KeyMap {
+    commands: HashMap<Binding, Command>,
+    /// The keys of every action in the order of `ACTIONS`.
+    keys: Vec<Vec<Binding>>,
+}
+
+impl Default for KeyMap {
+    fn default() -> KeyMap {
+        KeyMap::new(&[]).expect("the default key bindings do not conflict")
+    }
+}
+
+impl KeyMap {
+    /// The default bindings, with the actions named in `remapped` bound
+    /// to the keys given for them instead. Fails on unknown actions and
+    /// keys, and on keys bound to more than one action.
+    pub fn new(remapped: &[(String, Vec<String>)]) -> Result<KeyMap, Error> {
+        for (name, _) in remapped {
+            if !ACTIONS.iter().any(|(action, ..)| action == name) {
+                return Err(Error::Config(format!(
+                    "unknown action '{}' to bind keys to",
+                    name
+                )));
+            }
+        }
+        let mut map = KeyMap {
+            commands: HashMap::new(),
+            keys: Vec::new(),
+        };
+        for &(name, command, defaults) in ACTIONS {
+            let keys: Result<Vec<Binding>, Error> =
+                match remapped.iter().find(|(action, _)| action == name) {
+                    Some((_, keys)) => keys.iter().map(|key| parse_key(key)).collect(),
+                    None => defaults.iter().map(|key| parse_key(key)).collect(),
+                };
+            let keys = keys?;
+            for &key in &keys {
+                if let Some(&other) = map.commands.get(&key) {
+                    if other != command {
+                        return Err(Error::Config(format!(
+                            "'{}' is bound to both '{}' and '{}'",
+                            key_name(key),
+                            action_name(other),
+                            name
+                        )));
+                    }
+                }
+                map.commands.insert(key, command);
+            }
+            map.keys.push(keys);
+        }
+        Ok(map)
+    }
+
+    /// The command the key gives, with Ctrl held or not, where keys
+    /// without a binding of their own for Ctrl give the same as without it.
+    pub fn command(&self, key: Key, ctrl: bool) -> Option<Command> {
+        self.commands
+            .get(&(key, ctrl))
+            .or_else(|| self.commands.get(&(key, false)))
+            .copied()
+    }
+
+    /// The keys and what they do, in the order the help lists them, with
+    /// the first key of every action. `escape` is whatever the escape key
+    /// has been bound to.
+    pub fn help(&self, escape: Command) -> Vec<(String, &'static str)> {
+        let mut bindings = Vec::new();
+        for ((_, command, _), keys) in ACTIONS.iter().zip(&self.keys) {
+            if let Some(&key) = keys.first() {
+                bindings.push((key_name(key), command.description()));
+            }
+            if *command == Command::Quit {
+                bindings.push(("Escape".to_string(), escape.description()));
+            }
+        }
+        bindings.push(("Mouse wheel".to_string(), "Zoom around the cursor"));
+        bindings.push(("Middle drag".to_string(), "Pan the view"));
+        bindings.push(("Click minimap".to_string(), "Jump the view there"));
+        bindings.push(("Click cell".to_string(), "Shade its light cones"));
+        bindings.push(("Right click".to_string(), "Stop shading them"));
+        bindings
+    }
+}
+
+/// Parses a key like `S`, `F1`, `Space` or `Ctrl+S`, by the names SDL
+/// gives keys.
+fn parse_key(key: &str) -> Result<Binding, Error> {
+    let (name, ctrl) = match key.get(..5) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("ctrl+") && key.len() > 5 => (&key[5..], true),
+        _ => (key, false),
+    };
+    match Key::from_name(name) {
+        Some(Key::Named("Escape")) => Err(Error::Config(
+            "the escape key is set with --escape".to_string(),
+        )),
+        Some(key) => Ok((key, ctrl)),
+        None => Err(Error::Config(format!("unknown key '{}'", key))),
+    }
+}
+
+/// The keys that can be bound besides letters, digits and punctuation,
+/// by the names SDL gives them, so that the help shows them as written.
+const KEY_NAMES: &[&str] = &[
+    "Return",
+    "Escape",
+    "Backspace",
+    "Tab",
+    "Space",
+    "Delete",
+    "Insert",
+    "Home",
+    "End",
+    "PageUp",
+    "PageDown",
+    "Right",
+    "Left",
+    "Down",
+    "Up",
+    "PrintScreen",
+    "ScrollLock",
+    "Pause",
+    "Application",
+    "Menu",
+    "F1",
+    "F2",
+    "F3",
+    "F4",
+    "F5",
+    "F6",
+    "F7",
+    "F8",
+    "F9",
+    "F10",
+    "F11",
+    "F12",
+    "F13",
+    "F14",
+    "F15",
+    "F16",
+    "F17",
+    "F18",
+    "F19",
+    "F20",
+    "F21",
+    "F22",
+    "F23",
+    "F24",
+    "Keypad /",
+    "Keypad *",
+    "Keypad -",
+    "Keypad +",
+    "Keypad Enter",
+    "Keypad .",
+    "Keypad =",
+    "Keypad 0",
+    "Keypad 1",
+    "Keypad 2",
+    "Keypad 3",
+    "Keypad 4",
+    "Keypad 5",
+    "Keypad 6",
+    "Keypad 7",
+    "Keypad 8",
+    "Keypad 9",
+];
+
+/// The command of the action called `name` in the config file.
+pub fn action(name: &str) -> Option<Command> {
+    ACTIONS
+        .iter()
+        .find(|(action, ..)| *action == name)
+        .map(|&(_, command, _)| command)
+}
+
+fn key_name((key, ctrl): Binding) -> String {
+    match ctrl {
+        true => format!("Ctrl {}", key.name()),
+        false => key.name(),
+    }
+}
+
+fn action_name(command: Command) -> &'static str {
+    ACTIONS
+        .iter()
+        .find(|(_, action, _)| *action == command)
+        .map_or("?", |(name, ..)| name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn remap(action: &str, keys: &[&str]) -> Result<KeyMap, Error> {
+        let keys = keys.iter().map(|key| key.to_string()).collect();
+        KeyMap::new(&[(action.to_string(), keys)])
+    }
+
+    #[test]
+    fn remapping_replaces_the_default_keys() {
+        let map = remap("quit", &["X", "F12"]).unwrap();
+        assert_eq!(map.command(Key::Char('X'), false), Some(Command::Quit));
+        assert_eq!(map.command(Key::Named("F12"), false), Some(Command::Quit));
+        assert_eq!(map.command(Key::Char('Q'), false), None);
+    }
+
+    #[test]
+    fn rejects_keys_bound_twice() {
+        // Space pauses by default.
+        let error = remap("quit", &["Space"]).err().unwrap();
+        assert!(error.to_string().contains("bound to both"), "{}", error);
+        // Giving an action the same key twice is no conflict.
+        assert!(remap("quit", &["X", "x"]).is_ok());
+    }
+
+    #[test]
+    fn rejects_unknown_actions_and_keys() {
+        let error = remap("quitt", &["Q"]).err().unwrap();
+        assert!(
+            error.to_string().contains("unknown action 'quitt'"),
+            "{}",
+            error
+        );
+        assert!(remap("quit", &["Hyper"]).is_err());
+        assert!(remap("quit", &["Escape"]).is_err());
+    }
+
+    #[test]
+    fn parses_ctrl_in_any_case() {
+        assert_eq!(parse_key("Ctrl+S").unwrap(), (Key::Char('S'), true));
+        assert_eq!(parse_key("ctrl+s").unwrap(), (Key::Char('S'), true));
+        assert_eq!(
+            parse_key("CTRL+Keypad +").unwrap(),
+            (Key::Named("Keypad +"), true)
+        );
+        assert_eq!(parse_key("s").unwrap(), (Key::Char('S'), false));
+        assert!(parse_key("Ctrl+").is_err());
+        assert!(parse_key("Ctrl+Shift+S").is_err());
+    }
+
+    #[test]
+    fn ctrl_falls_back_to_the_key_alone() {
+        let map = KeyMap::default();
+        assert_eq!(
+            map.command(Key::Char('S'), true),
+            Some(Command::ExportDiagram)
+        );
+        assert_eq!(
+            map.command(Key::Char('S'), false),
+            Some(Command::Screenshot)
+        );
+        assert_eq!(map.command(Key::Char('Q'), true), Some(Command::Quit));
+    }
+}
@@ -0,0 +1,160 @@
+use crate::Error;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml::Value;
+
+/// Where the configuration file is looked for without `--config`, in the
+/// platform's directory for configuration. `None` if there is no such
+/// directory.
+pub fn default_path() -> Option<PathBuf> {
+    let var = |name: &str| env::var_os(name).filter(|value| !value.is_empty());
+    let config_dir = if cfg!(target_os = "windows") {
+        PathBuf::from(var("APPDATA")?)
+    } else if cfg!(target_os = "macos") {
+        PathBuf::from(var("HOME")?).join("Library/Application Support")
+    } else {
+        match var("XDG_CONFIG_HOME") {
+            Some(dir) => PathBuf::from(dir),
+            None => PathBuf::from(var("HOME")?).join(".config"),
+        }
+    };
+    Some(config_dir.join("rule30").join("config.toml"))
+}
+
+/// The start of the environment variables that set options.
+const ENV_PREFIX: &str = "RULE30_";
+
+/// The options set by environment variables, with the arguments they
+/// stand for: `RULE30_TICK_RATE=4` becomes `--tick-rate=4` and
+/// `RULE30_HEADLESS=1` becomes `--headless=1`.
+pub fn environment() -> Vec<(String, String)> {
+    let mut vars: Vec<(String, String)> = env::vars()
+        .filter(|(name, value)| name.starts_with(ENV_PREFIX) && !value.is_empty())
+        .collect();
+    vars.sort();
+    vars.into_iter()
+        .map(|(name, value)| {
+            let option = name[ENV_PREFIX.len()..].to_lowercase().replace('_', "-");
+            let argument = format!("--{}={}", option, value);
+            (name, argument)
+        })
+        .collect()
+}
+
+/// The option an argument like `--rule=90` sets, `rule`.
+pub fn option_name(argument: &str) -> &str {
+    let name = argument.trim_start_matches('-');
+    name.split_once('=').map_or(name, |(name, _)| name)
+}
+
+/// What a configuration file sets.
+#[derive(Default)]
+pub struct Config {
+    /// The options, as the arguments they stand for on the command line.
+    pub arguments: Vec<String>,
+    /// The actions of the `[keys]` table and the keys they are bound to.
+    pub keys: Vec<(String, Vec<String>)>,
+    /// The tables of the `[presets]` table, by name, as arguments.
+    pub presets: Vec<(String, Vec<String>)>,
+    /// The rules of the `[modes]` table, by name.
+    pub modes: Vec<(String, u8)>,
+}
+
+/// Reads the configuration file at `path`. Every key is an option without
+/// its dashes, `true` or `false` go to a flag and an array stands for an
+/// option given once for every value, so `rule = 90` becomes `--rule=90`,
+/// `crt = false` becomes `--crt=false` and `tick_rate = 4` becomes
+/// `--tick-rate=4` the same as `tick-rate = 4`. The `[keys]`
+/// table binds actions to a key or an array of keys, every table in
+/// `[presets]` is a preset of options, written like the options, and
+/// `[modes]` names elementary rules for `--mode`. There is no key for the
+/// size of the grid, which is fixed, `size` is the picture `render` draws.
+pub fn load(path: &Path) -> Result<Config, Error> {
+    let invalid = |e: &dyn std::fmt::Display| {
+        Error::Config(format!("invalid config '{}': {}", path.display(), e))
+    };
+    let contents = fs::read_to_string(path).map_err(|e| Error::io("read", path, e))?;
+    let table: toml::Table = toml::from_str(&contents).map_err(|e| invalid(&e))?;
+    let mut config = Config::default();
+    for (key, value) in table {
+        if let ("keys", Value::Table(keys)) = (key.as_str(), &value) {
+            for (action, keys) in keys {
+                let keys = match keys {
+                    Value::String(key) => vec![key.clone()],
+                    Value::Array(keys) => keys
+                        .iter()
+                        .map(|key| key.as_str().map(str::to_string))
+                        .collect::<Option<_>>()
+                        .ok_or_else(|| invalid(&format!("expected keys for '{}'", action)))?,
+                    _ => return Err(invalid(&format!("expected keys for '{}'", action))),
+                };
+                config.keys.push((action.clone(), keys));
+            }
+            continue;
+        }
+        if let ("presets", Value::Table(presets)) = (key.as_str(), &value) {
+            for (name, preset) in presets {
+                let Value::Table(preset) = preset else {
+                    return Err(invalid(&format!("expected a table for preset '{}'", name)));
+                };
+                let mut arguments = Vec::new();
+                for (key, value) in preset {
+                    push_arguments(key, value.clone(), &mut arguments).map_err(|e| invalid(&e))?;
+                }
+                config.presets.push((name.clone(), arguments));
+            }
+            continue;
+        }
+        if let ("modes", Value::Table(modes)) = (key.as_str(), &value) {
+            for (name, rule) in modes {
+                let rule = rule
+                    .as_integer()
+                    .and_then(|rule| u8::try_from(rule).ok())
+                    .ok_or_else(|| invalid(&format!("expected a rule for mode '{}'", name)))?;
+                config.modes.push((name.clone(), rule));
+            }
+            continue;
+        }
+        if key == "config" {
+            return Err(invalid(&"a config cannot name another one"));
+        }
+        push_arguments(&key, value, &mut config.arguments).map_err(|e| invalid(&e))?;
+    }
+    Ok(config)
+}
+
+/// Adds the arguments the option `key` set to `value` stands for, with
+/// underscores in `key` read as dashes.
+fn push_arguments(key: &str, value: Value, arguments: &mut Vec<String>) -> Result<(), Error> {
+    let key = key.replace('_', "-");
+    let values = match value {
+        Value::Array(values) => values,
+        value => vec![value],
+    };
+    for value in values {
+        let value = match value {
+            Value::Boolean(value) => value.to_string(),
+            Value::String(value) => value,
+            Value::Integer(value) => value.to_string(),
+            Value::Float(value) => value.to_string(),
+            _ => return Err(Error::Config(format!("unexpected value for '{}'", key))),
+        };
+        arguments.push(format!("--{}={}", key, value));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn underscores_stand_for_dashes() {
+        let mut arguments = Vec::new();
+        push_arguments("tick_rate", Value::Integer(4), &mut arguments).unwrap();
+        push_arguments("tick-rate", Value::Integer(4), &mut arguments).unwrap();
+        assert_eq!(arguments, ["--tick-rate=4", "--tick-rate=4"]);
+        assert_eq!(option_name(&arguments[0]), "tick-rate");
+    }
+}
@@ -0,0 +1,75 @@
+//! What can go wrong, for callers to tell apart.
+
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+/// Everything that can fail, in the application and in the library.
+pub enum Error {
+    /// SDL could not do something, in its own words.
+    Sdl(String),
+    /// A file could not be read, written or created.
+    Io {
+        /// What was done to the file, like `read` or `write`.
+        action: &'static str,
+        /// The file.
+        path: PathBuf,
+        /// Why it failed.
+        source: io::Error,
+    },
+    /// A rule that is not a number from 0 to 255.
+    InvalidRule(String),
+    /// Something that does not fit, like a pattern larger than the grid.
+    OutOfBounds(String),
+    /// Options, or a configuration file, that do not make sense together.
+    Config(String),
+    /// A value, or the contents of a file, that does not fit its format.
+    Parse(String),
+    /// Something could not be written out as TOML or JSON.
+    Serialize(String),
+}
+
+impl Error {
+    /// The error for failing to `action` the file at `path`.
+    pub fn io(action: &'static str, path: impl Into<PathBuf>, source: io::Error) -> Error {
+        Error::Io {
+            action,
+            path: path.into(),
+            source,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io {
+                action,
+                path,
+                source,
+            } => write!(f, "could not {} '{}': {}", action, path.display(), source),
+            Error::InvalidRule(rule) => write!(f, "invalid rule '{}', expected 0 to 255", rule),
+            Error::Sdl(message)
+            | Error::OutOfBounds(message)
+            | Error::Config(message)
+            | Error::Parse(message)
+            | Error::Serialize(message) => f.write_str(message),
+        }
+    }
+}
+
+/// Shows the message, which is what `main` prints when it fails.
+impl fmt::Debug for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use crate::Error;
+
+const USAGE: &str = "Usage: cellular_automata export KIND [TARGET] [OPTIONS]
+
+Runs an automaton for --generations without opening a window and saves or
+prints what it computed. Takes the options of 'run', like --rule, --seed,
+--pattern and --generations, see 'run --help'.
+
+Kinds:
+  diagram FILE         The diagram, in the format the extension of FILE
+                       names (png, svg, rle, cells, mc, csv, bin, json, npy)
+  tiles DIR            The rows as PNG tiles with an index.json
+  report DIR           The diagram, the command line, the starting state
+                       and per generation statistics
+  wav FILE             The rows as sound
+  midi FILE            The rows as notes
+  column               The center column of the rule, printed
+  text                 The rows as lines of blocks and spaces, printed";
+
+/// The kinds of export, the option of `run` that does each and whether
+/// it saves to a file or directory.
+const KINDS: &[(&str, &str, bool)] = &[
+    ("diagram", "--export", true),
+    ("tiles", "--export-tiles", true),
+    ("report", "--report", true),
+    ("wav", "--export-wav", true),
+    ("midi", "--export-midi", true),
+    ("column", "--center-column", false),
+    ("text", "--print", false),
+];
+
+/// Turns the arguments after `export` into the arguments of `run` that
+/// export the same. `None` when the usage was asked for.
+pub fn arguments(args: &[String]) -> Result<Option<Vec<String>>, Error> {
+    let Some(kind) = args.first() else {
+        return Err(Error::Config(format!(
+            "missing what to export\n\n{}",
+            USAGE
+        )));
+    };
+    if kind == "--help" || kind == "-h" {
+        println!("{}", USAGE);
+        return Ok(None);
+    }
+    let &(_, flag, has_target) = KINDS
+        .iter()
+        .find(|(name, ..)| name == kind)
+        .ok_or_else(|| Error::Config(format!("unknown export '{}'\n\n{}", kind, USAGE)))?;
+    let mut arguments = vec![flag.to_string()];
+    let mut rest = args[1..].iter();
+    if has_target {
+        match rest.next() {
+            Some(target) if !target.starts_with("--") => arguments.push(target.clone()),
+            _ => {
+                return Err(Error::Config(format!(
+                    "missing where to save the {}\n\n{}",
+                    kind, USAGE
+                )))
+            }
+        }
+    }
+    arguments.extend(rest.cloned());
+    Ok(Some(arguments))
+}
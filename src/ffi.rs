@@ -0,0 +1,149 @@
+//! A C API, built with the `ffi` feature, for embedding the simulation in
+//! C and C++ programs. `include/cellular_automata.h` declares it; an
+//! automaton is an opaque pointer from [`ca_new`] that goes back to
+//! [`ca_free`].
+//!
+//! ```c
+//! CaAutomaton *automaton = ca_new(30, "center");
+//! uint8_t row[CA_WIDTH];
+//! for (int i = 0; i < 1000; i++) {
+//!     ca_next_row(automaton, row, sizeof row);
+//! }
+//! ca_free(automaton);
+//! ```
+
+use crate::seed::Seed;
+use crate::{SimContext, StepMode, GRID_X_SIZE, GRID_Y_SIZE};
+use std::ffi::{c_char, CStr};
+
+// Spelled out rather than aliased so that cbindgen writes numbers into the
+// header instead of names it leaves out.
+/// How many cells a row has, and so how many bytes a row takes.
+pub const CA_WIDTH: u32 = 101;
+/// How many rows the grid has.
+pub const CA_HEIGHT: u32 = 100;
+const _: () = assert!(CA_WIDTH == GRID_X_SIZE && CA_HEIGHT == GRID_Y_SIZE);
+
+/// An elementary automaton, stepping a row at a time.
+pub struct CaAutomaton {
+    context: SimContext,
+}
+
+/// Grows `rule` from `seed`, `"center"` or a number, or from the center
+/// if `seed` is null. Returns null for a seed that is neither.
+///
+/// # Safety
+///
+/// `seed` is null or a NUL terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn ca_new(rule: u8, seed: *const c_char) -> *mut CaAutomaton {
+    let seed = if seed.is_null() {
+        Seed::Center
+    } else {
+        match CStr::from_ptr(seed)
+            .to_str()
+            .ok()
+            .and_then(|seed| seed.parse().ok())
+        {
+            Some(seed) => seed,
+            None => return std::ptr::null_mut(),
+        }
+    };
+    let mut context = SimContext::seeded(rule, seed);
+    context.step_mode = StepMode::Row;
+    Box::into_raw(Box::new(CaAutomaton { context }))
+}
+
+/// Computes `n` rows. A full grid stays as it is, see [`ca_next_row`] for
+/// going on past it.
+///
+/// # Safety
+///
+/// `automaton` comes from [`ca_new`] and has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn ca_step(automaton: *mut CaAutomaton, n: u64) {
+    let context = &mut (*automaton).context;
+    for _ in 0..n {
+        context.advance();
+    }
+}
+
+/// Computes the next row, scrolling the grid once it is full, and copies
+/// it into `out`, a byte for every cell, 1 for live ones. Returns the
+/// number of bytes copied, which is less than [`CA_WIDTH`] if `len` is.
+///
+/// # Safety
+///
+/// `automaton` comes from [`ca_new`] and has not been freed, `out` is null
+/// or points to `len` writable bytes. A null `out` copies nothing.
+#[no_mangle]
+pub unsafe extern "C" fn ca_next_row(
+    automaton: *mut CaAutomaton,
+    out: *mut u8,
+    len: usize,
+) -> usize {
+    let context = &mut (*automaton).context;
+    // The first row the iterator gives is the one already there.
+    let row = context.rows().nth(1).unwrap_or_default();
+    copy_row(row.into_iter(), out, len)
+}
+
+/// Copies row `y` of the grid, counted from the top, into `out` like
+/// [`ca_next_row`]. Returns 0 if there is no such row.
+///
+/// # Safety
+///
+/// `automaton` comes from [`ca_new`] and has not been freed, `out` is null
+/// or points to `len` writable bytes. A null `out` copies nothing.
+#[no_mangle]
+pub unsafe extern "C" fn ca_read_row(
+    automaton: *const CaAutomaton,
+    y: u32,
+    out: *mut u8,
+    len: usize,
+) -> usize {
+    if y >= GRID_Y_SIZE {
+        return 0;
+    }
+    let context = &(*automaton).context;
+    copy_row(
+        context.points.iter().map(|column| column[y as usize]),
+        out,
+        len,
+    )
+}
+
+/// The number of rows completed since the seed.
+///
+/// # Safety
+///
+/// `automaton` comes from [`ca_new`] and has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn ca_generation(automaton: *const CaAutomaton) -> u64 {
+    (*automaton).context.generation()
+}
+
+/// Frees an automaton from [`ca_new`]. Null is fine and does nothing.
+///
+/// # Safety
+///
+/// `automaton` is null or comes from [`ca_new`] and has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn ca_free(automaton: *mut CaAutomaton) {
+    if !automaton.is_null() {
+        drop(Box::from_raw(automaton));
+    }
+}
+
+unsafe fn copy_row(row: impl Iterator<Item = bool>, out: *mut u8, len: usize) -> usize {
+    if out.is_null() {
+        return 0;
+    }
+    let out = std::slice::from_raw_parts_mut(out, len);
+    let mut copied = 0;
+    for (byte, alive) in out.iter_mut().zip(row) {
+        *byte = alive as u8;
+        copied += 1;
+    }
+    copied
+}
@@ -0,0 +1,65 @@
+use crate::image::RgbaImage;
+use log::error;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Sender};
+use std::thread::{self, JoinHandle};
+
+/// Saves a PNG of the window every so many generations, for assembling a
+/// timelapse later. Encoding and writing happens on a thread of its own so
+/// the animation carries on meanwhile.
+pub struct FrameCapture {
+    every: u64,
+    /// The generation the last frame was saved at.
+    last: Option<u64>,
+    frames: Option<Sender<(PathBuf, RgbaImage)>>,
+    writer: Option<JoinHandle<()>>,
+}
+
+impl FrameCapture {
+    pub fn start(every: u64) -> FrameCapture {
+        let (frames, received) = mpsc::channel::<(PathBuf, RgbaImage)>();
+        let writer = thread::spawn(move || {
+            for (path, image) in received {
+                if let Err(e) = image.save_png(&path) {
+                    error!("{}", e);
+                }
+            }
+        });
+        FrameCapture {
+            every,
+            last: None,
+            frames: Some(frames),
+            writer: Some(writer),
+        }
+    }
+
+    /// Whether a frame showing `generation` should be saved, which is the
+    /// case whenever a multiple of the interval has been reached since the
+    /// last one. Several generations may pass in a single frame.
+    pub fn is_due(&mut self, generation: u64) -> bool {
+        let due = match self.last {
+            Some(last) => generation / self.every != last / self.every,
+            None => generation.is_multiple_of(self.every),
+        };
+        if due {
+            self.last = Some(generation);
+        }
+        due
+    }
+
+    pub fn save(&self, path: PathBuf, image: RgbaImage) {
+        if let Some(frames) = &self.frames {
+            let _ = frames.send((path, image));
+        }
+    }
+}
+
+impl Drop for FrameCapture {
+    /// Waits for the frames still being written.
+    fn drop(&mut self) {
+        self.frames = None;
+        if let Some(writer) = self.writer.take() {
+            let _ = writer.join();
+        }
+    }
+}
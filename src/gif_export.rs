@@ -0,0 +1,92 @@
+use crate::color::Color;
+use crate::Error;
+use crate::{Cells, GRID_X_SIZE, GRID_Y_SIZE};
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{self, BufWriter};
+use std::path::{Path, PathBuf};
+
+/// Records the grid into an animated GIF, one frame for every `every`th
+/// generation. The file is finished once the recorder is dropped.
+pub struct GifRecorder {
+    encoder: gif::Encoder<BufWriter<File>>,
+    path: PathBuf,
+    every: u64,
+    /// How long every frame shows, in hundredths of a second.
+    delay: u16,
+    scale: u32,
+    /// The generation the next frame is due at.
+    next_due: u64,
+}
+
+impl GifRecorder {
+    /// Starts a GIF at `path` with `scale` by `scale` pixels per cell,
+    /// live cells drawn in `live` and dead ones in `dead`.
+    pub fn create(
+        path: &Path,
+        scale: u32,
+        (live, dead): (Color, Color),
+        every: u64,
+        delay_ms: u32,
+    ) -> Result<GifRecorder, Error> {
+        let error =
+            |e: &dyn std::fmt::Display| Error::io("write", path, io::Error::other(e.to_string()));
+        let size = |cells: u32| {
+            cells
+                .checked_mul(scale)
+                .and_then(|pixels| u16::try_from(pixels).ok())
+        };
+        let (Some(width), Some(height)) = (size(GRID_X_SIZE), size(GRID_Y_SIZE)) else {
+            return Err(error(&"too large for a GIF"));
+        };
+
+        let file = File::create(path).map_err(|e| Error::io("write", path, e))?;
+        let palette = [dead.r, dead.g, dead.b, live.r, live.g, live.b];
+        let mut encoder = gif::Encoder::new(BufWriter::new(file), width, height, &palette)
+            .map_err(|e| error(&e))?;
+        encoder
+            .set_repeat(gif::Repeat::Infinite)
+            .map_err(|e| error(&e))?;
+        Ok(GifRecorder {
+            encoder,
+            path: path.to_path_buf(),
+            every: every.max(1),
+            delay: (delay_ms / 10).min(u16::MAX as u32) as u16,
+            scale,
+            next_due: 0,
+        })
+    }
+
+    /// Adds a frame of `cells` if `generation` has reached the next one
+    /// due, which it may have gone past when several generations pass in a
+    /// single frame.
+    pub fn capture(&mut self, generation: u64, cells: &Cells) -> Result<(), Error> {
+        if generation < self.next_due {
+            return Ok(());
+        }
+        self.next_due = (generation / self.every + 1).saturating_mul(self.every);
+
+        let scale = self.scale as usize;
+        let (width, height) = (GRID_X_SIZE as usize * scale, GRID_Y_SIZE as usize * scale);
+        let mut pixels = Vec::with_capacity(width * height);
+        for y in 0..GRID_Y_SIZE as usize {
+            let line: Vec<u8> = cells
+                .iter()
+                .flat_map(|column| [column[y] as u8].repeat(scale))
+                .collect();
+            for _ in 0..scale {
+                pixels.extend_from_slice(&line);
+            }
+        }
+        let frame = gif::Frame {
+            width: width as u16,
+            height: height as u16,
+            delay: self.delay,
+            buffer: Cow::Owned(pixels),
+            ..gif::Frame::default()
+        };
+        self.encoder
+            .write_frame(&frame)
+            .map_err(|e| Error::io("write", &self.path, io::Error::other(e.to_string())))
+    }
+}
@@ -0,0 +1,121 @@
+use crate::Error;
+use std::cmp::Reverse;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter};
+use std::path::Path;
+
+/// An image decoded to 8 bit RGBA, row by row.
+#[derive(Clone)]
+pub struct RgbaImage {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl RgbaImage {
+    pub fn load_png(path: &Path) -> Result<RgbaImage, Error> {
+        let error =
+            |e: &dyn std::fmt::Display| Error::io("read", path, io::Error::other(e.to_string()));
+
+        let file = File::open(path).map_err(|e| Error::io("read", path, e))?;
+        let mut decoder = png::Decoder::new(BufReader::new(file));
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+        let mut reader = decoder.read_info().map_err(|e| error(&e))?;
+        let mut buffer = vec![
+            0;
+            reader
+                .output_buffer_size()
+                .ok_or_else(|| error(&"image too large"))?
+        ];
+        let info = reader.next_frame(&mut buffer).map_err(|e| error(&e))?;
+        let bytes = &buffer[..info.buffer_size()];
+
+        let pixels = match info.color_type {
+            png::ColorType::Rgba => bytes.to_vec(),
+            png::ColorType::Rgb => bytes
+                .chunks_exact(3)
+                .flat_map(|rgb| [rgb[0], rgb[1], rgb[2], 255])
+                .collect(),
+            png::ColorType::GrayscaleAlpha => bytes
+                .chunks_exact(2)
+                .flat_map(|ga| [ga[0], ga[0], ga[0], ga[1]])
+                .collect(),
+            png::ColorType::Grayscale => bytes.iter().flat_map(|&g| [g, g, g, 255]).collect(),
+            png::ColorType::Indexed => return Err(error(&"unexpected indexed colors")),
+        };
+
+        Ok(RgbaImage {
+            width: info.width,
+            height: info.height,
+            pixels,
+        })
+    }
+
+    pub fn save_png(&self, path: &Path) -> Result<(), Error> {
+        let error =
+            |e: &dyn std::fmt::Display| Error::io("write", path, io::Error::other(e.to_string()));
+
+        let file = File::create(path).map_err(|e| Error::io("write", path, e))?;
+        let mut encoder = png::Encoder::new(BufWriter::new(file), self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().map_err(|e| error(&e))?;
+        writer
+            .write_image_data(&self.pixels)
+            .map_err(|e| error(&e))?;
+        writer.finish().map_err(|e| error(&e))
+    }
+
+    /// The image stretched or shrunk to the given size, taking the nearest
+    /// pixel for every one.
+    pub fn resized(&self, width: u32, height: u32) -> RgbaImage {
+        let mut pixels = Vec::with_capacity(width as usize * height as usize * 4);
+        for y in 0..height {
+            let source_y = (y as u64 * self.height as u64 / height as u64) as usize;
+            for x in 0..width {
+                let source_x = (x as u64 * self.width as u64 / width as u64) as usize;
+                let index = (source_y * self.width as usize + source_x) * 4;
+                pixels.extend_from_slice(&self.pixels[index..index + 4]);
+            }
+        }
+        RgbaImage {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// The bytes in one row of pixels.
+    pub fn pitch(&self) -> usize {
+        self.width as usize * 4
+    }
+
+    /// The most common colors of the image, most common first. Similar
+    /// colors are grouped together and averaged, transparent pixels
+    /// ignored.
+    pub fn dominant_colors(&self, count: usize) -> Vec<(u8, u8, u8)> {
+        // Four bits per channel gives 4096 groups.
+        let mut groups = vec![(0u64, [0u64; 3]); 1 << 12];
+        for pixel in self.pixels.chunks_exact(4).filter(|pixel| pixel[3] >= 128) {
+            let index = (pixel[0] as usize >> 4) << 8
+                | (pixel[1] as usize >> 4) << 4
+                | pixel[2] as usize >> 4;
+            let (pixels, sums) = &mut groups[index];
+            *pixels += 1;
+            for channel in 0..3 {
+                sums[channel] += pixel[channel] as u64;
+            }
+        }
+
+        groups.sort_by_key(|(pixels, _)| Reverse(*pixels));
+        groups
+            .into_iter()
+            .take_while(|(pixels, _)| *pixels > 0)
+            .take(count)
+            .map(|(pixels, sums)| {
+                let average = |sum: u64| (sum / pixels) as u8;
+                (average(sums[0]), average(sums[1]), average(sums[2]))
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,113 @@
+use crate::seed::{Seed, SplitMix64};
+use crate::Error;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+const FADE_DURATION: Duration = Duration::from_secs(1);
+
+/// A rule and seed to show in kiosk mode.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Preset {
+    pub rule: u8,
+    pub seed: Seed,
+}
+
+pub fn default_playlist() -> Vec<Preset> {
+    [30, 90, 110, 45, 73, 150]
+        .into_iter()
+        .flat_map(|rule| {
+            [
+                Preset {
+                    rule,
+                    seed: Seed::Center,
+                },
+                Preset {
+                    rule,
+                    seed: Seed::Random(rule as u64),
+                },
+            ]
+        })
+        .collect()
+}
+
+/// Parses `RULE` or `RULE:SEED`, for example `110:center` or `30:42`.
+impl FromStr for Preset {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (rule, seed) = s.split_once(':').unwrap_or((s, "center"));
+        Ok(Preset {
+            rule: rule
+                .parse()
+                .map_err(|_| Error::InvalidRule(rule.to_string()))?,
+            seed: seed.parse()?,
+        })
+    }
+}
+
+pub fn random_preset(rng: &mut SplitMix64) -> Preset {
+    let rule = rng.next_u64() as u8;
+    let seed = match rng.next_u64() & 1 {
+        0 => Seed::Center,
+        _ => Seed::Random(rng.next_u64()),
+    };
+    Preset { rule, seed }
+}
+
+/// Cycles through a playlist unattended, fading out before and fading in
+/// after every switch.
+pub struct Kiosk {
+    playlist: Vec<Preset>,
+    /// When set the playlist holds a single random preset that is replaced
+    /// on every switch.
+    shuffle: Option<SplitMix64>,
+    dwell: Duration,
+    current: usize,
+    started: Instant,
+}
+
+impl Kiosk {
+    pub fn new(playlist: Vec<Preset>, dwell: Duration) -> Kiosk {
+        Kiosk {
+            playlist,
+            shuffle: None,
+            dwell: dwell.max(FADE_DURATION * 2),
+            current: 0,
+            started: Instant::now(),
+        }
+    }
+
+    /// A kiosk that picks a random rule and seed on every switch.
+    pub fn shuffled(dwell: Duration, seed: u64) -> Kiosk {
+        let mut rng = SplitMix64::new(seed);
+        let mut kiosk = Kiosk::new(vec![random_preset(&mut rng)], dwell);
+        kiosk.shuffle = Some(rng);
+        kiosk
+    }
+
+    pub fn current(&self) -> Preset {
+        self.playlist[self.current]
+    }
+
+    /// Returns the next preset once the current one has been shown for the
+    /// dwell time.
+    pub fn update(&mut self, now: Instant) -> Option<Preset> {
+        if now.duration_since(self.started) < self.dwell {
+            return None;
+        }
+        match &mut self.shuffle {
+            Some(rng) => self.playlist[0] = random_preset(rng),
+            None => self.current = (self.current + 1) % self.playlist.len(),
+        }
+        self.started = now;
+        Some(self.current())
+    }
+
+    /// How far the picture is faded out, from 0.0 (fully visible) to 1.0.
+    pub fn fade(&self, now: Instant) -> f32 {
+        let elapsed = now.duration_since(self.started);
+        let remaining = self.dwell.saturating_sub(elapsed);
+        let fade = FADE_DURATION.saturating_sub(elapsed.min(remaining));
+        fade.as_secs_f32() / FADE_DURATION.as_secs_f32()
+    }
+}
@@ -0,0 +1,932 @@
+//! The simulation behind the cellular automata viewer, free of SDL and
+//! anything else to do with windows: elementary automata like Rule 30
+//! computed cell by cell by a scanner, and Conway's Game of Life, on a grid
+//! of [`GRID_X_SIZE`] by [`GRID_Y_SIZE`] cells that keeps the history to
+//! step back through.
+//!
+//! ```
+//! use cellular_automata::seed::Seed;
+//! use cellular_automata::{SimContext, StepMode};
+//!
+//! let mut context = SimContext::seeded(30, Seed::Center);
+//! context.step_mode = StepMode::Row;
+//! for _ in 0..10 {
+//!     context.advance();
+//! }
+//! assert_eq!(context.generation(), 10);
+//! let row: String = context
+//!     .finished_row()
+//!     .iter()
+//!     .map(|&alive| if alive { '#' } else { '.' })
+//!     .collect();
+//! println!("{}", row);
+//! ```
+#![warn(missing_docs)]
+
+pub mod error;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod library;
+pub mod life;
+pub mod pattern;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod registry;
+pub mod seed;
+pub mod tick_rate;
+#[cfg(feature = "web")]
+pub mod web;
+
+pub use crate::error::Error;
+
+use crate::library::LibraryEntry;
+use crate::pattern::Pattern;
+use crate::seed::Seed;
+use crate::tick_rate::TickRate;
+use core::ops::Add;
+use std::collections::VecDeque;
+use std::str::FromStr;
+
+/// How many cells a row of the grid has.
+pub const GRID_X_SIZE: u32 = 101;
+/// How many rows the grid has.
+pub const GRID_Y_SIZE: u32 = 100;
+/// The row holding the seed, generation zero.
+pub const SEED_ROW: usize = 1;
+/// How many generations of a 2D automaton can be undone.
+const MAX_GRID_HISTORY: usize = 1000;
+/// How many single steps can be undone, which only ever runs out in endless
+/// mode.
+const MAX_CELL_HISTORY: usize = 200_000;
+
+/// The cells of the grid, by column and then row, `true` for live ones.
+pub type Cells = [[bool; GRID_Y_SIZE as usize]; GRID_X_SIZE as usize];
+
+/// Whether the automaton advances on its own every tick.
+pub enum SimulationState {
+    /// Advancing on every tick.
+    Playing,
+    /// Only advancing when asked to.
+    Paused,
+}
+
+/// How much a single tick computes: one cell while the scanner
+/// visibly crawls along, or a complete row at once.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StepMode {
+    /// Every tick computes `cells_per_tick` cells.
+    Cell,
+    /// Every tick computes a whole row.
+    Row,
+}
+
+/// Which cellular automaton runs on the grid.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Automaton {
+    /// A one dimensional Wolfram rule, drawn row by row by the scanner.
+    Elementary,
+    /// Conway's Game of Life, updating the whole grid every generation.
+    Life,
+}
+
+impl Automaton {
+    /// The other automaton, to switch between them.
+    pub fn next(self) -> Automaton {
+        match self {
+            Automaton::Elementary => Automaton::Life,
+            Automaton::Life => Automaton::Elementary,
+        }
+    }
+}
+
+impl FromStr for Automaton {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "elementary" => Ok(Automaton::Elementary),
+            "life" => Ok(Automaton::Life),
+            _ => Err(Error::Parse(format!(
+                "unknown mode '{}', expected 'elementary' or 'life'",
+                s
+            ))),
+        }
+    }
+}
+
+/// What elementary rules read past the ends of a row.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Boundary {
+    /// Whatever the scanner covers on its way from one row to the next:
+    /// the first cell of a row sees the last cell of the row above to its
+    /// left, the last cell sees the first cell of the row below to its
+    /// right.
+    #[default]
+    Helix,
+    /// Dead cells.
+    Dead,
+    /// The other end of the same row, as if it were a ring.
+    Wrap,
+}
+
+/// The row an elementary `rule` computes from `row`, which can be any
+/// length, with dead cells past either end. [`SimContext`] steps rows as
+/// wide as the grid, a cell at a time; this is for whole rows of any
+/// other width.
+pub fn next_row(rule: u8, row: &[bool]) -> Vec<bool> {
+    let cell = |x: usize| row.get(x).copied().unwrap_or(false);
+    (0..row.len())
+        .map(|x| apply_rule(rule, x > 0 && cell(x - 1), cell(x), cell(x + 1)).0)
+        .collect()
+}
+
+/// Whether `rule` lets the cell below the three `left`, `center` and
+/// `right` live, and the number of their neighborhood.
+fn apply_rule(rule: u8, left: bool, center: bool, right: bool) -> (bool, u8) {
+    let neighborhood = (left as u8) << 2 | (center as u8) << 1 | right as u8;
+    (rule >> neighborhood & 1 == 1, neighborhood)
+}
+
+/// A cell of the grid, by column and row from the top left.
+#[derive(Copy, Clone, Debug)]
+pub struct Point(pub i32, pub i32);
+
+/// An automaton on the grid, how far it got and how to undo that.
+pub struct SimContext {
+    /// The cells of the grid.
+    pub points: Cells,
+    /// The three cells an elementary rule reads to compute the cell below
+    /// the middle one, the head at the right first.
+    pub scanner: Vec<Point>,
+    /// Whether the automaton is playing or paused.
+    pub state: SimulationState,
+    /// Which automaton runs.
+    pub automaton: Automaton,
+    /// The elementary rule, by Wolfram's numbering.
+    pub rule: u8,
+    /// What the automaton started from.
+    pub seed: Seed,
+    /// What elementary rules read past the ends of a row.
+    pub boundary: Boundary,
+    /// How much a step computes.
+    pub step_mode: StepMode,
+    /// How many cells a step computes in cell mode.
+    pub cells_per_tick: u32,
+    /// How often the automaton ticks while it plays.
+    pub tick_rate: TickRate,
+    /// The steps that can be undone, the last one last.
+    pub history: VecDeque<HistoryEntry>,
+    /// Which of the eight neighborhoods produced every cell, `None` for
+    /// cells that were never computed.
+    pub cases: [[Option<u8>; GRID_Y_SIZE as usize]; GRID_X_SIZE as usize],
+    /// The generations a 2D automaton has run for.
+    pub grid_generation: u64,
+    /// Whether an elementary automaton keeps going past the last row,
+    /// scrolling the older rows out at the top.
+    pub endless: bool,
+    /// How many rows have been scrolled out at the top.
+    pub scrolled_rows: u64,
+    /// How many generations of a 2D automaton every cell has been alive for.
+    pub heat: [[u32; GRID_Y_SIZE as usize]; GRID_X_SIZE as usize],
+}
+
+/// Everything needed to undo a single step.
+pub enum HistoryEntry {
+    /// Where the scanner was and what the cell it wrote contained before.
+    Cell {
+        /// The scanner before the step.
+        scanner: Vec<Point>,
+        /// The cell the step wrote.
+        cell: Point,
+        /// Whether that cell was alive before.
+        previous: bool,
+        /// The neighborhood that cell was computed from before.
+        previous_case: Option<u8>,
+    },
+    /// The complete grid before a generation of a 2D automaton.
+    Grid(Box<Cells>),
+    /// The row that scrolled out at the top in endless mode.
+    Scroll {
+        /// Its cells.
+        row: Vec<bool>,
+        /// The neighborhoods its cells were computed from.
+        cases: Vec<Option<u8>>,
+    },
+}
+
+impl Add<Point> for Point {
+    type Output = Point;
+
+    fn add(self, rhs: Point) -> Self::Output {
+        Point(self.0 + rhs.0, self.1 + rhs.1)
+    }
+}
+
+impl SimContext {
+    /// Rule 30 from a single live cell, what [`SimContext::builder`]
+    /// builds without being told otherwise.
+    pub fn new() -> SimContext {
+        Self::builder().build().expect("the defaults fit the grid")
+    }
+    /// An elementary automaton running `rule` from `seed`, paused in cell
+    /// mode.
+    pub fn seeded(rule: u8, seed: Seed) -> SimContext {
+        Self::build(Automaton::Elementary, rule, seed)
+    }
+    /// Starts building an automaton, Rule 30 from a single live cell
+    /// unless told otherwise.
+    ///
+    /// ```
+    /// use cellular_automata::seed::Seed;
+    /// use cellular_automata::{Boundary, SimContext};
+    ///
+    /// let context = SimContext::builder()
+    ///     .rule(110)
+    ///     .seed(Seed::Random(7))
+    ///     .boundary(Boundary::Wrap)
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(context.rule, 110);
+    /// ```
+    pub fn builder() -> SimContextBuilder {
+        SimContextBuilder {
+            automaton: Automaton::Elementary,
+            rule: 30,
+            seed: Seed::Center,
+            boundary: Boundary::default(),
+            step_mode: StepMode::Cell,
+            endless: false,
+            width: GRID_X_SIZE as usize,
+            pattern: None,
+        }
+    }
+    fn build(automaton: Automaton, rule: u8, seed: Seed) -> SimContext {
+        let mut cells: Cells = [[false; GRID_Y_SIZE as usize]; GRID_X_SIZE as usize];
+
+        match automaton {
+            Automaton::Elementary => {
+                for (x, alive) in seed.cells(GRID_X_SIZE as usize).into_iter().enumerate() {
+                    cells[x][SEED_ROW] = alive;
+                }
+            }
+            Automaton::Life => cells = life::seed_cells(seed),
+        }
+
+        SimContext {
+            scanner: vec![Point(0, 1), Point(1, 1), Point(2, 1)],
+            points: cells,
+            state: SimulationState::Paused,
+            automaton,
+            rule,
+            seed,
+            boundary: Boundary::default(),
+            step_mode: StepMode::Cell,
+            cells_per_tick: 1,
+            tick_rate: TickRate::default(),
+            history: VecDeque::new(),
+            cases: [[None; GRID_Y_SIZE as usize]; GRID_X_SIZE as usize],
+            grid_generation: 0,
+            endless: false,
+            scrolled_rows: 0,
+            heat: [[0; GRID_Y_SIZE as usize]; GRID_X_SIZE as usize],
+        }
+    }
+    /// Starts over from `seed` with `rule`, keeping the automaton and the
+    /// playback settings.
+    pub fn reset(&mut self, rule: u8, seed: Seed) {
+        let fresh = Self::build(self.automaton, rule, seed);
+        self.points = fresh.points;
+        self.scanner = fresh.scanner;
+        self.rule = fresh.rule;
+        self.seed = fresh.seed;
+        self.history = fresh.history;
+        self.cases = fresh.cases;
+        self.grid_generation = fresh.grid_generation;
+        self.scrolled_rows = fresh.scrolled_rows;
+        self.heat = fresh.heat;
+    }
+    /// Starts over from `pattern` in the middle of the grid. Elementary
+    /// automata take its first row as the seed row.
+    pub fn place(&mut self, pattern: &Pattern) {
+        self.reset(self.rule, self.seed);
+        let left = (GRID_X_SIZE as usize - pattern.width()) / 2;
+        match self.automaton {
+            Automaton::Elementary => {
+                for x in 0..GRID_X_SIZE as usize {
+                    self.points[x][SEED_ROW] = false;
+                }
+                for (x, &alive) in pattern.rows[0].iter().enumerate() {
+                    self.points[left + x][SEED_ROW] = alive;
+                }
+            }
+            Automaton::Life => {
+                let top = (GRID_Y_SIZE as usize - pattern.height()) / 2;
+                self.points = [[false; GRID_Y_SIZE as usize]; GRID_X_SIZE as usize];
+                for (y, row) in pattern.rows.iter().enumerate() {
+                    for (x, &alive) in row.iter().enumerate() {
+                        self.points[left + x][top + y] = alive;
+                    }
+                }
+            }
+        }
+    }
+    /// Starts over from a pattern of the library, switching to the
+    /// automaton and the rule it is meant for.
+    pub fn insert(&mut self, entry: &LibraryEntry) {
+        self.automaton = entry.automaton;
+        if entry.automaton == Automaton::Elementary {
+            self.rule = entry.rule;
+        }
+        self.place(&entry.pattern());
+    }
+    /// Shows `row`, computed elsewhere, below the last complete row like
+    /// the scanner would have, scrolling the grid up once it is full.
+    pub fn push_row(&mut self, row: &[bool]) {
+        if self.is_finished() {
+            self.scroll();
+        }
+        let y = self.scanner[0].1 as usize + 1;
+        for (x, &alive) in row.iter().enumerate().take(GRID_X_SIZE as usize) {
+            self.points[x][y] = alive;
+            self.cases[x][y] = None;
+        }
+        // Where the scanner would wait after finishing the row in row mode.
+        let last = GRID_X_SIZE as i32 - 1;
+        self.scanner = vec![
+            Point(0, y as i32),
+            Point(last, y as i32 - 1),
+            Point(last - 1, y as i32 - 1),
+        ];
+    }
+    /// The row the scanner is at. In row mode it waits at the start of the
+    /// row it has just finished.
+    pub fn finished_row(&self) -> Vec<bool> {
+        let y = self.scanner[0].1 as usize;
+        self.points.iter().map(|column| column[y]).collect()
+    }
+    /// Switches to `automaton` and starts over from the seed.
+    pub fn set_automaton(&mut self, automaton: Automaton) {
+        self.automaton = automaton;
+        self.reset(self.rule, self.seed);
+    }
+    /// Advances unless paused.
+    pub fn next_tick(&mut self) {
+        if let SimulationState::Paused = self.state {
+            return;
+        }
+        self.advance();
+    }
+    /// Ticks as often as the tick rate asks for on `frame`, which counts
+    /// the frames shown so far, and returns how often that was. Whatever
+    /// shows the frames paces them, this never waits.
+    pub fn play_frame(&mut self, frame: u64) -> u32 {
+        let ticks = self.tick_rate.ticks_on_frame(frame);
+        for _ in 0..ticks {
+            self.next_tick();
+        }
+        ticks
+    }
+    /// Steps back unless paused.
+    pub fn previous_tick(&mut self) {
+        if let SimulationState::Paused = self.state {
+            return;
+        }
+        self.rewind();
+    }
+    /// Moves the scanner `cells_per_tick` cells or, in row mode, runs it
+    /// until it wraps around to the start of the next row. 2D automata
+    /// always compute a whole generation.
+    pub fn advance(&mut self) {
+        if self.automaton == Automaton::Life {
+            return self.step_grid();
+        }
+        match self.step_mode {
+            StepMode::Cell => {
+                for _ in 0..self.cells_per_tick {
+                    self.step();
+                }
+            }
+            StepMode::Row => loop {
+                self.step();
+                if self.is_finished() || self.scanner[0].0 == 0 {
+                    break;
+                }
+            },
+        }
+    }
+    /// Undoes what `advance` did, as far as the history goes.
+    pub fn rewind(&mut self) {
+        if self.automaton == Automaton::Life {
+            return self.step_back();
+        }
+        match self.step_mode {
+            StepMode::Cell => {
+                for _ in 0..self.cells_per_tick {
+                    self.step_back();
+                }
+            }
+            StepMode::Row => loop {
+                self.step_back();
+                if self.history.is_empty() || self.scanner[0].0 == 0 {
+                    break;
+                }
+            },
+        }
+    }
+    /// Computes a single cell, scrolling first in endless mode once the
+    /// grid is full. Does nothing on a full grid otherwise.
+    pub fn step(&mut self) {
+        if self.is_finished() {
+            if !self.endless {
+                return;
+            }
+            self.scroll();
+        }
+        let scanner = self.scanner.clone();
+        self.move_scanner();
+
+        let cell = self.scanner[1] + Point(0, 1);
+        let previous = self.get_value_at_point(&cell);
+        let previous_case = self.cases[cell.0 as usize][cell.1 as usize];
+        self.calculate_state();
+
+        if self.history.len() >= MAX_CELL_HISTORY {
+            self.history.pop_front();
+        }
+        self.history.push_back(HistoryEntry::Cell {
+            scanner,
+            cell,
+            previous,
+            previous_case,
+        });
+    }
+    fn step_grid(&mut self) {
+        let previous = Box::new(self.points);
+        self.points = life::next_generation(&self.points);
+        self.grid_generation += 1;
+        for (heat, alive) in self
+            .heat
+            .iter_mut()
+            .flatten()
+            .zip(self.points.iter().flatten())
+        {
+            *heat += *alive as u32;
+        }
+
+        if self.history.len() >= MAX_GRID_HISTORY {
+            self.history.pop_front();
+        }
+        self.history.push_back(HistoryEntry::Grid(previous));
+    }
+    /// Moves every row up by one to make room for a new row at the bottom,
+    /// remembering the top row so that stepping back can bring it back.
+    fn scroll(&mut self) {
+        let row = self.points.iter().map(|column| column[0]).collect();
+        let cases = self.cases.iter().map(|column| column[0]).collect();
+        for column in self.points.iter_mut() {
+            column.rotate_left(1);
+            column[GRID_Y_SIZE as usize - 1] = false;
+        }
+        for column in self.cases.iter_mut() {
+            column.rotate_left(1);
+            column[GRID_Y_SIZE as usize - 1] = None;
+        }
+        for point in self.scanner.iter_mut() {
+            point.1 -= 1;
+        }
+        self.scrolled_rows += 1;
+        self.history.push_back(HistoryEntry::Scroll { row, cases });
+    }
+    /// Undoes the last step, if there is one.
+    pub fn step_back(&mut self) {
+        let Some(entry) = self.history.pop_back() else {
+            return;
+        };
+        match entry {
+            HistoryEntry::Cell {
+                scanner,
+                cell,
+                previous,
+                previous_case,
+            } => {
+                self.points[cell.0 as usize][cell.1 as usize] = previous;
+                self.cases[cell.0 as usize][cell.1 as usize] = previous_case;
+                self.scanner = scanner;
+            }
+            HistoryEntry::Grid(previous) => {
+                for (heat, alive) in self
+                    .heat
+                    .iter_mut()
+                    .flatten()
+                    .zip(self.points.iter().flatten())
+                {
+                    *heat -= *alive as u32;
+                }
+                self.points = *previous;
+                self.grid_generation -= 1;
+            }
+            HistoryEntry::Scroll { row, cases } => {
+                // The bottom row goes back to where it was before the
+                // scroll, where it is still complete.
+                for (column, alive) in self.points.iter_mut().zip(row) {
+                    column.rotate_right(1);
+                    column[0] = alive;
+                }
+                for (column, case) in self.cases.iter_mut().zip(cases) {
+                    column.rotate_right(1);
+                    column[0] = case;
+                }
+                for point in self.scanner.iter_mut() {
+                    point.1 += 1;
+                }
+                self.scrolled_rows -= 1;
+            }
+        }
+    }
+    /// The scanner writes one row below the one it reads, so it is done as
+    /// soon as its head reaches the last row. 2D automata never finish.
+    pub fn is_finished(&self) -> bool {
+        if self.automaton == Automaton::Life {
+            return false;
+        }
+        let head_position = self.scanner.first().unwrap();
+        head_position.1 >= (GRID_Y_SIZE - 1) as i32
+    }
+    /// Moves the scanner one cell along, to the start of the next row
+    /// after the last cell of one.
+    pub fn move_scanner(&mut self) {
+        let head_position = self.scanner.first().unwrap();
+
+        let mut next_head_position = *head_position + Point(1, 0);
+
+        if head_position.0 == (GRID_X_SIZE - 1) as i32 {
+            next_head_position = Point(0, head_position.1 + 1);
+        }
+
+        self.scanner.pop();
+        self.scanner.reverse();
+        self.scanner.push(next_head_position);
+        self.scanner.reverse()
+    }
+    /// Computes the cell below the middle of the scanner from the three
+    /// cells it covers.
+    pub fn calculate_state(&mut self) {
+        let pp = self.scanner.get(2).expect("Er ging iets fout");
+        let pq = self.scanner.get(1).expect("Er ging iets fout");
+        let pr = self.scanner.first().expect("Er ging iets fout");
+
+        let mut p = Self::get_value_at_point(self, pp);
+        let q = Self::get_value_at_point(self, pq);
+        let mut r = Self::get_value_at_point(self, pr);
+        let (x, y) = (pq.0 as usize, pq.1 as usize);
+        let last = GRID_X_SIZE as usize - 1;
+        match self.boundary {
+            Boundary::Helix => {}
+            Boundary::Dead if x == 0 => p = false,
+            Boundary::Dead if x == last => r = false,
+            Boundary::Wrap if x == 0 => p = self.points[last][y],
+            Boundary::Wrap if x == last => r = self.points[0][y],
+            Boundary::Dead | Boundary::Wrap => {}
+        }
+
+        let (result, neighborhood) = apply_rule(self.rule, p, q, r);
+
+        self.points[pq.0 as usize][(pq.1 + 1) as usize] = result;
+        self.cases[pq.0 as usize][(pq.1 + 1) as usize] = Some(neighborhood);
+    }
+    /// Whether the cell at `point` is alive.
+    pub fn get_value_at_point(&self, point: &Point) -> bool {
+        let point_x = point.0 as usize;
+        let point_y = point.1 as usize;
+
+        self.points[point_x][point_y]
+    }
+    /// Switches between cell and row mode.
+    pub fn toggle_step_mode(&mut self) {
+        self.step_mode = match self.step_mode {
+            StepMode::Cell => StepMode::Row,
+            StepMode::Row => StepMode::Cell,
+        }
+    }
+    /// The number of rows completed since the seed, or the number of
+    /// generations for 2D automata.
+    pub fn generation(&self) -> u64 {
+        match self.automaton {
+            Automaton::Elementary => {
+                (self.scanner[0].1 as usize - SEED_ROW) as u64 + self.scrolled_rows
+            }
+            Automaton::Life => self.grid_generation,
+        }
+    }
+    /// Every row there is, top to bottom. For elementary automata that is
+    /// the seed row and every complete row after it, in endless mode
+    /// starting with the rows that scrolled out, as far back as the
+    /// history reaches.
+    pub fn diagram(&self) -> Vec<Vec<bool>> {
+        let mut rows: Vec<Vec<bool>> = self
+            .history
+            .iter()
+            .filter_map(|entry| match entry {
+                HistoryEntry::Scroll { row, .. } => Some(row.clone()),
+                HistoryEntry::Cell { .. } | HistoryEntry::Grid(_) => None,
+            })
+            .collect();
+        let computed = self.computed_rows() as usize;
+        rows.extend((0..computed).map(|y| self.points.iter().map(|column| column[y]).collect()));
+        if self.automaton == Automaton::Life {
+            return rows;
+        }
+        // The rows above the seed row are never computed. How many of them
+        // are left depends on how many scrolled out with their history.
+        let first = self.scrolled_rows - (rows.len() - computed) as u64;
+        let above_seed = (SEED_ROW as u64).saturating_sub(first) as usize;
+        rows.split_off(above_seed.min(rows.len()))
+    }
+    /// How many rows from the top of the grid are complete, up to the last
+    /// row the scanner has finished.
+    pub fn computed_rows(&self) -> u32 {
+        match self.automaton {
+            Automaton::Elementary => (self.scanner[0].1 as u32 + 1).min(GRID_Y_SIZE),
+            Automaton::Life => GRID_Y_SIZE,
+        }
+    }
+    /// The generation the given row of the grid holds.
+    pub fn row_generation(&self, y: usize) -> u64 {
+        (y as u64 + self.scrolled_rows).saturating_sub(SEED_ROW as u64)
+    }
+    /// The neighborhood an elementary cell was computed from, or is about
+    /// to be if it is in the row the scanner is filling, as the bits of
+    /// the cells above it.
+    pub fn neighborhood_at(&self, x: usize, y: usize) -> Option<u8> {
+        if let Some(case) = self.cases[x][y] {
+            return Some(case);
+        }
+        let target_row = self.scanner[1].1 as usize + 1;
+        if self.automaton != Automaton::Elementary
+            || y != target_row
+            || x == 0
+            || x + 1 >= GRID_X_SIZE as usize
+        {
+            return None;
+        }
+        let above = |x: usize| self.points[x][y - 1] as u8;
+        Some(above(x - 1) << 2 | above(x) << 1 | above(x + 1))
+    }
+    /// A one line summary for the window title, which is kept up to date
+    /// even when nothing is drawn on top of the grid.
+    pub fn title(&self) -> String {
+        let automaton = match self.automaton {
+            Automaton::Elementary => format!("Rule {}", self.rule),
+            Automaton::Life => "Game of Life".to_string(),
+        };
+        let state = match self.state {
+            SimulationState::Playing => "playing",
+            SimulationState::Paused => "paused",
+        };
+        format!("{} — gen {} — {}", automaton, self.generation(), state)
+    }
+    /// Every row from the last complete one on, computing the next one
+    /// whenever it is asked for. It never ends: once the grid is full the
+    /// rows scroll on as if the automaton were endless, so take as many as
+    /// needed.
+    ///
+    /// ```
+    /// use cellular_automata::SimContext;
+    ///
+    /// let mut context = SimContext::new();
+    /// let rows: Vec<Vec<bool>> = context.rows().take(1000).collect();
+    /// assert_eq!(context.generation(), 999);
+    /// assert!(!context.endless);
+    /// ```
+    ///
+    /// Only elementary automata have rows, Game of Life computes whole
+    /// grids instead.
+    pub fn rows(&mut self) -> Rows<'_> {
+        Rows {
+            context: self,
+            started: false,
+        }
+    }
+    /// The number of live cells on the whole grid.
+    pub fn population(&self) -> usize {
+        self.points.iter().flatten().filter(|&&alive| alive).count()
+    }
+    /// Whether the automaton is paused.
+    pub fn is_paused(&self) -> bool {
+        matches!(self.state, SimulationState::Paused)
+    }
+    /// Pauses a playing automaton, or plays a paused one.
+    pub fn toggle_pause(&mut self) {
+        self.state = match self.state {
+            SimulationState::Playing => SimulationState::Paused,
+            SimulationState::Paused => SimulationState::Playing,
+        }
+    }
+}
+
+/// Builds a [`SimContext`] one setting at a time, from
+/// [`SimContext::builder`].
+#[derive(Clone)]
+pub struct SimContextBuilder {
+    automaton: Automaton,
+    rule: u8,
+    seed: Seed,
+    boundary: Boundary,
+    step_mode: StepMode,
+    endless: bool,
+    width: usize,
+    pattern: Option<Pattern>,
+}
+
+impl SimContextBuilder {
+    /// Which automaton runs, elementary by default.
+    pub fn automaton(mut self, automaton: Automaton) -> Self {
+        self.automaton = automaton;
+        self
+    }
+    /// The elementary rule, 30 by default.
+    pub fn rule(mut self, rule: u8) -> Self {
+        self.rule = rule;
+        self
+    }
+    /// What the automaton starts from, a single live cell by default.
+    pub fn seed(mut self, seed: Seed) -> Self {
+        self.seed = seed;
+        self
+    }
+    /// What elementary rules read past the ends of a row, the helix by
+    /// default.
+    pub fn boundary(mut self, boundary: Boundary) -> Self {
+        self.boundary = boundary;
+        self
+    }
+    /// How much a step computes, a cell by default.
+    pub fn step_mode(mut self, step_mode: StepMode) -> Self {
+        self.step_mode = step_mode;
+        self
+    }
+    /// Whether an elementary automaton keeps going past the last row, it
+    /// stops by default.
+    pub fn endless(mut self, endless: bool) -> Self {
+        self.endless = endless;
+        self
+    }
+    /// How many cells wide the grid is. It is always [`GRID_X_SIZE`], any
+    /// other width makes [`build`](Self::build) fail.
+    pub fn width(mut self, width: usize) -> Self {
+        self.width = width;
+        self
+    }
+    /// What the automaton starts from instead of the seed, in the middle
+    /// of the grid, see [`SimContext::place`].
+    pub fn pattern(mut self, pattern: Pattern) -> Self {
+        self.pattern = Some(pattern);
+        self
+    }
+    /// The automaton, paused, or [`Error::OutOfBounds`] if the width is
+    /// not the grid's or the pattern does not fit into the grid.
+    pub fn build(self) -> Result<SimContext, Error> {
+        if self.width != GRID_X_SIZE as usize {
+            return Err(Error::OutOfBounds(format!(
+                "the grid is always {} cells wide, not {}",
+                GRID_X_SIZE, self.width
+            )));
+        }
+        let mut context = SimContext::build(self.automaton, self.rule, self.seed);
+        context.boundary = self.boundary;
+        context.step_mode = self.step_mode;
+        context.endless = self.endless;
+        if let Some(pattern) = &self.pattern {
+            if pattern.width() > GRID_X_SIZE as usize
+                || pattern.height() > GRID_Y_SIZE as usize
+                || pattern.height() == 0
+            {
+                return Err(Error::OutOfBounds(format!(
+                    "a {} by {} pattern does not fit into the {} by {} grid",
+                    pattern.width(),
+                    pattern.height(),
+                    GRID_X_SIZE,
+                    GRID_Y_SIZE
+                )));
+            }
+            context.place(pattern);
+        }
+        Ok(context)
+    }
+}
+
+/// The rows of an elementary automaton one after another, from
+/// [`SimContext::rows`].
+pub struct Rows<'a> {
+    context: &'a mut SimContext,
+    started: bool,
+}
+
+impl<'a> Rows<'a> {
+    /// The rows packed into words of 64 cells, the first cell in the
+    /// highest bit of the first word and the last word padded with dead
+    /// cells.
+    pub fn packed(self) -> impl Iterator<Item = Vec<u64>> + 'a {
+        self.map(|row| {
+            row.chunks(64)
+                .map(|chunk| {
+                    chunk
+                        .iter()
+                        .enumerate()
+                        .fold(0, |word, (i, &alive)| word | (alive as u64) << (63 - i))
+                })
+                .collect()
+        })
+    }
+}
+
+impl Iterator for Rows<'_> {
+    type Item = Vec<bool>;
+
+    fn next(&mut self) -> Option<Vec<bool>> {
+        let context = &mut *self.context;
+        // A row the scanner is still on is finished before it counts.
+        // The rows scroll on past a full grid, without leaving the
+        // automaton endless for whatever steps it next.
+        if self.started || context.scanner[0].0 != 0 {
+            let (step_mode, endless) = (context.step_mode, context.endless);
+            context.step_mode = StepMode::Row;
+            context.endless = true;
+            context.advance();
+            context.step_mode = step_mode;
+            context.endless = endless;
+        }
+        self.started = true;
+        Some(context.finished_row())
+    }
+}
+
+impl Default for SimContext {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builder_rejects_what_does_not_fit() {
+        let wide = Pattern {
+            rows: vec![vec![true; GRID_X_SIZE as usize + 1]],
+        };
+        assert!(matches!(
+            SimContext::builder().pattern(wide).build(),
+            Err(Error::OutOfBounds(_))
+        ));
+        assert!(matches!(
+            SimContext::builder().width(501).build(),
+            Err(Error::OutOfBounds(_))
+        ));
+        let blinker = Pattern {
+            rows: vec![vec![true; 3]],
+        };
+        let context = SimContext::builder()
+            .width(GRID_X_SIZE as usize)
+            .pattern(blinker)
+            .build()
+            .unwrap();
+        assert_eq!(context.population(), 3);
+    }
+
+    #[test]
+    fn rows_start_with_the_seed_row() {
+        let mut context = SimContext::new();
+        let rows: Vec<Vec<bool>> = context.rows().take(3).collect();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0], Seed::Center.cells(GRID_X_SIZE as usize));
+        let live = |row: &[bool]| -> Vec<usize> { (0..row.len()).filter(|&x| row[x]).collect() };
+        assert_eq!(live(&rows[1]), [49, 50, 51]);
+        assert_eq!(live(&rows[2]), [48, 49, 52]);
+        assert_eq!(context.generation(), 2);
+    }
+
+    #[test]
+    fn rows_scroll_on_past_the_grid() {
+        let count = 3 * GRID_Y_SIZE as usize;
+        let mut context = SimContext::new();
+        let rows: Vec<Vec<bool>> = context.rows().take(count).collect();
+        assert_eq!(context.generation(), count as u64 - 1);
+        assert!(context.scrolled_rows > 0);
+        assert!(!context.endless);
+
+        // The same rows an endless automaton finishes one after another.
+        let mut endless = SimContext::builder()
+            .step_mode(StepMode::Row)
+            .endless(true)
+            .build()
+            .unwrap();
+        let mut expected = vec![endless.finished_row()];
+        for _ in 1..count {
+            endless.advance();
+            expected.push(endless.finished_row());
+        }
+        assert!(rows == expected);
+    }
+}
@@ -0,0 +1,95 @@
+//! The patterns that come with the application, to browse and drop into
+//! the grid without having to find them first.
+
+use crate::pattern::Pattern;
+use crate::Automaton;
+
+/// A named pattern together with the automaton and rule it is meant for.
+pub struct LibraryEntry {
+    /// What the library lists the pattern as.
+    pub name: &'static str,
+    /// The automaton the pattern is for.
+    pub automaton: Automaton,
+    /// The elementary rule, unused for Game of Life.
+    pub rule: u8,
+    contents: &'static str,
+    /// Whether `contents` is a plaintext pattern rather than RLE.
+    plaintext: bool,
+}
+
+impl LibraryEntry {
+    /// The cells of the pattern.
+    pub fn pattern(&self) -> Pattern {
+        match self.plaintext {
+            true => Pattern::parse_cells(self.contents),
+            false => Pattern::parse_rle(self.contents),
+        }
+        .expect("the built-in patterns are valid")
+    }
+}
+
+const fn elementary(name: &'static str, rule: u8, contents: &'static str) -> LibraryEntry {
+    LibraryEntry {
+        name,
+        automaton: Automaton::Elementary,
+        rule,
+        contents,
+        plaintext: true,
+    }
+}
+
+const fn life(name: &'static str, contents: &'static str) -> LibraryEntry {
+    LibraryEntry {
+        name,
+        automaton: Automaton::Life,
+        rule: 0,
+        contents,
+        plaintext: false,
+    }
+}
+
+/// Every pattern that comes with the application.
+pub const LIBRARY: [LibraryEntry; 12] = [
+    elementary(
+        "Rule 30 from a single cell",
+        30,
+        include_str!("../patterns/single-cell.cells"),
+    ),
+    elementary(
+        "Rule 90 Sierpinski triangle",
+        90,
+        include_str!("../patterns/single-cell.cells"),
+    ),
+    elementary(
+        "Rule 110 ether",
+        110,
+        include_str!("../patterns/rule-110-ether.cells"),
+    ),
+    elementary(
+        "Rule 110 glider moving left",
+        110,
+        include_str!("../patterns/rule-110-glider-left.cells"),
+    ),
+    elementary(
+        "Rule 110 glider moving right",
+        110,
+        include_str!("../patterns/rule-110-glider-right.cells"),
+    ),
+    elementary(
+        "Rule 110 standing glider",
+        110,
+        include_str!("../patterns/rule-110-glider-standing.cells"),
+    ),
+    life("Glider", include_str!("../patterns/glider.rle")),
+    life(
+        "Lightweight spaceship",
+        include_str!("../patterns/lwss.rle"),
+    ),
+    life(
+        "Gosper glider gun",
+        include_str!("../patterns/gosper-glider-gun.rle"),
+    ),
+    life("Pulsar", include_str!("../patterns/pulsar.rle")),
+    life("R-pentomino", include_str!("../patterns/r-pentomino.rle")),
+    life("Acorn", include_str!("../patterns/acorn.rle")),
+];
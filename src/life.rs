@@ -0,0 +1,55 @@
+//! Conway's Game of Life on the grid, whose edges are dead.
+
+use crate::seed::{Seed, SplitMix64};
+use crate::{Cells, GRID_X_SIZE, GRID_Y_SIZE};
+
+/// The R-pentomino, relative to the middle of the grid.
+const R_PENTOMINO: [(i32, i32); 5] = [(0, -1), (1, -1), (-1, 0), (0, 0), (0, 1)];
+
+/// The grid `seed` starts Game of Life from: the R-pentomino in the middle
+/// for the center seed, or cells scattered at random.
+pub fn seed_cells(seed: Seed) -> Cells {
+    let mut cells = [[false; GRID_Y_SIZE as usize]; GRID_X_SIZE as usize];
+    match seed {
+        Seed::Center => {
+            let (center_x, center_y) = (GRID_X_SIZE as i32 / 2, GRID_Y_SIZE as i32 / 2);
+            for (dx, dy) in R_PENTOMINO {
+                cells[(center_x + dx) as usize][(center_y + dy) as usize] = true;
+            }
+        }
+        Seed::Random(seed) => {
+            let mut rng = SplitMix64::new(seed);
+            for cell in cells.iter_mut().flatten() {
+                *cell = rng.next_u64().is_multiple_of(3);
+            }
+        }
+    }
+    cells
+}
+
+/// Computes the next generation of Conway's Game of Life, with the edges of
+/// the grid wrapping around.
+pub fn next_generation(cells: &Cells) -> Cells {
+    let mut next = [[false; GRID_Y_SIZE as usize]; GRID_X_SIZE as usize];
+
+    for x in 0..GRID_X_SIZE as usize {
+        for y in 0..GRID_Y_SIZE as usize {
+            next[x][y] = matches!((cells[x][y], neighbors(cells, x, y)), (true, 2) | (_, 3));
+        }
+    }
+    next
+}
+
+/// The number of live cells around `(x, y)`, wrapping around the edges.
+pub fn neighbors(cells: &Cells, x: usize, y: usize) -> u32 {
+    let (width, height) = (GRID_X_SIZE as usize, GRID_Y_SIZE as usize);
+    let mut neighbors = 0;
+    for dx in [width - 1, 0, 1] {
+        for dy in [height - 1, 0, 1] {
+            if (dx, dy) != (0, 0) && cells[(x + dx) % width][(y + dy) % height] {
+                neighbors += 1;
+            }
+        }
+    }
+    neighbors
+}
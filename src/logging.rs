@@ -0,0 +1,34 @@
+//! Logging to stderr. Warnings and errors always show, `-v` adds what
+//! the application is doing, `-vv` the details and `-vvv` everything.
+//! `RUST_LOG` wins over all of them, like `RUST_LOG=cellular_automata=debug`.
+
+use log::LevelFilter;
+
+/// Takes `-v`, `-vv`, `-vvv` and `--verbose` out of `args`, wherever they
+/// are, and starts logging at the level they add up to.
+pub fn init(args: &mut Vec<String>) {
+    let mut verbosity = 0;
+    args.retain(|arg| match arg.as_str() {
+        "--verbose" => {
+            verbosity += 1;
+            false
+        }
+        arg if arg.len() > 1 && arg.starts_with('-') && arg[1..].bytes().all(|b| b == b'v') => {
+            verbosity += arg.len() - 1;
+            false
+        }
+        _ => true,
+    });
+    let level = match verbosity {
+        0 => LevelFilter::Warn,
+        1 => LevelFilter::Info,
+        2 => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
+    };
+    env_logger::Builder::new()
+        .filter_level(level)
+        .parse_default_env()
+        .format_timestamp(None)
+        .format_target(false)
+        .init();
+}
@@ -0,0 +1,71 @@
+use crate::command::Command;
+
+/// Records commands together with the frame they were issued on, so a
+/// playback reproduces the original timing instead of firing everything at
+/// once.
+#[derive(Default)]
+pub struct MacroRecorder {
+    recording_since: Option<u64>,
+    recorded: Vec<(u64, Command)>,
+    playback_since: Option<u64>,
+    playback_cursor: usize,
+}
+
+impl MacroRecorder {
+    pub fn is_recording(&self) -> bool {
+        self.recording_since.is_some()
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playback_since.is_some()
+    }
+
+    pub fn toggle_recording(&mut self, frame: u64) {
+        self.recording_since = match self.recording_since {
+            Some(_) => None,
+            None => {
+                self.recorded.clear();
+                self.playback_since = None;
+                Some(frame)
+            }
+        }
+    }
+
+    pub fn record(&mut self, frame: u64, command: Command) {
+        if !command.is_recordable() {
+            return;
+        }
+        if let Some(start) = self.recording_since {
+            self.recorded.push((frame - start, command));
+        }
+    }
+
+    pub fn play(&mut self, frame: u64) {
+        if self.is_recording() || self.recorded.is_empty() {
+            return;
+        }
+        self.playback_since = Some(frame);
+        self.playback_cursor = 0;
+    }
+
+    /// Returns the recorded commands that are due on `frame`.
+    pub fn replay(&mut self, frame: u64) -> Vec<Command> {
+        let mut due = Vec::new();
+        let Some(start) = self.playback_since else {
+            return due;
+        };
+
+        while let Some((offset, command)) = self.recorded.get(self.playback_cursor) {
+            if start + offset > frame {
+                break;
+            }
+            due.push(*command);
+            self.playback_cursor += 1;
+        }
+
+        if self.playback_cursor == self.recorded.len() {
+            self.playback_since = None;
+        }
+        due
+    }
+}
@@ -1,215 +1,401 @@
+// Most of what the binary is made of serves the window, and lies unused
+// without it.
+#![cfg_attr(not(feature = "window"), allow(dead_code))]
+
+#[cfg(feature = "window")]
 extern crate sdl2;
 
-use core::ops::Add;
-use sdl2::event::Event;
-use sdl2::keyboard::Keycode;
-use sdl2::pixels::Color;
-use sdl2::rect::Rect;
-use sdl2::render::WindowCanvas;
-use sdl2::video::Window;
-use std::time::Duration;
+mod analyze;
+mod appearance;
+mod backend;
+mod background;
+mod bench;
+#[cfg(feature = "window")]
+mod bookmarks;
+#[cfg(feature = "window")]
+mod camera;
+mod center_column;
+mod color;
+mod coloring;
+mod command;
+mod config;
+mod export;
+#[cfg(feature = "window")]
+mod font;
+#[cfg(feature = "window")]
+mod frame_capture;
+#[cfg(feature = "window")]
+mod gif_export;
+mod image;
+mod kiosk;
+mod logging;
+#[cfg(feature = "window")]
+mod macro_recorder;
+mod midi;
+#[cfg(feature = "window")]
+mod minimap;
+mod options;
+mod presets;
+#[cfg(feature = "window")]
+mod rate_counter;
+mod remote;
+mod render;
+#[cfg(feature = "window")]
+mod renderer;
+mod report;
+mod row_stream;
+mod scale_filter;
+mod screenshot;
+mod script;
+mod serve;
+#[cfg(feature = "window")]
+mod session;
+mod state_file;
+#[cfg(feature = "window")]
+mod stdin_rows;
+mod terminal_backend;
+mod theme;
+mod tiles;
+#[cfg(feature = "window")]
+mod triangles;
+#[cfg(feature = "window")]
+mod video;
+#[cfg(feature = "window")]
+mod view;
+mod watcher;
+mod wav;
+#[cfg(feature = "window")]
+mod window_backend;
+#[cfg(feature = "window")]
+mod window_geometry;
+
+#[cfg(feature = "window")]
+use cellular_automata::{library, life, Cells, HistoryEntry};
+use cellular_automata::{
+    pattern, registry, seed, tick_rate, Automaton, Boundary, Error, Point, SimContext,
+    SimulationState, StepMode, GRID_X_SIZE, GRID_Y_SIZE, SEED_ROW,
+};
+
+use crate::analyze::Analyze;
+use crate::backend::BackendKind;
+use crate::bench::Bench;
+use crate::options::Options;
+use crate::render::Render;
+use crate::screenshot::DiagramFormat;
+use crate::terminal_backend::TerminalBackend;
+use crate::tiles::TiledExport;
+#[cfg(feature = "window")]
+use crate::window_backend::WindowBackend;
+use log::{debug, info, warn};
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::time::{Duration, Instant};
 
-const GRID_X_SIZE: u32 = 101;
-const GRID_Y_SIZE: u32 = 100;
 const DOT_SIZE_IN_PXS: u32 = 5;
-
-pub fn main() -> Result<(), String> {
-    let sdl_context = sdl2::init()?;
-    let video_subsystem = sdl_context.video()?;
-
-    let window = video_subsystem
-        .window(
-            "Cellular automata",
-            GRID_X_SIZE * DOT_SIZE_IN_PXS,
-            GRID_Y_SIZE * DOT_SIZE_IN_PXS,
-        )
-        .position_centered()
-        .opengl()
-        .build()
-        .map_err(|e| e.to_string())?;
-
-    let mut renderer = Renderer::new(window)?;
-    let mut event_pump = sdl_context.event_pump()?;
-    let mut context = SimContext::new();
-
-    let mut frame_counter = 0;
-    'running: loop {
-        for event in event_pump.poll_iter() {
-            match event {
-                Event::Quit { .. } => break 'running,
-                Event::KeyDown {
-                    keycode: Some(keycode),
-                    ..
-                } => match keycode {
-                    Keycode::Space => context.toggle_pause(),
-                    Keycode::Escape => context.toggle_pause(),
-                    _ => {}
-                },
-                _ => {}
+/// The area a single automaton is drawn to.
+const PANE_SIZE: (u32, u32) = (GRID_X_SIZE * DOT_SIZE_IN_PXS, GRID_Y_SIZE * DOT_SIZE_IN_PXS);
+const FRAMES_PER_SECOND: u32 = 60;
+const DEFAULT_STATE_FILE: &str = "state.toml";
+
+pub fn main() -> Result<(), Error> {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    logging::init(&mut args);
+    options::warn_unknown_environment();
+    match args.first().map(String::as_str) {
+        Some("run") => run(args.split_off(1)),
+        Some("render") => {
+            let Some(render) = Render::parse(args.split_off(1))? else {
+                return Ok(());
+            };
+            render.save()?;
+            println!("Saved the picture to {}", render.out.display());
+            Ok(())
+        }
+        Some("export") => match export::arguments(&args[1..])? {
+            Some(arguments) => run(arguments),
+            None => Ok(()),
+        },
+        Some("bench") => {
+            if let Some(bench) = Bench::parse(args.split_off(1))? {
+                bench.run();
             }
+            Ok(())
         }
-
-        ::std::thread::sleep(Duration::new(0, 1_000_000_000u32 / 65536));
-
-        frame_counter += 1;
-        if frame_counter % 10 == 0 {
-            context.next_tick();
-            frame_counter = 0;
+        Some("analyze") => {
+            if let Some(analyze) = Analyze::parse(args.split_off(1))? {
+                println!("{}", analyze.run());
+            }
+            Ok(())
         }
-        renderer.draw(&context)?;
+        // Without a command the window opens, as with `run`.
+        _ => run(args),
     }
-
-    Ok(())
-}
-
-pub enum SimulationState {
-    Playing,
-    Paused,
 }
 
-#[derive(Copy, Clone, Debug)]
-pub struct Point(pub i32, pub i32);
-
-pub struct SimContext {
-    pub points: [[bool; GRID_Y_SIZE as usize]; GRID_X_SIZE as usize],
-    pub scanner: Vec<Point>,
-    pub state: SimulationState,
-}
-
-impl Add<Point> for Point {
-    type Output = Point;
-
-    fn add(self, rhs: Point) -> Self::Output {
-        Point(self.0 + rhs.0, self.1 + rhs.1)
-    }
-}
-
-impl SimContext {
-    pub fn new() -> SimContext {
-        let mut cells: [[bool; GRID_Y_SIZE as usize]; GRID_X_SIZE as usize] =
-            [[false; GRID_Y_SIZE as usize]; GRID_X_SIZE as usize];
-
-        cells[GRID_X_SIZE.div_ceil(2) as usize][1] = true;
-
-        SimContext {
-            scanner: vec![Point(0, 1), Point(1, 1), Point(2, 1)],
-            points: cells,
-            state: SimulationState::Paused,
+/// Opens the window, or does what the headless options ask for instead.
+fn run(args: Vec<String>) -> Result<(), Error> {
+    // Kept for reloading the configuration, which they win over.
+    let arguments = args.clone();
+    let Some(options) = Options::parse(args)? else {
+        return Ok(());
+    };
+    debug!("Arguments {:?}", options.arguments);
+    info!(
+        "{:?} automaton, rule {}, seed {}, tick rate {}, theme {}, {:?} backend",
+        options.automaton,
+        options.rule,
+        options.seed,
+        options.tick_rate,
+        options.theme.name,
+        options.backend
+    );
+
+    let fresh = |options: &Options| {
+        let mut contexts = match options.compare.as_slice() {
+            [] => vec![configured(
+                SimContext::seeded(options.rule, options.seed),
+                options,
+            )],
+            rules => rules
+                .iter()
+                .map(|&rule| configured(SimContext::seeded(rule, options.seed), options))
+                .collect(),
+        };
+        if let Some(pattern) = &options.pattern {
+            contexts
+                .iter_mut()
+                .for_each(|context| context.place(pattern));
         }
-    }
-    pub fn next_tick(&mut self) {
-        if let SimulationState::Paused = self.state {
-            return
+        contexts
+    };
+    // The state file the automata come from, whose bookmarks come along.
+    let mut loaded_from = None;
+    let mut contexts = if options.load_state {
+        loaded_from = Some(options.state_file.clone());
+        info!("Loading the state from {}", options.state_file.display());
+        state_file::load(&options.state_file)?
+    } else if options.resume {
+        match state_file::resume() {
+            Ok(contexts) => {
+                info!("Resuming the last session");
+                loaded_from = state_file::autosave_path();
+                contexts
+            }
+            Err(e) => {
+                warn!("Starting over, {}", e);
+                fresh(&options)
+            }
         }
-        self.move_scanner();
-        self.calculate_state();
+    } else {
+        fresh(&options)
+    };
+    fs::create_dir_all(&options.output.dir)
+        .map_err(|e| Error::io("create", &options.output.dir, e))?;
+    if let Some(path) = &options.export {
+        return export_headless(contexts, &options, path);
     }
-    pub fn move_scanner(&mut self) {
-        if let SimulationState::Paused = self.state {
-            return;
-        }
-
-        let head_position = self.scanner.first().unwrap();
-
-        let mut next_head_position = *head_position + Point(1, 0);
-
-        if head_position.0 == (GRID_X_SIZE - 1) as i32 {
-            next_head_position = Point(0, head_position.1 + 1);
-        }
-
-        self.scanner.pop();
-        self.scanner.reverse();
-        self.scanner.push(next_head_position);
-        self.scanner.reverse()
+    if options.print {
+        return print_rows(contexts.remove(0), &options);
     }
-    pub fn calculate_state(&mut self) -> () {
-        let pp = self.scanner.get(2).expect("Er ging iets fout");
-        let pq = self.scanner.get(1).expect("Er ging iets fout");
-        let pr = self.scanner.get(0).expect("Er ging iets fout");
-
-        let p = Self::get_value_at_point(self, pp); 
-        let q = Self::get_value_at_point(self, pq);
-        let r = Self::get_value_at_point(self, pr);
-
-        let result = p ^ (q | r);
-
-        self.points[pq.0 as usize][(pq.1 + 1) as usize] = result;
-
+    if let Some(dir) = &options.report {
+        report::save(dir, contexts.remove(0), &options)?;
+        println!("Saved the report to {}", dir.display());
+        return Ok(());
     }
-    pub fn get_value_at_point(&self, point: &Point) -> bool {
-        let point_x = point.0 as usize;
-        let point_y = point.1 as usize;
-
-        return self.points[point_x][point_y]
+    if let Some(dir) = &options.export_tiles {
+        return export_tiles(contexts.remove(0), &options, dir);
     }
-    pub fn toggle_pause(&mut self) {
-        self.state = match self.state {
-            SimulationState::Playing => SimulationState::Paused,
-            SimulationState::Paused => SimulationState::Playing,
+    if let Some(path) = &options.export_wav {
+        return export_wav(contexts.remove(0), &options, path);
+    }
+    if let Some(path) = &options.export_midi {
+        return export_midi(contexts.remove(0), &options, path);
+    }
+    if options.center_column {
+        let bits = center_column::center_column(contexts[0].rule, options.generations as usize);
+        print!(
+            "{}",
+            center_column::format_column(&bits, options.column_format)
+        );
+        return Ok(());
+    }
+    match options.backend {
+        #[cfg(not(feature = "window"))]
+        BackendKind::Window => {
+            let _ = (arguments, loaded_from);
+            Err(Error::Config(
+                "built without the window feature, use --backend terminal or the headless \
+                 options"
+                    .to_string(),
+            ))
         }
+        #[cfg(feature = "window")]
+        BackendKind::Window => backend::run(WindowBackend::open(
+            options,
+            arguments,
+            contexts,
+            loaded_from,
+        )?),
+        BackendKind::Terminal => backend::run(TerminalBackend::new(&options, contexts)?),
     }
 }
 
-pub struct Renderer {
-    canvas: WindowCanvas,
-}
-
-impl Renderer {
-    pub fn new(window: Window) -> Result<Renderer, String> {
-        let canvas = window.into_canvas().build().map_err(|e| e.to_string())?;
-        Ok(Renderer { canvas })
-    }
-    fn draw_dot(&mut self, point: &Point) -> Result<(), String> {
-        let Point(x, y) = point;
-        self.canvas.fill_rect(Rect::new(
-            x * DOT_SIZE_IN_PXS as i32,
-            y * DOT_SIZE_IN_PXS as i32,
-            DOT_SIZE_IN_PXS,
-            DOT_SIZE_IN_PXS,
-        ))?;
-
-        Ok(())
+/// A new automaton set up as the options ask for.
+fn configured(mut context: SimContext, options: &Options) -> SimContext {
+    context.set_automaton(options.automaton);
+    context.tick_rate = options.tick_rate;
+    context.endless = options.endless;
+    if options.screensaver {
+        context.step_mode = StepMode::Row;
     }
-    pub fn draw(&mut self, context: &SimContext) -> Result<(), String> {
-        self.draw_background(context);
-        self.draw_sim(context)?;
-        self.draw_scanner(context)?;
-        self.canvas.present();
+    context
+}
 
-        Ok(())
+/// Runs `contexts` for `--generations` generations without opening a
+/// window and saves their diagrams to `path`, in the format its extension
+/// names. Compared rules each get a file with the rule in its name.
+fn export_headless(contexts: Vec<SimContext>, options: &Options, path: &Path) -> Result<(), Error> {
+    let format = DiagramFormat::of(path)?;
+    let colors = (options.theme.live_cell, options.theme.dead_cell);
+    let several = contexts.len() > 1;
+    for mut context in contexts {
+        info!(
+            "Running {} generations of rule {}",
+            options.generations, context.rule
+        );
+        let rows = match context.automaton {
+            // The rows are streamed rather than read back from the grid,
+            // whose history only reaches so far back.
+            Automaton::Elementary => {
+                let mut rows = context.diagram();
+                // The row the scanner waits at is the first one `rows`
+                // yields, a row it is still on comes after the diagram.
+                if context.scanner[0].0 == 0 {
+                    rows.pop();
+                }
+                let generations = options.generations as usize;
+                rows.extend(context.rows().take(generations + 1));
+                rows
+            }
+            Automaton::Life => {
+                for _ in 0..options.generations {
+                    context.advance();
+                }
+                context.diagram()
+            }
+        };
+        let path = if several {
+            let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+            path.with_file_name(format!("{}-{}.{}", stem, context.rule, format.extension()))
+        } else {
+            path.to_path_buf()
+        };
+        screenshot::save_diagram(&path, format, rows, &context, options.export_scale, colors)?;
+        println!("Saved the diagram to {}", path.display());
     }
+    Ok(())
+}
 
-    fn draw_background(&mut self, context: &SimContext) {
-        let color = match context.state {
-            SimulationState::Playing => Color::RGB(0, 0, 0),
-            SimulationState::Paused => Color::RGB(30, 30, 30),
-        };
-        self.canvas.set_draw_color(color);
-        self.canvas.clear();
+/// Prints every row of the elementary automaton in `context` to stdout as
+/// it is computed instead of opening a window, paced by the tick rate. It
+/// stops after `--generations` rows, or never in endless mode.
+fn print_rows(context: SimContext, options: &Options) -> Result<(), Error> {
+    match stream_rows(context, options, &mut std::io::stdout().lock()) {
+        // Whatever reads the rows, like `head`, has seen enough.
+        Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => Ok(()),
+        result => result.map_err(|e| Error::io("print to", "stdout", e)),
     }
+}
 
-    fn draw_sim(&mut self, context: &SimContext) -> Result<(), String> {
-        for (x, col) in context.points.iter().enumerate() {
-            for (y, _row) in col.iter().enumerate() {
-                let current_point = context.points[x][y];
-                match current_point {
-                    false => {},
-                    true => {
-                        self.canvas.set_draw_color(Color::WHITE);
-                        self.draw_dot(&Point(x as i32, y as i32))?;
-                    }
-                };
+fn stream_rows(
+    mut context: SimContext,
+    options: &Options,
+    out: &mut impl Write,
+) -> std::io::Result<()> {
+    let frame_duration = Duration::new(0, 1_000_000_000u32 / FRAMES_PER_SECOND);
+    let mut print = |context: &SimContext| {
+        out.write_all(screenshot::diagram_text(&[context.finished_row()]).as_bytes())?;
+        out.flush()
+    };
+    context.step_mode = StepMode::Row;
+    print(&context)?;
+    let mut printed = 0;
+    let mut frame: u64 = 0;
+    while options.endless || printed < options.generations {
+        let frame_start = Instant::now();
+        for _ in 0..context.tick_rate.ticks_on_frame(frame) {
+            context.endless |= context.is_finished();
+            context.advance();
+            print(&context)?;
+            printed += 1;
+            if !options.endless && printed == options.generations {
+                break;
             }
         }
-        Ok(())
-    }
-
-    fn draw_scanner(&mut self, context: &SimContext) -> Result<(), String> {
-        self.canvas.set_draw_color(Color::YELLOW);
-        for point in &context.scanner {
-            self.draw_dot(point)?;
+        frame += 1;
+        if let Some(remaining) = frame_duration.checked_sub(frame_start.elapsed()) {
+            ::std::thread::sleep(remaining);
         }
+    }
+    Ok(())
+}
 
-        Ok(())
+/// Runs the elementary automaton in `context` for `--generations` without
+/// opening a window and saves its rows from the seed down as PNG tiles
+/// with an index in `dir`, however many there are.
+fn export_tiles(mut context: SimContext, options: &Options, dir: &Path) -> Result<(), Error> {
+    let colors = (options.theme.live_cell, options.theme.dead_cell);
+    let mut tiles = TiledExport::create(
+        dir,
+        context.rule,
+        options.export_scale,
+        options.tile_size,
+        colors,
+    )?;
+    info!(
+        "Saving {} rows of rule {} as tiles",
+        options.generations + 1,
+        context.rule
+    );
+    for row in context.rows().take(options.generations as usize + 1) {
+        tiles.push_row(row)?;
     }
+    let index = tiles.finish()?;
+    println!("Saved the tiles with {}", index.display());
+    Ok(())
+}
+
+/// Runs the elementary automaton in `context` for `--generations` without
+/// opening a window and saves its rows from the seed down as sound.
+fn export_wav(mut context: SimContext, options: &Options, path: &Path) -> Result<(), Error> {
+    info!(
+        "Running {} generations of rule {}",
+        options.generations, context.rule
+    );
+    let rows: Vec<Vec<bool>> = context
+        .rows()
+        .take(options.generations as usize + 1)
+        .collect();
+    wav::save(path, &wav::sonify(&rows, options.wav_row_duration))?;
+    println!("Saved the sound to {}", path.display());
+    Ok(())
+}
+
+/// Runs the elementary automaton in `context` for `--generations` without
+/// opening a window and saves its rows from `--midi-from` on as notes.
+fn export_midi(mut context: SimContext, options: &Options, path: &Path) -> Result<(), Error> {
+    info!(
+        "Running {} generations of rule {}",
+        options.generations, context.rule
+    );
+    let rows: Vec<Vec<bool>> = context
+        .rows()
+        .take(options.generations as usize + 1)
+        .skip(options.midi_from as usize)
+        .collect();
+    let name = format!("Rule {}", context.rule);
+    midi::save(path, &midi::to_midi(&rows, &name))?;
+    println!("Saved the notes to {}", path.display());
+    Ok(())
 }
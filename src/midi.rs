@@ -0,0 +1,122 @@
+use crate::seed;
+use crate::Error;
+use std::fs;
+use std::path::Path;
+
+const TICKS_PER_QUARTER: u16 = 480;
+/// Every row lasts a sixteenth note.
+const TICKS_PER_ROW: u32 = TICKS_PER_QUARTER as u32 / 4;
+/// 120 beats per minute, in microseconds per quarter note.
+const TEMPO: u32 = 500_000;
+/// The note the middle column plays, middle C.
+const MIDDLE_NOTE: i32 = 60;
+const VELOCITY: u8 = 80;
+
+/// Turns `rows` into a standard MIDI file with a single track, a
+/// sixteenth note per row. Every column plays a note a semitone above the
+/// one to its left, with the middle one at middle C, held for as long as
+/// its cell stays alive from row to row. `name` goes into the track name.
+pub fn to_midi(rows: &[Vec<bool>], name: &str) -> Vec<u8> {
+    let width = rows.first().map_or(0, Vec::len);
+    let note = |x: usize| (MIDDLE_NOTE + x as i32 - seed::center(width) as i32).clamp(0, 127) as u8;
+
+    let mut track = Vec::new();
+    // The tempo and the name, before the first note.
+    track.extend_from_slice(&[0x00, 0xff, 0x51, 0x03]);
+    track.extend_from_slice(&TEMPO.to_be_bytes()[1..]);
+    track.extend_from_slice(&[0x00, 0xff, 0x03]);
+    push_variable(&mut track, name.len() as u32);
+    track.extend_from_slice(name.as_bytes());
+
+    let silence = vec![false; width];
+    let mut playing = &silence;
+    let mut last_event = 0;
+    let ends = rows.iter().chain([&silence]);
+    for (index, row) in ends.enumerate() {
+        let time = index as u32 * TICKS_PER_ROW;
+        let changed = (0..width).filter(|&x| playing[x] != row[x]);
+        // The notes that end come first, so one column ending and another
+        // starting at the same time never overlap.
+        let (mut ending, starting): (Vec<usize>, Vec<usize>) = changed.partition(|&x| playing[x]);
+        ending.extend(starting);
+        for x in ending {
+            push_variable(&mut track, time - last_event);
+            last_event = time;
+            let (status, velocity) = match row[x] {
+                true => (0x90, VELOCITY),
+                false => (0x80, 0),
+            };
+            track.extend_from_slice(&[status, note(x), velocity]);
+        }
+        playing = row;
+    }
+    track.extend_from_slice(&[0x00, 0xff, 0x2f, 0x00]);
+
+    let mut midi = Vec::with_capacity(22 + track.len());
+    midi.extend_from_slice(b"MThd");
+    midi.extend_from_slice(&6u32.to_be_bytes());
+    // A single track.
+    midi.extend_from_slice(&0u16.to_be_bytes());
+    midi.extend_from_slice(&1u16.to_be_bytes());
+    midi.extend_from_slice(&TICKS_PER_QUARTER.to_be_bytes());
+    midi.extend_from_slice(b"MTrk");
+    midi.extend_from_slice(&(track.len() as u32).to_be_bytes());
+    midi.extend_from_slice(&track);
+    midi
+}
+
+pub fn save(path: &Path, midi: &[u8]) -> Result<(), Error> {
+    fs::write(path, midi).map_err(|e| Error::io("write", path, e))
+}
+
+/// Appends `value` seven bits to a byte, the highest first, with the top
+/// bit set on all bytes but the last.
+fn push_variable(bytes: &mut Vec<u8>, value: u32) {
+    let mut groups = vec![(value & 0x7f) as u8];
+    let mut rest = value >> 7;
+    while rest > 0 {
+        groups.push((rest & 0x7f) as u8 | 0x80);
+        rest >>= 7;
+    }
+    bytes.extend(groups.into_iter().rev());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunk_lengths_add_up() {
+        let rows = vec![vec![true, false, true], vec![true, true, false]];
+        let midi = to_midi(&rows, "test");
+        let length = |at: usize| u32::from_be_bytes(midi[at..at + 4].try_into().unwrap());
+        assert_eq!(&midi[0..4], b"MThd");
+        assert_eq!(length(4), 6);
+        assert_eq!(midi[8..14], [0, 0, 0, 1, 0x01, 0xe0]);
+        assert_eq!(&midi[14..18], b"MTrk");
+        assert_eq!(length(18) as usize, midi.len() - 22);
+
+        let mut track = vec![0x00, 0xff, 0x51, 0x03, 0x07, 0xa1, 0x20];
+        track.extend_from_slice(&[0x00, 0xff, 0x03, 0x04]);
+        track.extend_from_slice(b"test");
+        // The outer columns start, the right one hands over to the middle
+        // one a sixteenth later, and the rest end after another.
+        track.extend_from_slice(&[0x00, 0x90, 59, VELOCITY, 0x00, 0x90, 61, VELOCITY]);
+        track.extend_from_slice(&[0x78, 0x80, 61, 0, 0x00, 0x90, 60, VELOCITY]);
+        track.extend_from_slice(&[0x78, 0x80, 59, 0, 0x00, 0x80, 60, 0]);
+        track.extend_from_slice(&[0x00, 0xff, 0x2f, 0x00]);
+        assert_eq!(midi[22..], track[..]);
+    }
+
+    #[test]
+    fn writes_variable_lengths() {
+        let mut bytes = Vec::new();
+        for value in [0, 0x7f, 0x80, 200, 0x0fff_ffff] {
+            push_variable(&mut bytes, value);
+        }
+        assert_eq!(
+            bytes,
+            [0x00, 0x7f, 0x81, 0x00, 0x81, 0x48, 0xff, 0xff, 0xff, 0x7f]
+        );
+    }
+}
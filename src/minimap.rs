@@ -0,0 +1,55 @@
+use crate::color::Color;
+use crate::{Cells, GRID_X_SIZE, GRID_Y_SIZE};
+
+const BYTES_PER_PIXEL: usize = 3;
+
+/// A picture of the whole grid with one RGB pixel per cell. Only the
+/// cells that changed since the last update are repainted, unless the
+/// colors changed too.
+pub struct Minimap {
+    cells: Box<Cells>,
+    colors: Option<(Color, Color)>,
+    pixels: Vec<u8>,
+}
+
+impl Minimap {
+    pub fn new() -> Minimap {
+        Minimap {
+            cells: Box::new([[false; GRID_Y_SIZE as usize]; GRID_X_SIZE as usize]),
+            colors: None,
+            pixels: vec![0; GRID_X_SIZE as usize * GRID_Y_SIZE as usize * BYTES_PER_PIXEL],
+        }
+    }
+
+    pub fn update(&mut self, cells: &Cells, live: Color, dead: Color) {
+        let repaint_all = self.colors != Some((live, dead));
+        self.colors = Some((live, dead));
+        for (x, column) in cells.iter().enumerate() {
+            for (y, &alive) in column.iter().enumerate() {
+                if !repaint_all && self.cells[x][y] == alive {
+                    continue;
+                }
+                self.cells[x][y] = alive;
+                let color = if alive { live } else { dead };
+                let index = (y * GRID_X_SIZE as usize + x) * BYTES_PER_PIXEL;
+                self.pixels[index..index + BYTES_PER_PIXEL]
+                    .copy_from_slice(&[color.r, color.g, color.b]);
+            }
+        }
+    }
+
+    /// The pixels row by row, ready to be uploaded as an RGB24 texture.
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    pub fn pitch(&self) -> usize {
+        GRID_X_SIZE as usize * BYTES_PER_PIXEL
+    }
+}
+
+impl Default for Minimap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
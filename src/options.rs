@@ -0,0 +1,1264 @@
+use crate::backend::BackendKind;
+use crate::background::{Background, BackgroundMode};
+use crate::center_column::ColumnFormat;
+use crate::color::Color;
+use crate::coloring::Palette;
+use crate::command::{Command, KeyMap};
+use crate::config::{self, Config};
+use crate::kiosk::{self, Preset};
+use crate::pattern::Pattern;
+use crate::presets;
+use crate::registry::{Mode, Registry};
+use crate::remote::ControlSource;
+use crate::row_stream::StreamFormat;
+use crate::scale_filter::ScaleFilter;
+use crate::screenshot::{DiagramFormat, OutputNames};
+use crate::script::Script;
+use crate::seed::Seed;
+use crate::serve::ServeFormat;
+use crate::theme::{self, Theme};
+use crate::tick_rate::TickRate;
+use crate::Error;
+use crate::{Automaton, DEFAULT_STATE_FILE, GRID_X_SIZE, GRID_Y_SIZE, PANE_SIZE, SEED_ROW};
+use clap::builder::{BoolishValueParser, RangedU64ValueParser, TypedValueParser};
+use clap::error::ErrorKind;
+use clap::parser::ValueSource;
+use clap::{value_parser, ArgAction, ArgGroup, ArgMatches, CommandFactory, FromArgMatches, Parser};
+use log::{info, warn};
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// How long every row sounds in `--export-wav`.
+const DEFAULT_WAV_ROW_DURATION: Duration = Duration::from_millis(100);
+
+/// The largest tiles `--export-tiles` saves, in pixels on a side.
+const DEFAULT_TILE_SIZE: u32 = 4096;
+
+/// What `--help` lists before the options.
+const COMMANDS: &str = "Commands:
+  run                  Open the window, also without a command
+  render               Draw a wallpaper sized picture of a rule without
+                       opening a window, see 'render --help'
+  export               Save the rows as a diagram, tiles, a report, sound
+                       or notes, or print them, see 'export --help'
+  bench                Measure how fast generations are computed, see
+                       'bench --help'
+  analyze              Print the population, the center column and the
+                       period of a rule, see 'analyze --help'";
+
+/// What `--help` lists after the options.
+const AFTER_HELP: &str = "\
+Options without a value, like --kiosk, also take true or false, so
+--kiosk=false turns off what the configuration file turned on.
+-v, -vv and -vvv log what happens to stderr, in more detail with every v,
+on top of the warnings and errors that always show, RUST_LOG wins over
+them.
+
+Every option can also be set by an environment variable named after it,
+like RULE30_TICK_RATE=4 or RULE30_HEADLESS=1. Those win over the
+configuration file, and the command line wins over both.
+
+The grid is always 101 cells wide and 100 rows high, no option sets its
+size. The size in the configuration file is that of the picture render
+draws.";
+
+/// The options of `run` as the command line gives them, before they are
+/// checked against each other and loaded. Options with a default in
+/// [`Options::default`] are `None` when not given.
+#[derive(Parser)]
+#[command(
+    name = "cellular_automata",
+    override_usage = "cellular_automata [run] [OPTIONS]
+       cellular_automata render [OPTIONS] --out FILE
+       cellular_automata export KIND [TARGET] [OPTIONS]
+       cellular_automata bench [OPTIONS]
+       cellular_automata analyze [OPTIONS]",
+    help_template = "{usage-heading} {usage}\n\n{before-help}Options:\n{options}{after-help}",
+    before_help = COMMANDS,
+    after_help = AFTER_HELP,
+    disable_version_flag = true,
+    args_override_self = true,
+    next_line_help = true
+)]
+#[command(group = ArgGroup::new("unattended").args(["kiosk", "screensaver"]).multiple(true))]
+#[command(group = ArgGroup::new("outputs").args([
+    "print",
+    "center_column",
+    "export",
+    "export_tiles",
+    "report",
+    "export_wav",
+    "export_midi",
+]))]
+struct Args {
+    /// The automaton to run, 'elementary', 'life', a rule known by name
+    /// ('sierpinski', 'rule110', 'traffic') or one the configuration file
+    /// or --script names [default: elementary]
+    #[arg(verbatim_doc_comment, long, value_name = "MODE")]
+    mode: Option<String>,
+    /// The elementary rule to start with, 0 to 255 [default: 30]
+    #[arg(verbatim_doc_comment, long, value_name = "RULE", value_parser = parse_rule, conflicts_with = "compare")]
+    rule: Option<u8>,
+    /// 'center' for a single live cell, or a number to draw a random
+    /// first row or grid from [default: center]
+    #[arg(verbatim_doc_comment, long, value_name = "SEED")]
+    seed: Option<Seed>,
+    /// Run K ticks every frame, or one tick every K frames
+    #[arg(verbatim_doc_comment, long, value_name = "K|1/K")]
+    tick_rate: Option<TickRate>,
+    /// Take the rule, seed, theme and tick rate of a preset from the
+    /// configuration file, the options for them win over it
+    #[arg(verbatim_doc_comment, long, value_name = "NAME")]
+    preset: Option<String>,
+    /// Run two to four comma separated rules side by side, from the same
+    /// seed and in lockstep
+    #[arg(
+        verbatim_doc_comment,
+        long,
+        value_name = "RULES",
+        value_delimiter = ',',
+        value_parser = parse_rule,
+        conflicts_with_all = [
+            "unattended",
+            "load_state",
+            "resume",
+            "print",
+            "export_tiles",
+            "report",
+            "export_wav",
+            "export_midi",
+        ]
+    )]
+    compare: Vec<u8>,
+    /// Open another window running RULE[:SEED], can be given more than
+    /// once
+    #[arg(
+        verbatim_doc_comment,
+        long,
+        value_name = "PRESET",
+        conflicts_with = "screensaver"
+    )]
+    window: Vec<Preset>,
+    /// Keep going past the last row, scrolling the grid up
+    #[arg(verbatim_doc_comment, long)]
+    endless: bool,
+    /// Start from an RLE, .cells or .mc pattern in the middle of the
+    /// grid, elementary automata take its first row, and start over
+    /// whenever it changes
+    #[arg(verbatim_doc_comment, long, value_name = "FILE")]
+    pattern: Option<PathBuf>,
+    /// Run a Rhai script that defines the rule as rule(left, center,
+    /// right) or reacts to every generation in on_generation(sim), see
+    /// the README
+    #[arg(verbatim_doc_comment, long, value_name = "FILE")]
+    script: Option<PathBuf>,
+    /// Run unattended, cycling through the playlist
+    #[arg(verbatim_doc_comment, long)]
+    kiosk: bool,
+    /// How long kiosk mode shows every preset [default: 30]
+    #[arg(verbatim_doc_comment, long, value_name = "SECONDS")]
+    dwell: Option<u64>,
+    /// Comma separated RULE[:SEED] presets for kiosk mode, where SEED is
+    /// 'center' or a number
+    #[arg(
+        verbatim_doc_comment,
+        long,
+        value_name = "PRESETS",
+        value_delimiter = ','
+    )]
+    playlist: Option<Vec<Preset>>,
+    /// A built-in theme (classic, paper, colorblind, colorblind-light,
+    /// amber, matrix, ocean) or a TOML theme file [default: classic or
+    /// paper, following the system's dark or light preference]
+    #[arg(verbatim_doc_comment, long, value_name = "NAME|FILE")]
+    theme: Option<String>,
+    /// Derive the theme from the dominant colors of a PNG
+    #[arg(
+        verbatim_doc_comment,
+        long,
+        value_name = "FILE",
+        conflicts_with = "theme"
+    )]
+    theme_from_image: Option<PathBuf>,
+    /// Draw a PNG image stretched over the grid
+    #[arg(verbatim_doc_comment, long, value_name = "FILE")]
+    background_image: Option<PathBuf>,
+    /// Show the image behind the cells, or only through the live ones
+    /// [default: behind]
+    #[arg(verbatim_doc_comment, long, value_name = "behind|mask")]
+    background_mode: Option<BackgroundMode>,
+    /// The gradient for coloring by row and heatmaps (viridis, cividis,
+    /// magma, rainbow, grayscale) [default: viridis]
+    #[arg(verbatim_doc_comment, long, value_name = "NAME")]
+    palette: Option<Palette>,
+    /// Draw the scanner in #rrggbb instead of the theme's color
+    #[arg(verbatim_doc_comment, long, value_name = "COLOR", value_parser = theme::parse_color)]
+    scanner_color: Option<Color>,
+    /// Start with the scanner hidden
+    #[arg(verbatim_doc_comment, long)]
+    hide_scanner: bool,
+    /// The length of the fading trail behind the scanner, 0 to disable it
+    /// [default: 8]
+    #[arg(verbatim_doc_comment, long, value_name = "CELLS")]
+    scanner_trail: Option<u32>,
+    /// Open the window without a border
+    #[arg(verbatim_doc_comment, long)]
+    borderless: bool,
+    /// Keep the window above all others
+    #[arg(verbatim_doc_comment, long)]
+    always_on_top: bool,
+    /// Start with scanlines, a vignette and a phosphor glow
+    #[arg(verbatim_doc_comment, long)]
+    crt: bool,
+    /// Scale textures like the minimap and the background image crisply
+    /// or smoothly [default: nearest]
+    #[arg(verbatim_doc_comment, long, value_name = "nearest|linear")]
+    scale_filter: Option<ScaleFilter>,
+    /// Enlarge the window by FACTOR [default: following the display's
+    /// DPI]
+    #[arg(verbatim_doc_comment, long, value_name = "FACTOR", value_parser = parse_scale)]
+    scale: Option<f32>,
+    /// Where S, Ctrl+S and R save to, relative --export and --export-gif
+    /// files go and F8 saves to without --state [default: .]
+    // --screenshot-dir is what --output-dir used to be called.
+    #[arg(
+        verbatim_doc_comment,
+        long,
+        value_name = "DIR",
+        alias = "screenshot-dir"
+    )]
+    output_dir: Option<PathBuf>,
+    /// What S, Ctrl+S and R name files, from {kind}, {rule}, {seed} and
+    /// {timestamp} [default: {kind}-{timestamp}]
+    #[arg(verbatim_doc_comment, long, value_name = "TEMPLATE")]
+    name_template: Option<String>,
+    /// The pixels per cell Ctrl+S saves the diagram with [default: 1]
+    #[arg(verbatim_doc_comment, long, value_name = "N", value_parser = value_parser!(u32).range(1..))]
+    export_scale: Option<u32>,
+    /// The format Ctrl+S saves the diagram in [default: png]
+    #[arg(
+        verbatim_doc_comment,
+        long,
+        value_name = "png|svg|rle|cells|mc|csv|bin|json|npy"
+    )]
+    diagram_format: Option<DiagramFormat>,
+    /// Save the diagram to FILE without opening a window, in the format
+    /// its extension names
+    #[arg(
+        verbatim_doc_comment,
+        long,
+        value_name = "FILE",
+        value_parser = parse_export,
+        conflicts_with = "unattended"
+    )]
+    export: Option<PathBuf>,
+    /// How many generations --export runs first, --print prints or
+    /// --center-column covers [default: enough to fill the grid]
+    #[arg(verbatim_doc_comment, long, value_name = "N")]
+    generations: Option<u64>,
+    /// Print the center column of the rule grown from a single cell on an
+    /// endless row for --generations, instead of opening a window
+    #[arg(verbatim_doc_comment, long, conflicts_with = "unattended")]
+    center_column: bool,
+    /// How --center-column and K write the column [default: bits]
+    #[arg(verbatim_doc_comment, long, value_name = "bits|hex")]
+    column_format: Option<ColumnFormat>,
+    /// Save --generations rows from the seed down as PNG tiles with an
+    /// index.json in DIR, without opening a window
+    #[arg(
+        verbatim_doc_comment,
+        long,
+        value_name = "DIR",
+        conflicts_with = "unattended"
+    )]
+    export_tiles: Option<PathBuf>,
+    /// How large the tiles get at most [default: 4096]
+    #[arg(verbatim_doc_comment, long, value_name = "PX", value_parser = value_parser!(u32).range(1..))]
+    tile_size: Option<u32>,
+    /// Run --generations and save the diagram, the command line, the
+    /// starting state and per generation statistics to DIR, without
+    /// opening a window
+    #[arg(
+        verbatim_doc_comment,
+        long,
+        value_name = "DIR",
+        conflicts_with = "unattended"
+    )]
+    report: Option<PathBuf>,
+    /// Save --generations rows from the seed down as sound to a WAV file,
+    /// without opening a window, every live cell sounding at the pitch of
+    /// its column
+    #[arg(
+        verbatim_doc_comment,
+        long,
+        value_name = "FILE",
+        conflicts_with = "unattended"
+    )]
+    export_wav: Option<PathBuf>,
+    /// How long every row sounds [default: 100]
+    #[arg(verbatim_doc_comment, long, value_name = "MS", value_parser = value_parser!(u64).range(1..))]
+    wav_row_ms: Option<u64>,
+    /// Save the rows from --midi-from to --generations as a MIDI file,
+    /// without opening a window, every column playing a note of its own
+    #[arg(
+        verbatim_doc_comment,
+        long,
+        value_name = "FILE",
+        conflicts_with = "unattended"
+    )]
+    export_midi: Option<PathBuf>,
+    /// The first generation --export-midi plays [default: 0]
+    #[arg(verbatim_doc_comment, long, value_name = "N")]
+    midi_from: Option<u64>,
+    /// Print the rows to the terminal as they are computed instead of
+    /// opening a window, forever with --endless
+    #[arg(verbatim_doc_comment, long, conflicts_with = "unattended")]
+    print: bool,
+    /// Never open a window, printing the rows like --print unless
+    /// something is exported
+    #[arg(
+        verbatim_doc_comment,
+        long,
+        conflicts_with_all = ["stdin", "stream", "control", "serve", "unattended", "window"]
+    )]
+    headless: bool,
+    /// Show the automata in the window with every control, or only play
+    /// them in the terminal, with the keys of the window
+    /// [default: window]
+    #[arg(verbatim_doc_comment, long, value_name = "window|terminal")]
+    backend: Option<BackendKind>,
+    /// Write every row to stdout once it is completed, as a line of 0s
+    /// and 1s or packed into bytes, while the window shows it. Nothing
+    /// else goes to stdout, messages go to stderr
+    #[arg(verbatim_doc_comment, long, value_name = "text|bin", conflicts_with_all = ["print", "center_column"])]
+    stream: Option<StreamFormat>,
+    /// Show rows of 0s and 1s piped in on stdin instead of computing
+    /// them, as a viewer for other programs
+    #[arg(
+        verbatim_doc_comment,
+        long,
+        conflicts_with_all = ["outputs", "compare", "pattern", "load_state", "resume", "unattended"]
+    )]
+    stdin: bool,
+    /// Whether the rows come as lines, or packed into bytes [default:
+    /// text]
+    #[arg(verbatim_doc_comment, long, value_name = "text|bin")]
+    stdin_format: Option<StreamFormat>,
+    /// How many cells a packed row has [default: 101]
+    #[arg(
+        verbatim_doc_comment,
+        long,
+        value_name = "CELLS",
+        value_parser = RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    stdin_width: Option<usize>,
+    /// Take commands like 'pause', 'step', 'set-rule 110' or 'screenshot'
+    /// for the first window, a line each, from stdin or from a Unix
+    /// socket created at SOCKET
+    #[arg(verbatim_doc_comment, long, value_name = "stdin|SOCKET")]
+    control: Option<ControlSource>,
+    /// Mirror the first window to WebSocket clients, which can send
+    /// commands like --control takes, on localhost unless a host is given.
+    /// Browsers only connect from pages on localhost, but any other
+    /// client that reaches a host besides localhost has full control
+    #[arg(verbatim_doc_comment, long, value_name = "PORT|HOST:PORT", value_parser = serve_address)]
+    serve: Option<String>,
+    /// Whether the rows go out as JSON, or packed into binary messages
+    /// [default: json]
+    #[arg(verbatim_doc_comment, long, value_name = "json|bin")]
+    serve_format: Option<ServeFormat>,
+    /// Record the generations into an animated GIF, with --export-scale
+    /// pixels per cell
+    #[arg(verbatim_doc_comment, long, value_name = "FILE")]
+    export_gif: Option<PathBuf>,
+    /// Record only every Nth generation [default: 1]
+    #[arg(verbatim_doc_comment, long, value_name = "N", value_parser = value_parser!(u64).range(1..))]
+    gif_every: Option<u64>,
+    /// How long every frame of the GIF shows [default: 100]
+    #[arg(verbatim_doc_comment, long, value_name = "MS")]
+    gif_delay: Option<u32>,
+    /// Save a PNG of the window every N generations, for a timelapse
+    #[arg(verbatim_doc_comment, long, value_name = "N", value_parser = value_parser!(u64).range(1..))]
+    capture_every: Option<u64>,
+    /// The format R records videos in, like mp4 or webm, using ffmpeg
+    /// [default: mp4]
+    #[arg(verbatim_doc_comment, long, value_name = "EXT")]
+    video_format: Option<String>,
+    /// Where F8 saves the automata to and F9 loads them from [default:
+    /// state.toml]
+    #[arg(verbatim_doc_comment, long, value_name = "FILE")]
+    state: Option<PathBuf>,
+    /// Carry on with the automata saved when the last session ended
+    #[arg(verbatim_doc_comment, long, conflicts_with_all = ["load_state", "unattended"])]
+    resume: bool,
+    /// Start from automata saved to FILE, which F8 and F9 then use too
+    #[arg(
+        verbatim_doc_comment,
+        long,
+        value_name = "FILE",
+        conflicts_with_all = ["state", "unattended"]
+    )]
+    load_state: Option<PathBuf>,
+    /// What the Escape key does [default: quit]
+    #[arg(verbatim_doc_comment, long, value_name = "quit|pause", value_parser = ["quit", "pause"], hide_possible_values = true)]
+    escape: Option<String>,
+    /// Require pressing quit twice in a row
+    #[arg(verbatim_doc_comment, long)]
+    confirm_quit: bool,
+    /// Run fullscreen with random rules, quit on any input, also given as
+    /// /s
+    #[arg(verbatim_doc_comment, long)]
+    screensaver: bool,
+    /// Read defaults for these options from FILE instead of
+    /// rule30/config.toml in the platform's configuration directory, like
+    /// ~/.config/rule30/config.toml on Linux
+    #[arg(verbatim_doc_comment, long, value_name = "FILE")]
+    config: Option<PathBuf>,
+}
+
+/// Options given on the command line.
+pub struct Options {
+    pub automaton: Automaton,
+    pub rule: u8,
+    pub seed: Seed,
+    pub tick_rate: TickRate,
+    pub theme: Theme,
+    /// The file the theme came from, watched for changes.
+    pub theme_file: Option<PathBuf>,
+    /// The Rhai script, which registers its rule as a mode and reacts to
+    /// generations.
+    pub script: Option<Script>,
+    /// The modes `--mode` picks from.
+    pub registry: Registry,
+    pub palette: Palette,
+    pub background: Option<Background>,
+    pub scanner_color: Option<Color>,
+    pub hide_scanner: bool,
+    pub scanner_trail: u32,
+    pub borderless: bool,
+    pub always_on_top: bool,
+    pub crt: bool,
+    /// How much to enlarge the window, `None` to follow the display.
+    pub scale: Option<f32>,
+    pub scale_filter: ScaleFilter,
+    /// Where the files the application names itself go and their names.
+    pub output: OutputNames,
+    pub export_scale: u32,
+    pub diagram_format: DiagramFormat,
+    pub export_gif: Option<PathBuf>,
+    /// Where to save the diagram to instead of opening a window.
+    pub export: Option<PathBuf>,
+    pub generations: u64,
+    /// Whether to print the rows to stdout instead of opening a window.
+    pub print: bool,
+    /// Whether to never open a window.
+    pub headless: bool,
+    /// What shows the automata.
+    pub backend: BackendKind,
+    /// How to write the rows to stdout while a window shows them.
+    pub stream: Option<StreamFormat>,
+    /// Whether to show rows read from stdin instead of computing them.
+    pub stdin: bool,
+    pub stdin_format: StreamFormat,
+    pub stdin_width: usize,
+    /// Where commands for the first window come from.
+    pub control: Option<ControlSource>,
+    /// Where to serve the first window on.
+    pub serve: Option<String>,
+    pub serve_format: ServeFormat,
+    /// Where to save the rows as tiles to instead of opening a window.
+    pub export_tiles: Option<PathBuf>,
+    pub tile_size: u32,
+    /// Where to save the rows as sound to instead of opening a window.
+    pub export_wav: Option<PathBuf>,
+    pub wav_row_duration: Duration,
+    /// Where to save the rows as notes to instead of opening a window.
+    pub export_midi: Option<PathBuf>,
+    pub midi_from: u64,
+    /// Where to save a report of the run to instead of opening a window.
+    pub report: Option<PathBuf>,
+    /// The command line the options were parsed from.
+    pub arguments: Vec<String>,
+    /// Whether to print the center column instead of opening a window.
+    pub center_column: bool,
+    pub column_format: ColumnFormat,
+    pub video_format: String,
+    /// How many generations apart to save pictures of the window.
+    pub capture_every: Option<u64>,
+    pub state_file: PathBuf,
+    /// Whether to start from the automata saved to `state_file`.
+    pub load_state: bool,
+    /// Whether to start from the automata saved on the last exit.
+    pub resume: bool,
+    pub gif_every: u64,
+    pub gif_delay_ms: u32,
+    pub escape: Command,
+    /// The keys and the commands they give, apart from escape.
+    pub keys: KeyMap,
+    /// The presets of the configuration file, in its order.
+    pub presets: Vec<presets::Preset>,
+    /// The preset given with `--preset`.
+    pub preset: Option<usize>,
+    pub confirm_quit: bool,
+    /// The rules to compare side by side, empty for a single automaton.
+    pub compare: Vec<u8>,
+    /// Presets for the windows to open next to the main one.
+    pub windows: Vec<Preset>,
+    pub endless: bool,
+    pub pattern: Option<Pattern>,
+    /// The file the pattern came from, watched for changes.
+    pub pattern_file: Option<PathBuf>,
+    pub kiosk: bool,
+    pub screensaver: bool,
+    pub dwell: Duration,
+    pub playlist: Vec<Preset>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            automaton: Automaton::Elementary,
+            rule: 30,
+            seed: Seed::Center,
+            tick_rate: TickRate::default(),
+            theme: Theme::system_default(),
+            theme_file: None,
+            script: None,
+            registry: Registry::built_in(),
+            palette: Palette::Viridis,
+            background: None,
+            scanner_color: None,
+            hide_scanner: false,
+            scanner_trail: 8,
+            borderless: false,
+            always_on_top: false,
+            crt: false,
+            scale: None,
+            scale_filter: ScaleFilter::Nearest,
+            output: OutputNames::default(),
+            export_scale: 1,
+            diagram_format: DiagramFormat::Png,
+            export_gif: None,
+            export: None,
+            capture_every: None,
+            print: false,
+            headless: false,
+            backend: BackendKind::Window,
+            stream: None,
+            stdin: false,
+            stdin_format: StreamFormat::Text,
+            stdin_width: GRID_X_SIZE as usize,
+            control: None,
+            serve: None,
+            serve_format: ServeFormat::Json,
+            export_tiles: None,
+            tile_size: DEFAULT_TILE_SIZE,
+            center_column: false,
+            report: None,
+            export_wav: None,
+            export_midi: None,
+            midi_from: 0,
+            wav_row_duration: DEFAULT_WAV_ROW_DURATION,
+            arguments: Vec::new(),
+            column_format: ColumnFormat::Bits,
+            generations: (GRID_Y_SIZE as usize - 1 - SEED_ROW) as u64,
+            video_format: "mp4".to_string(),
+            state_file: PathBuf::from(DEFAULT_STATE_FILE),
+            load_state: false,
+            resume: false,
+            gif_every: 1,
+            gif_delay_ms: 100,
+            escape: Command::Quit,
+            keys: KeyMap::default(),
+            presets: Vec::new(),
+            preset: None,
+            confirm_quit: false,
+            compare: Vec::new(),
+            windows: Vec::new(),
+            endless: false,
+            pattern: None,
+            pattern_file: None,
+            kiosk: false,
+            screensaver: false,
+            dwell: Duration::from_secs(30),
+            playlist: kiosk::default_playlist(),
+        }
+    }
+}
+
+impl Options {
+    /// Parses the arguments, without the program name, after the options
+    /// of the configuration file, which they override. Returns `None` when
+    /// only the usage was requested.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Option<Options>, Error> {
+        let mut cli = Vec::new();
+        for arg in args {
+            match arg.as_str() {
+                "/s" | "/S" => cli.push("--screensaver".to_string()),
+                // Windows asks screensavers for a configuration dialog or a
+                // preview window with these, neither of which exist.
+                arg if arg.starts_with("/c") || arg.starts_with("/p") => return Ok(None),
+                _ => cli.push(arg),
+            }
+        }
+        let Some(mut layered) = parse_layered(command(), cli)? else {
+            return Ok(None);
+        };
+        let mut registry = Registry::built_in();
+        let (keys, presets) = match layered.config.take() {
+            Some((path, config)) => {
+                let invalid =
+                    |e: Error| Error::Config(format!("invalid config '{}': {}", path.display(), e));
+                let keys = KeyMap::new(&config.keys).map_err(invalid)?;
+                let presets = config
+                    .presets
+                    .iter()
+                    .map(|(name, arguments)| presets::Preset::parse(name, arguments))
+                    .collect::<Result<_, _>>()
+                    .map_err(invalid)?;
+                for (name, rule) in &config.modes {
+                    let mode = Mode::rule(name, *rule, "From the configuration file");
+                    registry.register(mode).map_err(invalid)?;
+                }
+                (keys, presets)
+            }
+            None => (KeyMap::default(), Vec::new()),
+        };
+        let args =
+            Args::from_arg_matches(&layered.matches).map_err(|e| Error::Config(e.to_string()))?;
+        let source = |id: &str| layered.source(id);
+        let mut rule_source = source("rule");
+        let defaults = Options::default();
+        let mut options = Options {
+            arguments: layered.arguments.clone(),
+            keys,
+            presets,
+            registry,
+            automaton: defaults.automaton,
+            rule: args.rule.unwrap_or(defaults.rule),
+            seed: args.seed.unwrap_or(defaults.seed),
+            tick_rate: args.tick_rate.unwrap_or(defaults.tick_rate),
+            theme: defaults.theme,
+            theme_file: None,
+            script: None,
+            palette: args.palette.unwrap_or(defaults.palette),
+            background: None,
+            scanner_color: args.scanner_color,
+            hide_scanner: args.hide_scanner,
+            scanner_trail: args.scanner_trail.unwrap_or(defaults.scanner_trail),
+            borderless: args.borderless,
+            always_on_top: args.always_on_top,
+            crt: args.crt,
+            scale: args.scale,
+            scale_filter: args.scale_filter.unwrap_or(defaults.scale_filter),
+            output: defaults.output,
+            export_scale: args.export_scale.unwrap_or(defaults.export_scale),
+            diagram_format: args.diagram_format.unwrap_or(defaults.diagram_format),
+            export_gif: args.export_gif,
+            export: args.export,
+            generations: args.generations.unwrap_or(defaults.generations),
+            print: args.print,
+            headless: args.headless,
+            backend: args.backend.unwrap_or(defaults.backend),
+            stream: args.stream,
+            stdin: args.stdin,
+            stdin_format: args.stdin_format.unwrap_or(defaults.stdin_format),
+            stdin_width: args.stdin_width.unwrap_or(defaults.stdin_width),
+            control: args.control,
+            serve: args.serve,
+            serve_format: args.serve_format.unwrap_or(defaults.serve_format),
+            export_tiles: args.export_tiles,
+            tile_size: args.tile_size.unwrap_or(defaults.tile_size),
+            export_wav: args.export_wav,
+            wav_row_duration: args
+                .wav_row_ms
+                .map_or(defaults.wav_row_duration, Duration::from_millis),
+            export_midi: args.export_midi,
+            midi_from: args.midi_from.unwrap_or(defaults.midi_from),
+            report: args.report,
+            center_column: args.center_column,
+            column_format: args.column_format.unwrap_or(defaults.column_format),
+            video_format: args.video_format.unwrap_or(defaults.video_format),
+            capture_every: args.capture_every,
+            state_file: defaults.state_file,
+            load_state: args.load_state.is_some(),
+            resume: args.resume,
+            gif_every: args.gif_every.unwrap_or(defaults.gif_every),
+            gif_delay_ms: args.gif_delay.unwrap_or(defaults.gif_delay_ms),
+            escape: match args.escape.as_deref() {
+                Some("pause") => Command::TogglePause,
+                _ => defaults.escape,
+            },
+            preset: None,
+            confirm_quit: args.confirm_quit,
+            compare: args.compare,
+            windows: args.window,
+            endless: args.endless,
+            pattern: None,
+            pattern_file: None,
+            kiosk: args.kiosk,
+            screensaver: args.screensaver,
+            dwell: args.dwell.map_or(defaults.dwell, Duration::from_secs),
+            playlist: args.playlist.unwrap_or(defaults.playlist),
+        };
+        if !options.compare.is_empty() && !(2..=4).contains(&options.compare.len()) {
+            let rules: Vec<String> = options.compare.iter().map(u8::to_string).collect();
+            return Err(Error::Config(format!(
+                "invalid rules '{}', expected two to four of them",
+                rules.join(",")
+            )));
+        }
+        if let Some(name) = &args.theme {
+            options.theme = Theme::find(name)?;
+            options.theme_file = (!Theme::is_built_in(name)).then(|| PathBuf::from(name));
+        }
+        if let Some(path) = &args.theme_from_image {
+            options.theme = Theme::from_image(path)?;
+        }
+        // A preset wins over what layers below its own set, and loses to
+        // its own layer and those above.
+        if let Some(name) = &args.preset {
+            let index = options
+                .presets
+                .iter()
+                .position(|preset| &preset.name == name)
+                .ok_or_else(|| Error::Config(format!("unknown preset '{}'", name)))?;
+            let preset = options.presets[index].clone();
+            let wins = |id: &str| source(id) < source("preset");
+            if let (Some(rule), true) = (preset.rule, wins("rule")) {
+                options.rule = rule;
+                rule_source = source("preset");
+            }
+            if let (Some(seed), true) = (preset.seed, wins("seed")) {
+                options.seed = seed;
+            }
+            if let (Some(tick_rate), true) = (preset.tick_rate, wins("tick_rate")) {
+                options.tick_rate = tick_rate;
+            }
+            if let (Some(theme), true) = (preset.theme, wins("theme") && wins("theme_from_image")) {
+                options.theme = theme;
+                options.theme_file = preset.theme_file;
+            }
+            options.preset = Some(index);
+        }
+        if let Some(dir) = args.output_dir {
+            options.output.dir = dir;
+        }
+        if let Some(template) = &args.name_template {
+            options.output.set_template(template)?;
+        }
+        if let Some(path) = args.pattern {
+            options.pattern = Some(load_pattern(&path)?);
+            options.pattern_file = Some(path);
+        }
+
+        // A script's rule is a mode named after the script, run unless
+        // --mode picks another. The rule of a mode gives way to a rule set
+        // in the same layer or above.
+        let mut mode = args.mode.map(|mode| (mode, source("mode")));
+        if let Some(path) = args.script {
+            let script = Script::load(&path)?;
+            if let Some(rule) = script.rule() {
+                let name = path
+                    .file_stem()
+                    .map_or_else(|| "script".into(), |stem| stem.to_string_lossy());
+                options
+                    .registry
+                    .register(Mode::rule(&name, rule, "From --script"))?;
+                mode.get_or_insert_with(|| (name.into_owned(), source("script")));
+            }
+            options.script = Some(script);
+        }
+        if let Some((name, mode_source)) = mode {
+            let mode = options.registry.get(&name)?;
+            options.automaton = mode.automaton;
+            if let (Some(rule), true) = (mode.rule, rule_source < mode_source) {
+                options.rule = rule;
+            }
+        }
+
+        // Files written without a directory of their own go to the output
+        // directory, loading the state from elsewhere is left alone though.
+        options.state_file = match (args.load_state, args.state) {
+            (Some(path), _) | (None, Some(path)) => path,
+            (None, None) => options.output.dir.join(DEFAULT_STATE_FILE),
+        };
+        options.export_gif = options.export_gif.map(|path| options.output.dir.join(path));
+        options.export = options.export.map(|path| options.output.dir.join(path));
+        options.export_tiles = options
+            .export_tiles
+            .map(|path| options.output.dir.join(path));
+        options.report = options.report.map(|path| options.output.dir.join(path));
+        options.export_wav = options.export_wav.map(|path| options.output.dir.join(path));
+        options.export_midi = options
+            .export_midi
+            .map(|path| options.output.dir.join(path));
+        if let Some(path) = args.background_image {
+            options.background = Some(Background::load(
+                &path,
+                args.background_mode.unwrap_or(BackgroundMode::Behind),
+                PANE_SIZE,
+            )?);
+        }
+
+        // What clap cannot check, since it depends on values.
+        let exports = options.center_column
+            || options.export.is_some()
+            || options.export_tiles.is_some()
+            || options.report.is_some()
+            || options.export_wav.is_some()
+            || options.export_midi.is_some();
+        if options.headless {
+            if !exports && (options.automaton == Automaton::Life || !options.compare.is_empty()) {
+                return Err(Error::Config(
+                    "--headless prints a single elementary automaton when nothing is exported"
+                        .to_string(),
+                ));
+            }
+            options.print |= !exports;
+        }
+        if options.automaton == Automaton::Life {
+            let elementary_only = [
+                ("--print", options.print),
+                ("--stdin", options.stdin),
+                ("--center-column", options.center_column),
+                ("--export-tiles", options.export_tiles.is_some()),
+                ("--export-wav", options.export_wav.is_some()),
+                ("--export-midi", options.export_midi.is_some()),
+            ];
+            if let Some((flag, _)) = elementary_only.iter().find(|(_, given)| *given) {
+                return Err(Error::Config(format!(
+                    "{} only works for elementary automata",
+                    flag
+                )));
+            }
+        }
+        if options.backend != BackendKind::Window
+            && (options.headless
+                || options.stdin
+                || options.stream.is_some()
+                || options.control.is_some()
+                || options.serve.is_some()
+                || options.kiosk
+                || options.screensaver
+                || !options.windows.is_empty()
+                || options.export_gif.is_some()
+                || options.capture_every.is_some())
+        {
+            return Err(Error::Config(
+                "--backend terminal only plays the automata, and cannot be combined with \
+                 --headless, --stdin, --stream, --control, --serve, --kiosk, --screensaver, \
+                 --window, --export-gif or --capture-every"
+                    .to_string(),
+            ));
+        }
+        if options.stdin && options.control == Some(ControlSource::Stdin) {
+            return Err(Error::Config(
+                "--stdin and --control stdin cannot both read stdin".to_string(),
+            ));
+        }
+        if options.midi_from > options.generations {
+            return Err(Error::Config(format!(
+                "--midi-from {} is past the last of the {} generations",
+                options.midi_from, options.generations
+            )));
+        }
+        Ok(Some(options))
+    }
+}
+
+/// Loads a pattern to start from, as long as it fits into the grid.
+pub fn load_pattern(path: &Path) -> Result<Pattern, Error> {
+    let pattern = Pattern::load(path)?;
+    if pattern.width() > GRID_X_SIZE as usize
+        || pattern.height() > GRID_Y_SIZE as usize
+        || pattern.height() == 0
+    {
+        return Err(Error::Config(format!(
+            "pattern '{}' does not fit into the {} by {} grid",
+            path.display(),
+            GRID_X_SIZE,
+            GRID_Y_SIZE
+        )));
+    }
+    Ok(pattern)
+}
+
+/// The command line `run` takes, where every option without a value can
+/// also be given `true` or `false`, to turn off what a layer below turned
+/// on.
+pub fn command() -> clap::Command {
+    Args::command().mut_args(|arg| match arg.get_action() {
+        ArgAction::SetTrue => arg
+            .action(ArgAction::Set)
+            .num_args(0..=1)
+            .require_equals(true)
+            .default_value("false")
+            .default_missing_value("true")
+            .value_parser(BoolishValueParser::new())
+            .value_name("true|false")
+            .hide_default_value(true)
+            .hide_possible_values(true),
+        _ => arg,
+    })
+}
+
+/// Where an option was set, every layer winning over those before it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Source {
+    /// Nowhere, so the default holds.
+    Default,
+    Config,
+    Environment,
+    CommandLine,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Source::Default => "default",
+            Source::Config => "config",
+            Source::Environment => "environment",
+            Source::CommandLine => "command line",
+        })
+    }
+}
+
+/// The options of every layer, merged.
+pub struct Layered {
+    pub matches: ArgMatches,
+    /// The arguments the options were merged into.
+    pub arguments: Vec<String>,
+    /// Where every option that was set came from, by its id.
+    sources: HashMap<String, Source>,
+    /// The configuration file read, with its path.
+    pub config: Option<(PathBuf, Config)>,
+}
+
+impl Layered {
+    /// Where the option `id` came from.
+    pub fn source(&self, id: &str) -> Source {
+        self.sources.get(id).copied().unwrap_or(Source::Default)
+    }
+}
+
+/// Parses `args` for `command` over the options the configuration file
+/// and the environment set, leaving out those `command` does not take,
+/// since they are shared by every command. Returns `None` if the help was asked
+/// for.
+pub fn parse_layered(command: clap::Command, args: Vec<String>) -> Result<Option<Layered>, Error> {
+    let environment: Vec<String> = config::environment()
+        .into_iter()
+        .map(|(_, argument)| argument)
+        .filter(|argument| takes(&command, argument))
+        .collect();
+    let layers = [
+        (Source::Environment, environment.clone()),
+        (Source::CommandLine, args.clone()),
+    ];
+    // Only to find the configuration file, which may give what is required.
+    let optional = command.clone().mut_args(|arg| arg.required(false));
+    let Some(given) = layer(optional, layers)? else {
+        return Ok(None);
+    };
+    let path = match given.matches.try_get_one::<PathBuf>("config") {
+        Ok(Some(path)) => Some(path.clone()),
+        _ => config::default_path().filter(|path| path.exists()),
+    };
+    let config = match path {
+        Some(path) => {
+            info!("Reading the configuration from {}", path.display());
+            let config = config::load(&path)?;
+            warn_unknown_config(&path, &config);
+            Some((path, config))
+        }
+        None => None,
+    };
+    let arguments = config.iter().flat_map(|(_, config)| {
+        config
+            .arguments
+            .iter()
+            .filter(|argument| takes(&command, argument))
+            .cloned()
+    });
+    let layers = [
+        (Source::Config, arguments.collect()),
+        (Source::Environment, environment),
+        (Source::CommandLine, args),
+    ];
+    Ok(layer(command, layers)?.map(|layered| Layered { config, ..layered }))
+}
+
+/// Whether `command` has the option that `argument`, like `--rule=90`,
+/// sets.
+fn takes(command: &clap::Command, argument: &str) -> bool {
+    let name = config::option_name(argument);
+    command.get_arguments().any(|arg| {
+        arg.get_long() == Some(name)
+            || arg
+                .get_all_aliases()
+                .is_some_and(|aliases| aliases.contains(&name))
+    })
+}
+
+/// Every command that takes options from the configuration file and the
+/// environment.
+pub fn commands() -> [clap::Command; 4] {
+    [
+        command(),
+        crate::render::command(),
+        crate::bench::command(),
+        crate::analyze::command(),
+    ]
+}
+
+/// Warns about the environment variables that set no option of any
+/// command, which are likely typos.
+pub fn warn_unknown_environment() {
+    let commands = commands();
+    for (name, argument) in config::environment() {
+        if !commands.iter().any(|command| takes(command, &argument)) {
+            warn!("{} sets no option of any command, ignoring it", name);
+        }
+    }
+}
+
+/// Warns about the keys of the configuration file at `path` that set no
+/// option of any command, like the environment variables.
+fn warn_unknown_config(path: &Path, config: &Config) {
+    let commands = commands();
+    for argument in &config.arguments {
+        if !commands.iter().any(|command| takes(command, argument)) {
+            warn!(
+                "'{}' in {} sets no option of any command, ignoring it",
+                config::option_name(argument),
+                path.display()
+            );
+        }
+    }
+}
+
+/// Parses the arguments of every layer, lowest first, for `command` and
+/// merges them. Every option comes from the highest layer that sets it,
+/// and an option a layer sets drops those of the layers below that cannot
+/// be combined with it, so `--compare` on the command line does away with
+/// the `rule` of the configuration file. Returns `None` if the command
+/// line asks for the help.
+pub fn layer(
+    command: clap::Command,
+    layers: impl IntoIterator<Item = (Source, Vec<String>)>,
+) -> Result<Option<Layered>, Error> {
+    // What is required may come from any layer.
+    let optional = command.clone().mut_args(|arg| arg.required(false));
+    // The arguments for every option set so far and where they came from.
+    // Options turned off have no arguments, being off by default.
+    let mut set: HashMap<String, (Source, Vec<String>)> = HashMap::new();
+    for (source, arguments) in layers {
+        let (arguments, off) = switched_off(&optional, arguments);
+        let matches = match matches(optional.clone(), &arguments) {
+            Ok(Some(matches)) => matches,
+            Ok(None) => return Ok(None),
+            Err(e) if source == Source::CommandLine => return Err(e),
+            Err(e) => {
+                let message = e.to_string();
+                let message = message.split("\n\n").next().unwrap_or_default();
+                return Err(Error::Config(format!("invalid {}: {}", source, message)));
+            }
+        };
+        for id in off {
+            set.insert(id, (source, Vec::new()));
+        }
+        for arg in optional.get_arguments() {
+            let id = arg.get_id().as_str();
+            if matches.value_source(id) != Some(ValueSource::CommandLine) {
+                continue;
+            }
+            set.retain(|other, _| !conflict(&optional, id, other));
+            let delimiter = arg
+                .get_value_delimiter()
+                .map(String::from)
+                .unwrap_or_default();
+            let long = arg.get_long().unwrap_or(id);
+            let arguments = matches
+                .get_raw_occurrences(id)
+                .into_iter()
+                .flatten()
+                .map(|values| {
+                    let values: Vec<_> = values.map(|value| value.to_string_lossy()).collect();
+                    format!("--{}={}", long, values.join(&delimiter))
+                })
+                .collect();
+            set.insert(id.to_string(), (source, arguments));
+        }
+    }
+    let arguments: Vec<String> = optional
+        .get_arguments()
+        .filter_map(|arg| set.get(arg.get_id().as_str()))
+        .flat_map(|(_, arguments)| arguments.clone())
+        .collect();
+    let Some(matches) = matches(command, &arguments)? else {
+        return Ok(None);
+    };
+    Ok(Some(Layered {
+        matches,
+        arguments,
+        sources: set
+            .into_iter()
+            .map(|(id, (source, ..))| (id, source))
+            .collect(),
+        config: None,
+    }))
+}
+
+/// Takes the options without a value that end up `false` out of
+/// `arguments`, returning the other arguments and the ids of those
+/// options. Clap would hold them against the options they cannot be
+/// combined with, even though they are off.
+fn switched_off(command: &clap::Command, arguments: Vec<String>) -> (Vec<String>, Vec<String>) {
+    let switch = |argument: &str| {
+        let argument = argument.strip_prefix("--")?;
+        let (name, value) = argument.split_once('=').unwrap_or((argument, "true"));
+        let arg = command.get_arguments().find(|arg| {
+            arg.get_long() == Some(name)
+                && matches!(arg.get_default_values(), [value] if value == "false")
+        })?;
+        let on = BoolishValueParser::new()
+            .parse_ref(command, Some(arg), OsStr::new(value))
+            .ok()?;
+        Some((arg.get_id().to_string(), on))
+    };
+    // The last of them wins.
+    let mut off = Vec::new();
+    for (id, on) in arguments.iter().filter_map(|argument| switch(argument)) {
+        off.retain(|other| other != &id);
+        if !on {
+            off.push(id);
+        }
+    }
+    let arguments = arguments
+        .into_iter()
+        .filter(|argument| !switch(argument).is_some_and(|(id, _)| off.contains(&id)))
+        .collect();
+    (arguments, off)
+}
+
+/// Whether the options `a` and `b` of `command` cannot be combined.
+fn conflict(command: &clap::Command, a: &str, b: &str) -> bool {
+    let find = |id: &str| command.get_arguments().find(|arg| arg.get_id() == id);
+    let (Some(first), Some(second)) = (find(a), find(b)) else {
+        return false;
+    };
+    let declared = |arg: &clap::Arg, other: &str| {
+        command
+            .get_arg_conflicts_with(arg)
+            .iter()
+            .any(|conflict| conflict.get_id() == other)
+    };
+    // Options of a group that takes only one of them cannot be combined
+    // either.
+    let grouped = command.get_groups().any(|group| {
+        !group.clone().is_multiple()
+            && group.get_args().any(|id| id == a)
+            && group.get_args().any(|id| id == b)
+    });
+    a != b && (declared(first, b) || declared(second, a) || grouped)
+}
+
+/// Parses `args` for `command`, or prints its help instead and returns
+/// `None` if they ask for it.
+pub fn matches(command: clap::Command, args: &[String]) -> Result<Option<ArgMatches>, Error> {
+    let name = command.get_name().to_string();
+    match command.try_get_matches_from(std::iter::once(name).chain(args.iter().cloned())) {
+        Ok(matches) => Ok(Some(matches)),
+        Err(e) if e.kind() == ErrorKind::DisplayHelp => {
+            let _ = e.print();
+            Ok(None)
+        }
+        Err(e) => {
+            let message = e.to_string();
+            let message = message.strip_prefix("error: ").unwrap_or(&message);
+            Err(Error::Config(message.trim_end().to_string()))
+        }
+    }
+}
+
+/// Parses a rule, 0 to 255.
+pub fn parse_rule(rule: &str) -> Result<u8, Error> {
+    rule.trim()
+        .parse()
+        .map_err(|_| Error::InvalidRule(rule.to_string()))
+}
+
+fn parse_scale(factor: &str) -> Result<f32, Error> {
+    match factor.parse::<f32>() {
+        Ok(factor) if factor > 0.0 => Ok(factor),
+        _ => Err(Error::Parse(format!("invalid scale '{}'", factor))),
+    }
+}
+
+/// A file to export the diagram to, in a format its extension names.
+fn parse_export(path: &str) -> Result<PathBuf, Error> {
+    let path = PathBuf::from(path);
+    DiagramFormat::of(&path)?;
+    Ok(path)
+}
+
+/// The address `--serve` listens on: `address` itself if it has a host,
+/// or the port on localhost.
+fn serve_address(address: &str) -> Result<String, Error> {
+    if address.contains(':') {
+        return Ok(address.to_string());
+    }
+    match address.parse::<u16>() {
+        Ok(port) => Ok(format!("127.0.0.1:{}", port)),
+        Err(_) => Err(Error::Parse(format!(
+            "invalid address '{}', expected a port or HOST:PORT",
+            address
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The environment is shared by every test, so this is the only one
+    /// setting it, going through the commands one at a time.
+    #[test]
+    fn command_line_wins_over_environment_wins_over_config() {
+        let path = std::env::temp_dir().join(format!("rule30-layers-{}.toml", std::process::id()));
+        // `render` needs somewhere to save to, the others leave `out` be.
+        std::fs::write(&path, "rule = 90\nseed = \"7\"\nout = \"rule.png\"\n").unwrap();
+        let config = format!("--config={}", path.display());
+        for command in commands() {
+            let name = command.get_name().to_string();
+            let rule = |args: &[&str]| {
+                let mut args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+                args.push(config.clone());
+                let layered = parse_layered(command.clone(), args).unwrap().unwrap();
+                let rule = *layered.matches.get_one::<u8>("rule").unwrap();
+                (rule, layered.source("rule"), layered.source("seed"))
+            };
+            std::env::remove_var("RULE30_RULE");
+            assert_eq!(rule(&[]), (90, Source::Config, Source::Config), "{}", name);
+            std::env::set_var("RULE30_RULE", "110");
+            assert_eq!(
+                rule(&[]),
+                (110, Source::Environment, Source::Config),
+                "{}",
+                name
+            );
+            assert_eq!(
+                rule(&["--rule", "30"]),
+                (30, Source::CommandLine, Source::Config),
+                "{}",
+                name
+            );
+        }
+        std::env::remove_var("RULE30_RULE");
+        std::fs::remove_file(&path).unwrap();
+    }
+}
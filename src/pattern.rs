@@ -0,0 +1,664 @@
+//! Patterns in the RLE format Golly and the LifeWiki use, see
+//! <https://conwaylife.com/wiki/Run_Length_Encoded>, in the plaintext
+//! `.cells` format, see <https://conwaylife.com/wiki/Plaintext>, and in
+//! Golly's macrocell format, see <https://conwaylife.com/wiki/Macrocell>.
+
+use crate::Error;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// How long the lines of a written RLE pattern get at most.
+const RLE_LINE_LENGTH: usize = 70;
+/// How many cells wide and high a macrocell pattern may spread out to be
+/// read, since its live cells all end up in one rectangle. Macrocells can
+/// describe patterns far too large for any grid.
+const MAX_MACROCELL_SIDE: u64 = 1 << 12;
+/// The size of the leaves of a macrocell tree.
+const LEAF_LEVEL: u32 = 3;
+const LEAF_SIZE: usize = 1 << LEAF_LEVEL;
+
+/// A rectangle of cells, row by row.
+#[derive(Clone)]
+pub struct Pattern {
+    /// The rows from the top, `true` for live cells. They can differ in
+    /// length, missing cells are dead.
+    pub rows: Vec<Vec<bool>>,
+}
+
+impl Pattern {
+    /// Reads a pattern file, in the format its extension names and RLE
+    /// for anything else.
+    pub fn load(path: &Path) -> Result<Pattern, Error> {
+        let contents = fs::read_to_string(path).map_err(|e| Error::io("read", path, e))?;
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("cells") => Pattern::parse_cells(&contents),
+            Some("mc") => Pattern::parse_macrocell(&contents),
+            _ => Pattern::parse_rle(&contents),
+        }
+        .map_err(|e| Error::Parse(format!("invalid pattern '{}': {}", path.display(), e)))
+    }
+
+    /// The length of the longest row.
+    pub fn width(&self) -> usize {
+        self.rows.iter().map(Vec::len).max().unwrap_or(0)
+    }
+
+    /// How many rows there are.
+    pub fn height(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Reads an RLE pattern. States beyond dead and alive, as multi-state
+    /// rules have them, all count as alive.
+    pub fn parse_rle(text: &str) -> Result<Pattern, Error> {
+        let mut lines = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'));
+        let header = lines
+            .next()
+            .ok_or_else(|| Error::Parse("missing header".to_string()))?;
+        let dimension = |name: &str| -> Result<usize, Error> {
+            header
+                .split(',')
+                .filter_map(|part| part.split_once('='))
+                .find(|(key, _)| key.trim() == name)
+                .and_then(|(_, value)| value.trim().parse().ok())
+                .ok_or_else(|| Error::Parse(format!("missing '{}' in the header", name)))
+        };
+        let (width, height) = (dimension("x")?, dimension("y")?);
+        let too_big = || Error::Parse(format!("the cells do not fit into {} by {}", width, height));
+
+        let mut rows = vec![Vec::new()];
+        let mut count = String::new();
+        'body: for line in lines {
+            for c in line.chars() {
+                if c.is_ascii_digit() {
+                    count.push(c);
+                    continue;
+                }
+                let run = match count.as_str() {
+                    "" => 1,
+                    digits => match digits.parse::<usize>() {
+                        Ok(run) if run > 0 => run,
+                        _ => return Err(Error::Parse(format!("invalid run length '{}'", digits))),
+                    },
+                };
+                count.clear();
+                // Checked before growing, so a huge run fails rather than
+                // allocating its cells.
+                let row = rows.last_mut().unwrap();
+                match c {
+                    'b' | '.' | 'o' | 'A'..='X' => {
+                        if run > width - row.len() {
+                            return Err(too_big());
+                        }
+                        row.extend(std::iter::repeat_n(!matches!(c, 'b' | '.'), run));
+                    }
+                    '$' => {
+                        if run > height.saturating_sub(rows.len()) {
+                            return Err(too_big());
+                        }
+                        rows.extend(std::iter::repeat_with(Vec::new).take(run));
+                    }
+                    '!' => break 'body,
+                    c if c.is_whitespace() => {}
+                    c => return Err(Error::Parse(format!("unexpected '{}'", c))),
+                }
+            }
+        }
+
+        if rows.len() > height {
+            return Err(too_big());
+        }
+        rows.resize_with(height, Vec::new);
+        for row in &mut rows {
+            row.resize(width, false);
+        }
+        Ok(Pattern { rows })
+    }
+
+    /// Reads a plaintext pattern, `O` for live and `.` for dead cells with
+    /// `!` starting a comment line. Rows may end early.
+    pub fn parse_cells(text: &str) -> Result<Pattern, Error> {
+        let mut rows = text
+            .lines()
+            .filter(|line| !line.starts_with('!'))
+            .map(|line| {
+                line.trim_end()
+                    .chars()
+                    .map(|c| match c {
+                        'O' | 'o' | '*' => Ok(true),
+                        '.' => Ok(false),
+                        c => Err(Error::Parse(format!("unexpected '{}'", c))),
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+        for row in &mut rows {
+            row.resize(width, false);
+        }
+        Ok(Pattern { rows })
+    }
+
+    /// Writes the pattern in the plaintext format, under a `!Name:` line.
+    pub fn to_cells(&self, name: &str) -> String {
+        let mut cells = format!("!Name: {}\n", name);
+        for row in &self.rows {
+            let length = row
+                .iter()
+                .rposition(|&alive| alive)
+                .map_or(0, |last| last + 1);
+            cells.extend(
+                row[..length]
+                    .iter()
+                    .map(|&alive| if alive { 'O' } else { '.' }),
+            );
+            cells.push('\n');
+        }
+        cells
+    }
+
+    /// Writes the pattern as RLE for `rule`, such as `B3/S23` for Game of
+    /// Life or `W30` for an elementary automaton.
+    pub fn to_rle(&self, rule: &str) -> String {
+        let mut tokens = Vec::new();
+        let mut last_written = None;
+        for (y, row) in self.rows.iter().enumerate() {
+            // Dead cells at the end of a row and empty rows at the end of
+            // the pattern go without saying.
+            let length = row
+                .iter()
+                .rposition(|&alive| alive)
+                .map_or(0, |last| last + 1);
+            if length == 0 {
+                continue;
+            }
+            let ended_rows = match last_written {
+                Some(last) => y - last,
+                None => y,
+            };
+            if ended_rows > 0 {
+                tokens.push(run(ended_rows, '$'));
+            }
+            last_written = Some(y);
+            let mut x = 0;
+            while x < length {
+                let alive = row[x];
+                let start = x;
+                while x < length && row[x] == alive {
+                    x += 1;
+                }
+                tokens.push(run(x - start, if alive { 'o' } else { 'b' }));
+            }
+        }
+        tokens.push("!".to_string());
+
+        let mut rle = format!(
+            "x = {}, y = {}, rule = {}\n",
+            self.width(),
+            self.height(),
+            rule
+        );
+        let mut line = String::new();
+        for token in tokens {
+            if line.len() + token.len() > RLE_LINE_LENGTH {
+                rle.push_str(&line);
+                rle.push('\n');
+                line.clear();
+            }
+            line.push_str(&token);
+        }
+        rle.push_str(&line);
+        rle.push('\n');
+        rle
+    }
+}
+
+/// A node of a macrocell tree, which splits a square of cells into four
+/// quarters until they are down to leaves of 8 by 8 cells.
+enum Node {
+    /// One byte per row, the first cell in the lowest bit.
+    Leaf([u8; LEAF_SIZE]),
+    /// The nodes of the quarters, top left, top right, bottom left and
+    /// bottom right, with 0 standing for an empty one.
+    Square { level: u32, quarters: [usize; 4] },
+}
+
+/// The live cells of a node, as the first and last column and row
+/// holding any, relative to the node's top left corner.
+type Bounds = Option<(u64, u64, u64, u64)>;
+
+impl Pattern {
+    /// Reads a two state macrocell pattern. The cells are built up from
+    /// the live cells' bounding rectangle, what lies around it is gone.
+    pub fn parse_macrocell(text: &str) -> Result<Pattern, Error> {
+        let mut lines = text.lines().map(str::trim);
+        if !lines.next().is_some_and(|line| line.starts_with("[M2]")) {
+            return Err(Error::Parse("missing the [M2] header".to_string()));
+        }
+        // Nodes are numbered from 1 in the order they appear, the empty
+        // node 0 is not written down.
+        let mut nodes = vec![Node::Leaf([0; LEAF_SIZE])];
+        let mut bounds: Vec<Bounds> = vec![None];
+        for line in lines.filter(|line| !line.is_empty() && !line.starts_with('#')) {
+            let node = match line.chars().next() {
+                Some(c) if c.is_ascii_digit() => parse_square(line, &nodes)?,
+                _ => parse_leaf(line)?,
+            };
+            bounds.push(node_bounds(&node, &bounds));
+            nodes.push(node);
+        }
+        if nodes.len() == 1 {
+            return Err(Error::Parse("missing nodes".to_string()));
+        }
+        let root = nodes.len() - 1;
+        let Some((left, top, right, bottom)) = bounds[root] else {
+            return Ok(Pattern { rows: Vec::new() });
+        };
+        let (width, height) = (right - left + 1, bottom - top + 1);
+        if width > MAX_MACROCELL_SIDE || height > MAX_MACROCELL_SIDE {
+            return Err(Error::Parse(format!(
+                "the live cells spread over {} by {}, which is too large",
+                width, height
+            )));
+        }
+        let mut rows = vec![vec![false; width as usize]; height as usize];
+        paint(&nodes, root, (0, 0), (left, top), &mut rows);
+        Ok(Pattern { rows })
+    }
+
+    /// Writes the pattern as a macrocell for `rule`. Quarters that repeat
+    /// are written only once, which is what makes the format small for
+    /// regular patterns.
+    pub fn to_macrocell(&self, rule: &str) -> String {
+        let side = self.width().max(self.height()).next_power_of_two();
+        let level = side.trailing_zeros().max(LEAF_LEVEL);
+        let mut lines = Vec::new();
+        let mut numbers = HashMap::new();
+        if self.write_node(level, (0, 0), &mut lines, &mut numbers) == 0 {
+            // Even an empty pattern needs a node to be read back.
+            lines.push("$".to_string());
+        }
+        let mut macrocell = format!("[M2] (cellular_automata)\n#R {}\n", rule);
+        for line in lines {
+            macrocell.push_str(&line);
+            macrocell.push('\n');
+        }
+        macrocell
+    }
+
+    /// Writes the node for the square of `level` with its top left corner
+    /// at `(x, y)` after the nodes it is made of, unless the same node has
+    /// been written before. Returns its number, 0 when it is empty.
+    fn write_node(
+        &self,
+        level: u32,
+        (x, y): (usize, usize),
+        lines: &mut Vec<String>,
+        numbers: &mut HashMap<String, usize>,
+    ) -> usize {
+        let alive = |x: usize, y: usize| {
+            self.rows
+                .get(y)
+                .and_then(|row| row.get(x))
+                .copied()
+                .unwrap_or(false)
+        };
+        let line = if level == LEAF_LEVEL {
+            let rows: Vec<String> = (y..y + LEAF_SIZE)
+                .map(|y| {
+                    let row: String = (x..x + LEAF_SIZE)
+                        .map(|x| if alive(x, y) { '*' } else { '.' })
+                        .collect();
+                    row.trim_end_matches('.').to_string()
+                })
+                .collect();
+            let Some(last) = rows.iter().rposition(|row| !row.is_empty()) else {
+                return 0;
+            };
+            rows[..=last]
+                .iter()
+                .map(|row| format!("{}$", row))
+                .collect()
+        } else {
+            let half = 1 << (level - 1);
+            let quarters = [(x, y), (x + half, y), (x, y + half), (x + half, y + half)]
+                .map(|corner| self.write_node(level - 1, corner, lines, numbers));
+            if quarters == [0; 4] {
+                return 0;
+            }
+            let [nw, ne, sw, se] = quarters;
+            format!("{} {} {} {} {}", level, nw, ne, sw, se)
+        };
+        *numbers.entry(line).or_insert_with_key(|line| {
+            lines.push(line.clone());
+            lines.len()
+        })
+    }
+}
+
+fn parse_leaf(line: &str) -> Result<Node, Error> {
+    let mut leaf = [0; LEAF_SIZE];
+    let (mut x, mut y) = (0, 0);
+    for c in line.chars() {
+        match c {
+            '.' => x += 1,
+            '*' => {
+                if x >= LEAF_SIZE || y >= LEAF_SIZE {
+                    return Err(Error::Parse(format!(
+                        "the leaf '{}' is larger than 8 by 8",
+                        line
+                    )));
+                }
+                leaf[y] |= 1 << x;
+                x += 1;
+            }
+            '$' => {
+                x = 0;
+                y += 1;
+            }
+            c => return Err(Error::Parse(format!("unexpected '{}'", c))),
+        }
+    }
+    Ok(Node::Leaf(leaf))
+}
+
+fn parse_square(line: &str, nodes: &[Node]) -> Result<Node, Error> {
+    let numbers = line
+        .split_whitespace()
+        .map(str::parse::<usize>)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| Error::Parse(format!("invalid node '{}'", line)))?;
+    let [level, nw, ne, sw, se] = numbers[..] else {
+        return Err(Error::Parse(format!("invalid node '{}'", line)));
+    };
+    if level == 1 {
+        return Err(Error::Parse(
+            "only two state macrocells are supported".to_string(),
+        ));
+    }
+    let level = level as u32;
+    if level <= LEAF_LEVEL || level > 63 {
+        return Err(Error::Parse(format!("invalid level in '{}'", line)));
+    }
+    for quarter in [nw, ne, sw, se].into_iter().filter(|&quarter| quarter != 0) {
+        let quarter_level = match nodes.get(quarter) {
+            Some(Node::Leaf(_)) => LEAF_LEVEL,
+            Some(Node::Square { level, .. }) => *level,
+            None => {
+                return Err(Error::Parse(format!(
+                    "'{}' refers to a node not defined yet",
+                    line
+                )))
+            }
+        };
+        if quarter_level != level - 1 {
+            return Err(Error::Parse(format!(
+                "'{}' refers to a node of the wrong size",
+                line
+            )));
+        }
+    }
+    Ok(Node::Square {
+        level,
+        quarters: [nw, ne, sw, se],
+    })
+}
+
+/// Where the live cells of `node` are, from those of the nodes before it.
+fn node_bounds(node: &Node, bounds: &[Bounds]) -> Bounds {
+    match node {
+        Node::Leaf(leaf) => {
+            let rows: Vec<u64> = (0..LEAF_SIZE as u64)
+                .filter(|&y| leaf[y as usize] != 0)
+                .collect();
+            let columns = leaf.iter().fold(0u8, |columns, row| columns | row);
+            Some((
+                columns.trailing_zeros() as u64,
+                *rows.first()?,
+                7 - columns.leading_zeros() as u64,
+                *rows.last()?,
+            ))
+        }
+        Node::Square { level, quarters } => {
+            let half = 1u64 << (level - 1);
+            let offsets = [(0, 0), (half, 0), (0, half), (half, half)];
+            quarters
+                .iter()
+                .zip(offsets)
+                .filter_map(|(&quarter, (dx, dy))| {
+                    let (left, top, right, bottom) = bounds[quarter]?;
+                    Some((left + dx, top + dy, right + dx, bottom + dy))
+                })
+                .reduce(|a, b| (a.0.min(b.0), a.1.min(b.1), a.2.max(b.2), a.3.max(b.3)))
+        }
+    }
+}
+
+/// Sets the live cells of node `number`, with its top left corner at
+/// `corner`, in `rows` that start at `origin`.
+fn paint(
+    nodes: &[Node],
+    number: usize,
+    (x, y): (u64, u64),
+    origin: (u64, u64),
+    rows: &mut [Vec<bool>],
+) {
+    if number == 0 {
+        return;
+    }
+    match &nodes[number] {
+        Node::Leaf(leaf) => {
+            for (dy, row) in leaf.iter().enumerate() {
+                for dx in (0..LEAF_SIZE).filter(|dx| row >> dx & 1 == 1) {
+                    let (cell_x, cell_y) = (x + dx as u64 - origin.0, y + dy as u64 - origin.1);
+                    rows[cell_y as usize][cell_x as usize] = true;
+                }
+            }
+        }
+        Node::Square { level, quarters } => {
+            let half = 1u64 << (level - 1);
+            let offsets = [(0, 0), (half, 0), (0, half), (half, half)];
+            for (&quarter, (dx, dy)) in quarters.iter().zip(offsets) {
+                paint(nodes, quarter, (x + dx, y + dy), origin, rows);
+            }
+        }
+    }
+}
+
+fn run(length: usize, tag: char) -> String {
+    match length {
+        1 => tag.to_string(),
+        length => format!("{}{}", length, tag),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GLIDER: &str = "x = 3, y = 3, rule = B3/S23\nbo$2bo$3o!\n";
+
+    fn cells(rows: &[&str]) -> Vec<Vec<bool>> {
+        rows.iter()
+            .map(|row| row.chars().map(|c| c == 'o').collect())
+            .collect()
+    }
+
+    #[test]
+    fn parses_a_glider() {
+        let pattern = Pattern::parse_rle(GLIDER).unwrap();
+        assert_eq!(pattern.rows, cells(&[".o.", "..o", "ooo"]));
+    }
+
+    #[test]
+    fn parses_comments_line_breaks_and_empty_rows() {
+        let text = "#N Blinkers\n#C Two of them.\nx = 5, y = 4\n3o2$\n2b\n3o!";
+        let pattern = Pattern::parse_rle(text).unwrap();
+        assert_eq!(pattern.rows, cells(&["ooo..", ".....", "..ooo", "....."]));
+    }
+
+    #[test]
+    fn counts_other_states_as_alive() {
+        let pattern = Pattern::parse_rle("x = 3, y = 1\nA.B!").unwrap();
+        assert_eq!(pattern.rows, cells(&["o.o"]));
+    }
+
+    #[test]
+    fn writes_a_glider() {
+        let pattern = Pattern::parse_rle(GLIDER).unwrap();
+        assert_eq!(pattern.to_rle("B3/S23"), GLIDER);
+    }
+
+    #[test]
+    fn round_trips_a_gosper_glider_gun() {
+        let gun = "x = 36, y = 9, rule = B3/S23\n\
+                   24bo$22bobo$12b2o6b2o12b2o$11bo3bo4b2o12b2o$2o8bo5bo3b2o$2o8bo3bob2o4b\n\
+                   obo$10bo5bo7bo$11bo3bo$12b2o!\n";
+        let pattern = Pattern::parse_rle(gun).unwrap();
+        assert_eq!((pattern.width(), pattern.height()), (36, 9));
+        assert_eq!(pattern.to_rle("B3/S23"), gun);
+        let again = Pattern::parse_rle(&pattern.to_rle("B3/S23")).unwrap();
+        assert_eq!(again.rows, pattern.rows);
+    }
+
+    #[test]
+    fn round_trips_trailing_dead_cells_and_rows() {
+        let pattern = Pattern {
+            rows: cells(&["o....", ".....", "....o", "....."]),
+        };
+        let rle = pattern.to_rle("B3/S23");
+        assert_eq!(rle, "x = 5, y = 4, rule = B3/S23\no2$4bo!\n");
+        assert_eq!(Pattern::parse_rle(&rle).unwrap().rows, pattern.rows);
+    }
+
+    #[test]
+    fn round_trips_a_glider_as_cells() {
+        let pattern = Pattern::parse_rle(GLIDER).unwrap();
+        let text = pattern.to_cells("Glider");
+        assert_eq!(text, "!Name: Glider\n.O\n..O\nOOO\n");
+        assert_eq!(Pattern::parse_cells(&text).unwrap().rows, pattern.rows);
+    }
+
+    #[test]
+    fn skips_cells_comments_and_pads_ragged_rows() {
+        let text = "!Name: Ragged\n!Two rows, the first cut short.\n.O\nO..O\n\n*\n";
+        let pattern = Pattern::parse_cells(text).unwrap();
+        assert_eq!(pattern.rows, cells(&[".o..", "o..o", "....", "o..."]));
+        let again = Pattern::parse_cells(&pattern.to_cells("Ragged")).unwrap();
+        assert_eq!(again.rows, pattern.rows);
+    }
+
+    #[test]
+    fn round_trips_an_empty_cells_file() {
+        let pattern = Pattern::parse_cells("").unwrap();
+        assert!(pattern.rows.is_empty());
+        let text = pattern.to_cells("Empty");
+        assert_eq!(text, "!Name: Empty\n");
+        assert!(Pattern::parse_cells(&text).unwrap().rows.is_empty());
+    }
+
+    #[test]
+    fn rejects_unknown_cells() {
+        assert!(Pattern::parse_cells(".O\nOx\n").is_err());
+    }
+
+    #[test]
+    fn round_trips_a_glider_as_a_macrocell() {
+        let pattern = Pattern::parse_rle(GLIDER).unwrap();
+        let text = pattern.to_macrocell("B3/S23");
+        assert_eq!(text, "[M2] (cellular_automata)\n#R B3/S23\n.*$..*$***$\n");
+        assert_eq!(Pattern::parse_macrocell(&text).unwrap().rows, pattern.rows);
+    }
+
+    #[test]
+    fn round_trips_a_macrocell_over_several_leaves() {
+        // Every 8 by 8 block is the same, so a single leaf is written.
+        let rows = vec![vec![true; 32]; 16];
+        let pattern = Pattern { rows };
+        let text = pattern.to_macrocell("B3/S23");
+        let leaves = text.lines().filter(|line| line.ends_with('$')).count();
+        assert_eq!(leaves, 1);
+        assert_eq!(Pattern::parse_macrocell(&text).unwrap().rows, pattern.rows);
+
+        let gun = "x = 36, y = 9, rule = B3/S23\n\
+                   24bo$22bobo$12b2o6b2o12b2o$11bo3bo4b2o12b2o$2o8bo5bo3b2o$2o8bo3bob2o4b\n\
+                   obo$10bo5bo7bo$11bo3bo$12b2o!\n";
+        let pattern = Pattern::parse_rle(gun).unwrap();
+        let again = Pattern::parse_macrocell(&pattern.to_macrocell("B3/S23")).unwrap();
+        assert_eq!(again.rows, pattern.rows);
+    }
+
+    #[test]
+    fn round_trips_an_empty_macrocell() {
+        let pattern = Pattern { rows: Vec::new() };
+        let text = pattern.to_macrocell("B3/S23");
+        assert!(Pattern::parse_macrocell(&text).unwrap().rows.is_empty());
+    }
+
+    #[test]
+    fn rejects_malformed_macrocells() {
+        for text in [
+            "",
+            ".*$\n",
+            "[M2]\n",
+            "[M2]\n.*x$\n",
+            "[M2]\n.........*$\n",
+            // Levels below a leaf's, too high for the cells' offsets, and
+            // the first level multi-state macrocells use.
+            "[M2]\n*$\n3 1 0 0 0\n",
+            "[M2]\n*$\n64 1 0 0 0\n",
+            "[M2]\n*$\n1 1 0 0 0\n",
+            "[M2]\n*$\nfour 1 0 0 0\n",
+            "[M2]\n*$\n4 1 0 0\n",
+            // A quarter that is not defined yet, and one of the wrong size.
+            "[M2]\n*$\n4 1 0 0 2\n",
+            "[M2]\n*$\n5 1 0 0 0\n",
+        ] {
+            assert!(Pattern::parse_macrocell(text).is_err(), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_headers() {
+        for text in [
+            "",
+            "# only a comment",
+            "y = 3\n3o!",
+            "x = 3\n3o!",
+            "x = three, y = 1\n3o!",
+            "x = -1, y = 1\no!",
+            "3o$3o!",
+        ] {
+            assert!(Pattern::parse_rle(text).is_err(), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_run_counts() {
+        for text in [
+            "x = 3, y = 1\n0o!",
+            "x = 3, y = 1\n99999999999999999999999o!",
+            "x = 3, y = 1\n4o!",
+            "x = 3, y = 1\n2$o!",
+            "x = 3, y = 1\n3q!",
+        ] {
+            assert!(Pattern::parse_rle(text).is_err(), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn rejects_runs_past_the_header_before_growing() {
+        for text in [
+            "x = 3, y = 1\n999999999999o!",
+            "x = 3, y = 1\nob999999999999b!",
+            "x = 3, y = 2\n999999999999$o!",
+            "x = 3, y = 0\no!",
+        ] {
+            assert!(Pattern::parse_rle(text).is_err(), "{:?}", text);
+        }
+    }
+}
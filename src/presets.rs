@@ -0,0 +1,60 @@
+use crate::seed::Seed;
+use crate::theme::Theme;
+use crate::tick_rate::TickRate;
+use crate::Error;
+use std::path::PathBuf;
+
+/// A named bundle of settings from the `[presets]` table of the
+/// configuration file, given with `--preset` or switched to at runtime.
+/// What a preset leaves out stays as it is.
+#[derive(Clone)]
+pub struct Preset {
+    pub name: String,
+    pub rule: Option<u8>,
+    pub seed: Option<Seed>,
+    pub theme: Option<Theme>,
+    /// The file the theme came from, if it is not a built-in one.
+    pub theme_file: Option<PathBuf>,
+    pub tick_rate: Option<TickRate>,
+}
+
+impl Preset {
+    /// Makes a preset out of the arguments its table in the configuration
+    /// file stands for.
+    pub fn parse(name: &str, arguments: &[String]) -> Result<Preset, Error> {
+        let mut preset = Preset {
+            name: name.to_string(),
+            rule: None,
+            seed: None,
+            theme: None,
+            theme_file: None,
+            tick_rate: None,
+        };
+        for arg in arguments {
+            let (arg, value) = arg.split_once('=').unwrap_or((arg, ""));
+            if !["--rule", "--seed", "--theme", "--tick-rate"].contains(&arg) {
+                return Err(Error::Config(format!(
+                    "preset '{}' can only set rule, seed, theme and tick-rate, not '{}'",
+                    name,
+                    arg.trim_start_matches('-')
+                )));
+            }
+            match arg {
+                "--rule" => {
+                    preset.rule = Some(
+                        value
+                            .parse()
+                            .map_err(|_| Error::InvalidRule(value.to_string()))?,
+                    )
+                }
+                "--seed" => preset.seed = Some(value.parse()?),
+                "--theme" => {
+                    preset.theme = Some(Theme::find(value)?);
+                    preset.theme_file = (!Theme::is_built_in(value)).then(|| PathBuf::from(value));
+                }
+                _ => preset.tick_rate = Some(value.parse()?),
+            }
+        }
+        Ok(preset)
+    }
+}
@@ -0,0 +1,152 @@
+//! A Python module, built with the `python` feature, for driving the
+//! simulation from scripts and notebooks. Python only asks for steps and
+//! reads the grid, the steps themselves run in Rust with the GIL released.
+//!
+//! ```python
+//! import cellular_automata
+//! import numpy
+//!
+//! automaton = cellular_automata.Automaton(rule=30, seed="center")
+//! automaton.step(50)
+//! grid = numpy.frombuffer(automaton.grid(), dtype=numpy.uint8).reshape(automaton.shape)
+//! ```
+
+use crate::seed::Seed;
+use crate::Error;
+use crate::{Automaton, SimContext, StepMode, GRID_X_SIZE, GRID_Y_SIZE};
+use pyo3::exceptions::{PyOSError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+impl From<Error> for PyErr {
+    fn from(error: Error) -> PyErr {
+        match error {
+            Error::Io { .. } => PyOSError::new_err(error.to_string()),
+            _ => PyValueError::new_err(error.to_string()),
+        }
+    }
+}
+
+/// An automaton on the grid, stepping a row, or a generation of Game of
+/// Life, at a time.
+#[pyclass(name = "Automaton")]
+pub struct PyAutomaton {
+    context: SimContext,
+}
+
+#[pymethods]
+impl PyAutomaton {
+    /// Grows `rule` from `seed`, `center` or a number, as `elementary` or
+    /// `life`. Endless automata scroll on once the grid is full, the
+    /// others stop.
+    #[new]
+    #[pyo3(signature = (rule = 30, seed = "center", automaton = "elementary", endless = false))]
+    fn new(rule: u8, seed: &str, automaton: &str, endless: bool) -> PyResult<PyAutomaton> {
+        let context = SimContext::builder()
+            .automaton(automaton.parse::<Automaton>()?)
+            .rule(rule)
+            .seed(seed.parse::<Seed>()?)
+            .step_mode(StepMode::Row)
+            .endless(endless)
+            .build()?;
+        Ok(PyAutomaton { context })
+    }
+
+    /// The elementary rule. Setting it starts over from the seed.
+    #[getter]
+    fn rule(&self) -> u8 {
+        self.context.rule
+    }
+
+    #[setter]
+    fn set_rule(&mut self, rule: u8) {
+        self.context.reset(rule, self.context.seed);
+    }
+
+    /// The seed, like `center`. Setting it starts over with the rule.
+    #[getter]
+    fn seed(&self) -> String {
+        self.context.seed.to_string()
+    }
+
+    #[setter]
+    fn set_seed(&mut self, seed: &str) -> PyResult<()> {
+        let seed: Seed = seed.parse()?;
+        self.context.reset(self.context.rule, seed);
+        Ok(())
+    }
+
+    /// The number of rows completed since the seed, or of generations.
+    #[getter]
+    fn generation(&self) -> u64 {
+        self.context.generation()
+    }
+
+    /// The number of live cells on the grid.
+    #[getter]
+    fn population(&self) -> usize {
+        self.context.population()
+    }
+
+    /// The rows and columns of `grid`.
+    #[getter]
+    fn shape(&self) -> (u32, u32) {
+        (GRID_Y_SIZE, GRID_X_SIZE)
+    }
+
+    /// Computes `n` rows, or generations. A full grid that is not
+    /// endless stays as it is.
+    #[pyo3(signature = (n = 1))]
+    fn step(&mut self, py: Python<'_>, n: u64) {
+        let context = &mut self.context;
+        py.detach(|| {
+            for _ in 0..n {
+                context.advance();
+            }
+        });
+    }
+
+    /// Undoes `n` steps, as far as the history goes.
+    #[pyo3(signature = (n = 1))]
+    fn rewind(&mut self, n: u64) {
+        for _ in 0..n {
+            self.context.rewind();
+        }
+    }
+
+    /// The grid row by row from the top, a byte for every cell, 1 for
+    /// live ones and 0 for dead ones.
+    fn grid<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        let mut cells = Vec::with_capacity((GRID_X_SIZE * GRID_Y_SIZE) as usize);
+        for y in 0..GRID_Y_SIZE as usize {
+            cells.extend(self.context.points.iter().map(|column| column[y] as u8));
+        }
+        PyBytes::new(py, &cells)
+    }
+
+    /// The next `n` rows of an elementary automaton one after another, a
+    /// byte for every cell like `grid`, scrolling on once the grid is full
+    /// so there are always more.
+    fn rows<'py>(&mut self, py: Python<'py>, n: usize) -> Bound<'py, PyBytes> {
+        let context = &mut self.context;
+        let cells = py.detach(|| {
+            context
+                .rows()
+                .take(n)
+                .flatten()
+                .map(|alive| alive as u8)
+                .collect::<Vec<u8>>()
+        });
+        PyBytes::new(py, &cells)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("<Automaton {}>", self.context.title())
+    }
+}
+
+/// The module Python imports as `cellular_automata`.
+#[pymodule]
+fn cellular_automata(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyAutomaton>()
+}
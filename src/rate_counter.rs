@@ -0,0 +1,42 @@
+use std::time::{Duration, Instant};
+
+/// How long events are counted before the rate is recomputed.
+const WINDOW: Duration = Duration::from_secs(1);
+
+/// Turns a stream of events, like presented frames or simulation ticks,
+/// into a rate per second.
+pub struct RateCounter {
+    count: u32,
+    since: Instant,
+    rate: f64,
+}
+
+impl RateCounter {
+    pub fn new(now: Instant) -> RateCounter {
+        RateCounter {
+            count: 0,
+            since: now,
+            rate: 0.0,
+        }
+    }
+
+    pub fn add(&mut self, now: Instant, count: u32) {
+        self.roll_over(now);
+        self.count += count;
+    }
+
+    /// The rate measured over the last complete window.
+    pub fn rate(&mut self, now: Instant) -> f64 {
+        self.roll_over(now);
+        self.rate
+    }
+
+    fn roll_over(&mut self, now: Instant) {
+        let elapsed = now.duration_since(self.since);
+        if elapsed >= WINDOW {
+            self.rate = self.count as f64 / elapsed.as_secs_f64();
+            self.count = 0;
+            self.since = now;
+        }
+    }
+}
@@ -0,0 +1,110 @@
+//! The modes `--mode` picks by name: the built-in automata, elementary
+//! rules known by a name of their own, and whatever the configuration
+//! file or a script registers on top.
+//!
+//! ```
+//! use cellular_automata::registry::{Mode, Registry};
+//! use cellular_automata::Automaton;
+//!
+//! let mut registry = Registry::built_in();
+//! registry.register(Mode::rule("majority", 232, "Every cell votes with its neighbors"))?;
+//! assert_eq!(registry.get("majority")?.rule, Some(232));
+//! assert_eq!(registry.get("life")?.automaton, Automaton::Life);
+//! # Ok::<(), cellular_automata::Error>(())
+//! ```
+
+use crate::{Automaton, Error};
+
+/// An automaton to run, under a name.
+#[derive(Clone, Debug)]
+pub struct Mode {
+    /// What `--mode` calls it.
+    pub name: String,
+    /// A line on what it shows.
+    pub description: String,
+    /// Which automaton runs.
+    pub automaton: Automaton,
+    /// The elementary rule it runs, or `None` to keep the one given.
+    pub rule: Option<u8>,
+}
+
+impl Mode {
+    /// An elementary automaton running `rule`.
+    pub fn rule(name: &str, rule: u8, description: &str) -> Mode {
+        Mode {
+            name: name.to_string(),
+            description: description.to_string(),
+            automaton: Automaton::Elementary,
+            rule: Some(rule),
+        }
+    }
+}
+
+/// The modes there are, in the order they were registered.
+#[derive(Clone, Debug)]
+pub struct Registry {
+    modes: Vec<Mode>,
+}
+
+impl Registry {
+    /// The automata themselves, and some rules worth a name.
+    pub fn built_in() -> Registry {
+        let automaton = |name: &str, automaton: Automaton, description: &str| Mode {
+            name: name.to_string(),
+            description: description.to_string(),
+            automaton,
+            rule: None,
+        };
+        Registry {
+            modes: vec![
+                automaton(
+                    "elementary",
+                    Automaton::Elementary,
+                    "An elementary automaton running --rule",
+                ),
+                automaton("life", Automaton::Life, "Conway's Game of Life"),
+                Mode::rule("sierpinski", 90, "Rule 90, the Sierpinski triangle"),
+                Mode::rule("rule110", 110, "Rule 110, which is Turing complete"),
+                Mode::rule("traffic", 184, "Rule 184, cars queueing along a road"),
+            ],
+        }
+    }
+
+    /// Adds `mode`, unless its name is taken.
+    pub fn register(&mut self, mode: Mode) -> Result<(), Error> {
+        if self.modes.iter().any(|old| old.name == mode.name) {
+            return Err(Error::Config(format!(
+                "mode '{}' is already registered",
+                mode.name
+            )));
+        }
+        self.modes.push(mode);
+        Ok(())
+    }
+
+    /// The mode called `name`.
+    pub fn get(&self, name: &str) -> Result<&Mode, Error> {
+        self.modes
+            .iter()
+            .find(|mode| mode.name == name)
+            .ok_or_else(|| {
+                let names: Vec<&str> = self.modes.iter().map(|mode| mode.name.as_str()).collect();
+                Error::Parse(format!(
+                    "unknown mode '{}', expected one of {}",
+                    name,
+                    names.join(", ")
+                ))
+            })
+    }
+
+    /// Every mode.
+    pub fn iter(&self) -> impl Iterator<Item = &Mode> {
+        self.modes.iter()
+    }
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Registry::built_in()
+    }
+}
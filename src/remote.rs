@@ -0,0 +1,184 @@
+//! Remote control of the first window with `--control`: commands like
+//! `pause`, `step`, `set-rule 110` or `screenshot`, a line each, read from
+//! stdin or a Unix socket, so scripts and hotkey tools like OBS can drive
+//! the window. Every action the config file can bind keys to is a command.
+
+use crate::command::{self, Command};
+use crate::Error;
+use log::{debug, warn};
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+
+/// Where the commands come from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ControlSource {
+    Stdin,
+    /// A Unix socket at this path, which every connection can send
+    /// commands to and gets `ok` or the error back for each.
+    Socket(PathBuf),
+}
+
+impl FromStr for ControlSource {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "stdin" | "-" => Ok(ControlSource::Stdin),
+            "" => Err(Error::Parse(
+                "invalid control source '', expected stdin or a socket path".to_string(),
+            )),
+            path => Ok(ControlSource::Socket(PathBuf::from(path))),
+        }
+    }
+}
+
+/// Wakes the event loop up, even while it waits for input.
+pub type WakeUp = Arc<dyn Fn() + Send + Sync>;
+
+/// The commands read in the background, waiting for the event loop.
+pub struct Remote {
+    commands: Receiver<Command>,
+    /// The socket to remove once the window closes.
+    socket: Option<PathBuf>,
+}
+
+impl Remote {
+    /// Starts reading commands from `source`, calling `wake_up` for every
+    /// command.
+    pub fn spawn(source: &ControlSource, wake_up: WakeUp) -> Result<Remote, Error> {
+        let (sender, commands) = mpsc::channel();
+        match source {
+            ControlSource::Stdin => {
+                thread::spawn(move || {
+                    serve(io::stdin().lock(), None::<io::Stdout>, &sender, &*wake_up)
+                });
+                Ok(Remote {
+                    commands,
+                    socket: None,
+                })
+            }
+            ControlSource::Socket(path) => {
+                listen(path, sender, wake_up)?;
+                Ok(Remote {
+                    commands,
+                    socket: Some(path.clone()),
+                })
+            }
+        }
+    }
+
+    /// The commands that came in since the last call.
+    pub fn commands(&self) -> Vec<Command> {
+        self.commands.try_iter().collect()
+    }
+}
+
+impl Drop for Remote {
+    fn drop(&mut self) {
+        if let Some(path) = &self.socket {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+/// Parses a line like `pause` or `set-rule 110`. Empty lines and lines
+/// starting with `#` are no command.
+pub fn parse(line: &str) -> Result<Option<Command>, Error> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let command = match words.as_slice() {
+        ["set-rule", rule] => Command::SetRule(
+            rule.parse()
+                .map_err(|_| Error::InvalidRule(rule.to_string()))?,
+        ),
+        ["set-tick-rate", tick_rate] => Command::SetTickRate(tick_rate.parse()?),
+        [name] => command::action(name)
+            .ok_or_else(|| Error::Parse(format!("unknown command '{}'", name)))?,
+        _ => return Err(Error::Parse(format!("invalid command '{}'", line))),
+    };
+    Ok(Some(command))
+}
+
+/// Passes on the commands in `lines` until they end or the window is
+/// gone, answering every line on `reply` if there is one. Without one the
+/// errors go to the log.
+fn serve(
+    lines: impl BufRead,
+    mut reply: Option<impl Write>,
+    sender: &Sender<Command>,
+    wake_up: &(dyn Fn() + Send + Sync),
+) {
+    for line in lines.lines() {
+        let Ok(line) = line else {
+            return;
+        };
+        let answer = match parse(&line) {
+            Ok(None) => continue,
+            Ok(Some(command)) => {
+                debug!("Remote command {:?}", command);
+                if sender.send(command).is_err() {
+                    return;
+                }
+                wake_up();
+                "ok".to_string()
+            }
+            Err(e) if reply.is_none() => {
+                warn!("{}", e);
+                continue;
+            }
+            Err(e) => format!("error: {}", e),
+        };
+        if let Some(out) = &mut reply {
+            if writeln!(out, "{}", answer)
+                .and_then(|()| out.flush())
+                .is_err()
+            {
+                return;
+            }
+        }
+    }
+}
+
+/// Listens on the socket at `path`, taking over a socket left behind by a
+/// window that crashed, but not one still in use.
+#[cfg(unix)]
+fn listen(path: &std::path::Path, sender: Sender<Command>, wake_up: WakeUp) -> Result<(), Error> {
+    use std::os::unix::net::{UnixListener, UnixStream};
+
+    if path.exists() {
+        if UnixStream::connect(path).is_ok() {
+            return Err(Error::Config(format!(
+                "another window is listening on '{}'",
+                path.display()
+            )));
+        }
+        std::fs::remove_file(path).map_err(|e| Error::io("remove", path, e))?;
+    }
+    let listener = UnixListener::bind(path).map_err(|e| Error::io("listen on", path, e))?;
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let (sender, wake_up) = (sender.clone(), Arc::clone(&wake_up));
+            thread::spawn(move || {
+                if let Ok(reader) = stream.try_clone() {
+                    serve(io::BufReader::new(reader), Some(stream), &sender, &*wake_up);
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn listen(path: &std::path::Path, _: Sender<Command>, _: WakeUp) -> Result<(), Error> {
+    Err(Error::Config(format!(
+        "cannot listen on '{}', sockets need Unix, use --control stdin",
+        path.display()
+    )))
+}
@@ -0,0 +1,148 @@
+use crate::image::RgbaImage;
+use crate::options;
+use crate::seed::Seed;
+use crate::theme::Theme;
+use crate::Error;
+use cellular_automata::next_row;
+use clap::{value_parser, CommandFactory, FromArgMatches, Parser};
+use std::path::PathBuf;
+
+const DEFAULT_SIZE: (u32, u32) = (3840, 2160);
+const DEFAULT_CELL_SIZE: u32 = 4;
+
+/// The arguments after `render`.
+#[derive(Parser)]
+#[command(
+    name = "cellular_automata render",
+    override_usage = "cellular_automata render [OPTIONS] --out FILE",
+    about = "Draws an elementary automaton as big as a desktop wallpaper into a PNG,
+without opening a window.",
+    help_template = "{usage-heading} {usage}\n\n{about}\n\nOptions:\n{options}",
+    disable_version_flag = true,
+    args_override_self = true,
+    next_line_help = true
+)]
+struct Args {
+    /// The PNG to save
+    #[arg(long, value_name = "FILE")]
+    out: PathBuf,
+    /// The size of the picture in pixels [default: 3840x2160]
+    #[arg(long, value_name = "WIDTHxHEIGHT", value_parser = parse_size)]
+    size: Option<(u32, u32)>,
+    /// The elementary rule, 0 to 255 [default: 30]
+    #[arg(long, value_name = "RULE", value_parser = options::parse_rule)]
+    rule: Option<u8>,
+    /// 'center' for a single live cell, or a number to draw a random first
+    /// row from [default: center]
+    #[arg(verbatim_doc_comment, long, value_name = "SEED")]
+    seed: Option<Seed>,
+    /// The pixels per cell, at most the picture's width and height
+    /// [default: 4]
+    #[arg(verbatim_doc_comment, long, value_name = "PX", value_parser = value_parser!(u32).range(1..))]
+    cell: Option<u32>,
+    /// A built-in theme or a TOML theme file [default: classic or paper,
+    /// following the system's dark or light preference]
+    #[arg(verbatim_doc_comment, long, value_name = "NAME|FILE")]
+    theme: Option<String>,
+    /// Read defaults for these options from FILE instead of
+    /// rule30/config.toml in the platform's configuration directory
+    #[arg(verbatim_doc_comment, long, value_name = "FILE")]
+    config: Option<PathBuf>,
+}
+
+/// The command line `render` takes.
+pub fn command() -> clap::Command {
+    Args::command()
+}
+
+/// What the `render` command draws, from the arguments after `render`.
+pub struct Render {
+    pub out: PathBuf,
+    pub size: (u32, u32),
+    pub rule: u8,
+    pub seed: Seed,
+    pub cell_size: u32,
+    pub theme: Theme,
+}
+
+impl Render {
+    /// `None` when the usage was asked for.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Option<Render>, Error> {
+        let Some(layered) = options::parse_layered(command(), args.into_iter().collect())? else {
+            return Ok(None);
+        };
+        let args =
+            Args::from_arg_matches(&layered.matches).map_err(|e| Error::Config(e.to_string()))?;
+        let (size, cell_size) = (
+            args.size.unwrap_or(DEFAULT_SIZE),
+            args.cell.unwrap_or(DEFAULT_CELL_SIZE),
+        );
+        // The picture has to hold at least one whole cell.
+        if cell_size > size.0.min(size.1) {
+            return Err(Error::Config(format!(
+                "invalid cell size {}, expected at most {} pixels for a {}x{} picture",
+                cell_size,
+                size.0.min(size.1),
+                size.0,
+                size.1
+            )));
+        }
+        Ok(Some(Render {
+            out: args.out,
+            size,
+            rule: args.rule.unwrap_or(30),
+            seed: args.seed.unwrap_or(Seed::Center),
+            cell_size,
+            theme: match &args.theme {
+                Some(name) => Theme::find(name)?,
+                None => Theme::system_default(),
+            },
+        }))
+    }
+
+    /// Draws the picture and saves it to `out`.
+    pub fn save(&self) -> Result<(), Error> {
+        self.image().save_png(&self.out)
+    }
+
+    fn image(&self) -> RgbaImage {
+        let (width, height) = self.size;
+        let columns = width.div_ceil(self.cell_size) as usize;
+        let rows = height.div_ceil(self.cell_size) as usize;
+        // Cells can reach in by one column every row, so growing the rows
+        // that far out on either side keeps the edges from showing.
+        let margin = rows;
+        let mut row = vec![false; margin];
+        row.extend(self.seed.cells(columns));
+        row.extend(vec![false; margin]);
+
+        let (live, dead) = (self.theme.live_cell, self.theme.dead_cell);
+        let mut pixels = Vec::with_capacity(width as usize * height as usize * 4);
+        for y in 0..height {
+            if y > 0 && y % self.cell_size == 0 {
+                row = next_row(self.rule, &row);
+            }
+            for x in 0..width as usize {
+                let color = match row[margin + x / self.cell_size as usize] {
+                    true => live,
+                    false => dead,
+                };
+                pixels.extend_from_slice(&[color.r, color.g, color.b, 255]);
+            }
+        }
+        RgbaImage {
+            width,
+            height,
+            pixels,
+        }
+    }
+}
+
+fn parse_size(size: &str) -> Result<(u32, u32), Error> {
+    let invalid = || Error::Parse(format!("invalid size '{}', expected WIDTHxHEIGHT", size));
+    let (width, height) = size.split_once('x').ok_or_else(invalid)?;
+    match (width.parse(), height.parse()) {
+        (Ok(width), Ok(height)) if width > 0 && height > 0 => Ok((width, height)),
+        _ => Err(invalid()),
+    }
+}
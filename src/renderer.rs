@@ -0,0 +1,1586 @@
+use crate::background::{Background, BackgroundMode};
+use crate::bookmarks::Bookmark;
+use crate::camera::Camera;
+use crate::center_column::ColumnFormat;
+use crate::color::Color;
+use crate::coloring::{CellColoring, Palette};
+use crate::command::Command;
+use crate::frame_capture::FrameCapture;
+use crate::image::RgbaImage;
+use crate::library::LIBRARY;
+use crate::minimap::Minimap;
+use crate::rate_counter::RateCounter;
+use crate::scale_filter::ScaleFilter;
+use crate::screenshot::{DiagramFormat, OutputNames};
+use crate::theme::Theme;
+use crate::video::VideoRecorder;
+use crate::view::View;
+use crate::window_geometry::WindowGeometry;
+use crate::{
+    center_column, coloring, font, life, screenshot, triangles, view, Automaton, Cells, Error,
+    HistoryEntry, Point, SimContext, SimulationState, StepMode, DOT_SIZE_IN_PXS, FRAMES_PER_SECOND,
+    GRID_X_SIZE, GRID_Y_SIZE, PANE_SIZE, SEED_ROW,
+};
+use log::{error, warn};
+use sdl2::pixels::PixelFormatEnum;
+use sdl2::rect::Rect;
+use sdl2::render::{BlendMode, WindowCanvas};
+use sdl2::sys::SDL_WindowFlags;
+use sdl2::video::{FullscreenType, Window};
+use std::fs;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Grid lines only show once cells are at least this large on screen.
+const MIN_GRID_LINE_CELL_SIZE_IN_PXS: f32 = 8.0;
+/// The opacity of the scanner trail right behind the scanner.
+const SCANNER_TRAIL_OPACITY: f32 = 0.6;
+const HUD_TEXT_SCALE: u32 = 2;
+const HUD_PADDING: u32 = 4;
+const STATUS_BAR_TEXT_SCALE: u32 = 1;
+const HELP_TEXT_SCALE: u32 = 1;
+/// How wide, in cells, a triangle has to be to be highlighted.
+const MIN_TRIANGLE_WIDTH: usize = 3;
+/// How opaque the highlight over a triangle is.
+const TRIANGLE_TINT_ALPHA: u8 = 110;
+/// The cells a chosen cell can influence and those that influenced it.
+const FORWARD_CONE_COLOR: Color = Color::RGBA(230, 159, 0, 100);
+const BACKWARD_CONE_COLOR: Color = Color::RGBA(86, 180, 233, 100);
+const RECORDING_COLOR: Color = Color::RGB(220, 30, 30);
+const MINIMAP_MARGIN: i32 = 4;
+const INSPECTOR_TEXT_SCALE: u32 = 1;
+/// How many generations the stacked view shows.
+const STACK_DEPTH: usize = 24;
+/// How much of the live cell color the oldest generation in the stack keeps.
+const STACK_MIN_BRIGHTNESS: f32 = 0.25;
+/// How far, in window pixels, the phosphor glow of the CRT effect bleeds.
+const CRT_GLOW_RADIUS: i32 = 2;
+/// How bright the glow is, out of 255.
+const CRT_GLOW_STRENGTH: u8 = 40;
+/// How dark the gaps between the scanlines are, out of 255.
+const CRT_SCANLINE_DARKNESS: u8 = 70;
+/// How far the vignette reaches in from the edges, as a fraction of the
+/// smaller window dimension.
+const CRT_VIGNETTE_SIZE: f32 = 0.15;
+/// The radius the spiral view leaves empty in the middle, in rings.
+const SPIRAL_INNER_RINGS: f32 = 4.0;
+/// How far the inspector keeps away from the mouse pointer.
+const INSPECTOR_OFFSET: i32 = 12;
+const STATUS_BAR_HEIGHT_IN_PXS: u32 = 15;
+/// How long a notice shows in the status bar.
+const NOTICE_DURATION: Duration = Duration::from_secs(3);
+/// Keeps the window from disappearing altogether.
+const MIN_OPACITY: f32 = 0.2;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Forward,
+    Backward,
+}
+
+pub struct Renderer {
+    canvas: WindowCanvas,
+    camera: Camera,
+    /// The built-in themes, preceded by the one given on the command line
+    /// unless that was one of them.
+    themes: Vec<Theme>,
+    current_theme: usize,
+    pub coloring: CellColoring,
+    pub view: View,
+    pub palette: Palette,
+    pub show_grid_lines: bool,
+    pub show_scanner: bool,
+    pub show_hud: bool,
+    pub show_rates: bool,
+    /// The page of the help shown, `None` while it is hidden.
+    pub help_page: Option<usize>,
+    /// The pattern picked in the library, `None` while it is closed.
+    pub library: Option<usize>,
+    /// The generations marked in the grid, by generation.
+    pub bookmarks: Vec<Bookmark>,
+    /// The key bindings the help lists, as the keys and what they do.
+    pub help: Vec<(String, &'static str)>,
+    /// Frames actually presented, which drops while idle.
+    pub measured_fps: RateCounter,
+    /// Simulation ticks taken, whatever the tick rate is set to.
+    pub measured_tps: RateCounter,
+    /// How many cells the fading trail behind the scanner covers.
+    pub scanner_trail: u32,
+    /// Replaces the theme's scanner color when set.
+    pub scanner_color: Option<Color>,
+    /// How far the picture is faded to the background, from 0.0 to 1.0.
+    pub fade: f32,
+    /// Whether the picture gets scanlines, a vignette and a glow like an
+    /// old CRT monitor.
+    pub crt: bool,
+    /// How far, in rows, the grid is drawn below where it is, so that
+    /// endless mode scrolls smoothly instead of a whole row at a time.
+    pub scroll_offset: f32,
+    /// How the textures are filtered when they are scaled.
+    pub scale_filter: ScaleFilter,
+    /// Where screenshots, diagrams and videos are saved to.
+    pub output: OutputNames,
+    /// Whether to save the next frame drawn as a screenshot.
+    pub screenshot_requested: bool,
+    /// The file extension videos are recorded with, which picks the format.
+    pub video_format: String,
+    video: Option<VideoRecorder>,
+    /// Saves a picture of the window every so many generations.
+    pub frame_capture: Option<FrameCapture>,
+    /// An image drawn with the grid, scaled to `PANE_SIZE`.
+    pub background: Option<Background>,
+    minimap: Minimap,
+    pub show_inspector: bool,
+    /// Whether the triangles of dead cells get tinted by their size.
+    pub show_triangles: bool,
+    /// The cell whose light cones are shaded, the same one in every pane.
+    pub light_cone: Option<Point>,
+    /// Where the mouse pointer is, if it is over the window.
+    pub hover: Option<(i32, i32)>,
+    /// How many automata are drawn side by side.
+    pane_count: usize,
+    /// What the status bar shows instead of the hints, and since when.
+    notice: Option<(String, Instant)>,
+}
+
+/// The columns and rows the given number of panes are arranged in.
+fn pane_layout(count: usize) -> (u32, u32) {
+    match count {
+        1 => (1, 1),
+        2 => (2, 1),
+        _ => (2, 2),
+    }
+}
+
+/// The height of a text panel with the given number of lines.
+fn panel_height(lines: usize, scale: u32) -> u32 {
+    lines as u32 * (font::text_height(scale) + HUD_PADDING) + HUD_PADDING
+}
+
+fn panel_width(lines: &[String], scale: u32) -> u32 {
+    let widest = lines
+        .iter()
+        .map(|line| font::text_width(line, scale))
+        .max()
+        .unwrap_or(0);
+    widest + 2 * HUD_PADDING
+}
+
+impl Renderer {
+    pub fn new(window: Window, theme: Theme) -> Result<Renderer, Error> {
+        let canvas = window
+            .into_canvas()
+            .build()
+            .map_err(|e| e.to_string())
+            .map_err(Error::Sdl)?;
+        let mut themes = Theme::built_in();
+        let current_theme = match themes.iter().position(|t| t.name == theme.name) {
+            Some(index) => index,
+            None => {
+                themes.insert(0, theme);
+                0
+            }
+        };
+        let mut renderer = Renderer {
+            canvas,
+            camera: Camera::new(DOT_SIZE_IN_PXS as f32),
+            themes,
+            current_theme,
+            coloring: CellColoring::Flat,
+            view: View::Flat,
+            palette: Palette::Viridis,
+            show_grid_lines: false,
+            show_scanner: true,
+            show_hud: true,
+            show_rates: false,
+            help_page: None,
+            library: None,
+            bookmarks: Vec::new(),
+            help: Vec::new(),
+            measured_fps: RateCounter::new(Instant::now()),
+            measured_tps: RateCounter::new(Instant::now()),
+            scanner_trail: 0,
+            scanner_color: None,
+            fade: 0.0,
+            crt: false,
+            scroll_offset: 0.0,
+            scale_filter: ScaleFilter::Nearest,
+            output: OutputNames::default(),
+            screenshot_requested: false,
+            video_format: "mp4".to_string(),
+            video: None,
+            frame_capture: None,
+            background: None,
+            minimap: Minimap::new(),
+            show_inspector: false,
+            show_triangles: false,
+            light_cone: None,
+            hover: None,
+            pane_count: 1,
+            notice: None,
+        };
+        renderer.scale_to_window()?;
+        Ok(renderer)
+    }
+    pub fn window_id(&self) -> u32 {
+        self.canvas.window().id()
+    }
+    pub fn geometry(&self) -> WindowGeometry {
+        WindowGeometry::of(self.canvas.window())
+    }
+    pub fn restore_geometry(
+        &mut self,
+        geometry: WindowGeometry,
+        keep_size: bool,
+    ) -> Result<(), Error> {
+        geometry.restore(self.canvas.window_mut(), keep_size)?;
+        self.scale_to_window()
+    }
+    /// Shows `text` in the status bar for a few seconds.
+    pub fn show_notice(&mut self, text: &str) {
+        self.notice = Some((text.to_uppercase(), Instant::now()));
+    }
+    pub fn set_title(&mut self, title: &str) -> Result<(), Error> {
+        self.canvas
+            .window_mut()
+            .set_title(title)
+            .map_err(|e| Error::Sdl(e.to_string()))
+    }
+    /// The size of everything drawn for `pane_count` automata, including
+    /// the status bar.
+    pub fn size_for(pane_count: usize) -> (u32, u32) {
+        let (columns, rows) = pane_layout(pane_count);
+        (
+            columns * PANE_SIZE.0,
+            rows * PANE_SIZE.1 + STATUS_BAR_HEIGHT_IN_PXS,
+        )
+    }
+    pub fn set_pane_count(&mut self, pane_count: usize) -> Result<(), Error> {
+        self.pane_count = pane_count;
+        self.scale_to_window()
+    }
+    /// Scales the whole picture with the window, keeping its aspect ratio,
+    /// instead of drawing it at its native pixel size.
+    fn scale_to_window(&mut self) -> Result<(), Error> {
+        let (width, height) = Self::size_for(self.pane_count);
+        self.canvas
+            .set_logical_size(width, height)
+            .map_err(|e| Error::Sdl(e.to_string()))
+    }
+    fn pane_rect(&self, index: usize) -> Rect {
+        let (columns, _) = pane_layout(self.pane_count);
+        let (column, row) = (index as u32 % columns, index as u32 / columns);
+        Rect::new(
+            (column * PANE_SIZE.0) as i32,
+            (row * PANE_SIZE.1) as i32,
+            PANE_SIZE.0,
+            PANE_SIZE.1,
+        )
+    }
+    /// The pane under the given position, and the position within it.
+    fn pane_at(&self, (x, y): (i32, i32)) -> Option<(usize, (i32, i32))> {
+        (0..self.pane_count).find_map(|index| {
+            let pane = self.pane_rect(index);
+            pane.contains_point((x, y))
+                .then(|| (index, (x - pane.x(), y - pane.y())))
+        })
+    }
+    /// The size of the area being drawn to, in the units drawing uses.
+    fn viewport(&self) -> (u32, u32) {
+        match self.canvas.logical_size() {
+            (0, 0) => self.canvas.output_size().unwrap_or((0, 0)),
+            size => size,
+        }
+    }
+    /// The middle of a pane, which all panes share a camera for.
+    fn viewport_center(&self) -> (i32, i32) {
+        (PANE_SIZE.0 as i32 / 2, PANE_SIZE.1 as i32 / 2)
+    }
+    pub fn zoom_in(&mut self) {
+        self.zoom_at(1, self.viewport_center());
+    }
+    pub fn zoom_out(&mut self) {
+        self.zoom_at(-1, self.viewport_center());
+    }
+    /// Zooms and pans so that every row computed so far fills the pane.
+    /// The whole grid always fits at a pixel per cell, so there is no need
+    /// to go below that.
+    pub fn zoom_to_fit(&mut self, context: &SimContext) {
+        self.camera
+            .fit((GRID_X_SIZE, context.computed_rows()), PANE_SIZE);
+    }
+    /// Moves the view by the given number of pixels.
+    pub fn pan(&mut self, dx: i32, dy: i32) {
+        self.camera.pan(dx, dy);
+    }
+    pub fn pan_cells(&mut self, dx: i32, dy: i32) {
+        self.camera.pan(self.camera.edge(dx), self.camera.edge(dy));
+    }
+    /// Zooms in by `steps` levels, or out for negative values, around the
+    /// given window position.
+    pub fn zoom_at(&mut self, steps: i32, anchor: (i32, i32)) {
+        let anchor = self.pane_at(anchor).map_or(anchor, |(_, local)| local);
+        for _ in 0..steps.abs() {
+            match steps > 0 {
+                true => self.camera.zoom_in(anchor),
+                false => self.camera.zoom_out(anchor),
+            }
+        }
+    }
+    /// Where the minimap goes, in the bottom right corner of the grid
+    /// area, or `None` while the whole grid is visible anyway.
+    fn minimap_rect(&self) -> Option<Rect> {
+        let (width, height) = self.viewport();
+        let height = height.saturating_sub(STATUS_BAR_HEIGHT_IN_PXS);
+        let pane = Rect::new(0, 0, PANE_SIZE.0, PANE_SIZE.1);
+        if pane.contains_rect(self.grid_rect()) {
+            return None;
+        }
+        Some(Rect::new(
+            width as i32 - GRID_X_SIZE as i32 - MINIMAP_MARGIN,
+            height as i32 - GRID_Y_SIZE as i32 - MINIMAP_MARGIN,
+            GRID_X_SIZE,
+            GRID_Y_SIZE,
+        ))
+    }
+    /// Jumps the view to the cell under `(x, y)` if that is on the
+    /// minimap. Returns whether it was.
+    pub fn click_minimap(&mut self, x: i32, y: i32) -> bool {
+        match self.minimap_rect() {
+            Some(minimap) if minimap.contains_point((x, y)) => {
+                let center = self.viewport_center();
+                self.camera
+                    .center_on((x - minimap.x(), y - minimap.y()), center);
+                true
+            }
+            _ => false,
+        }
+    }
+    /// Chooses the cell at the given window position to shade the light
+    /// cones of, or stops shading them if it already was. Returns whether
+    /// the position was over the grid at all.
+    pub fn click_cell(&mut self, x: i32, y: i32) -> bool {
+        let Some((_, local)) = self.pane_at((x, y)) else {
+            return false;
+        };
+        let point = self.camera.cell_at(local);
+        let Point(x, y) = point;
+        if !(0..GRID_X_SIZE as i32).contains(&x) || !(0..GRID_Y_SIZE as i32).contains(&y) {
+            return false;
+        }
+        self.light_cone = match self.light_cone {
+            Some(Point(cone_x, cone_y)) if (cone_x, cone_y) == (x, y) => None,
+            _ => Some(point),
+        };
+        true
+    }
+    /// Puts `theme` in the place of the theme called `name`, if there is
+    /// one, so that its new colors show up right away if it is current.
+    pub fn replace_theme(&mut self, name: &str, theme: Theme) {
+        if let Some(old) = self.themes.iter_mut().find(|old| old.name == name) {
+            *old = theme;
+        }
+    }
+    pub fn theme(&self) -> &Theme {
+        &self.themes[self.current_theme]
+    }
+    /// Inverts the colors of all themes at once, so that cycling through
+    /// them keeps the inversion.
+    pub fn invert_colors(&mut self) {
+        for theme in self.themes.iter_mut() {
+            *theme = theme.inverted();
+        }
+    }
+    /// Switches to the next theme that is dark where the current one is
+    /// light, or the other way around.
+    pub fn toggle_dark_light(&mut self) {
+        let dark = self.theme().is_dark();
+        let count = self.themes.len();
+        if let Some(offset) = (1..count)
+            .find(|offset| self.themes[(self.current_theme + offset) % count].is_dark() != dark)
+        {
+            self.current_theme = (self.current_theme + offset) % count;
+        }
+    }
+    pub fn next_theme(&mut self) {
+        self.current_theme = (self.current_theme + 1) % self.themes.len();
+    }
+    /// Switches to `theme`, or to the theme of the same name if there
+    /// already is one.
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.current_theme = match self.themes.iter().position(|old| old.name == theme.name) {
+            Some(index) => index,
+            None => {
+                self.themes.push(theme);
+                self.themes.len() - 1
+            }
+        };
+    }
+    /// Switches between a window and desktop fullscreen.
+    pub fn toggle_fullscreen(&mut self) -> Result<(), Error> {
+        let window = self.canvas.window_mut();
+        match window.fullscreen_state() {
+            FullscreenType::Off => window.set_fullscreen(FullscreenType::Desktop),
+            _ => window.set_fullscreen(FullscreenType::Off),
+        }
+        .map_err(Error::Sdl)
+    }
+    pub fn toggle_borderless(&mut self) {
+        let window = self.canvas.window_mut();
+        let borderless = window.window_flags() & SDL_WindowFlags::SDL_WINDOW_BORDERLESS as u32 != 0;
+        window.set_bordered(borderless);
+    }
+    pub fn toggle_always_on_top(&mut self) {
+        let window = self.canvas.window_mut();
+        window.set_always_on_top(!window.is_always_on_top());
+    }
+    /// Makes the window more opaque, or more transparent for a negative
+    /// `delta`. Not every platform supports this, in which case it does
+    /// nothing.
+    pub fn change_opacity(&mut self, delta: f32) {
+        let window = self.canvas.window_mut();
+        if let Ok(opacity) = window.opacity() {
+            let _ = window.set_opacity((opacity + delta).clamp(MIN_OPACITY, 1.0));
+        }
+    }
+    fn draw_dot(&mut self, point: &Point) -> Result<(), Error> {
+        self.canvas
+            .fill_rect(self.camera.cell_rect(point))
+            .map_err(Error::Sdl)?;
+
+        Ok(())
+    }
+    /// Draws every automaton into its own pane, with the overlays that
+    /// are not about a single one of them on top.
+    pub fn draw(&mut self, contexts: &[SimContext]) -> Result<(), Error> {
+        // The hint is shared by all windows, so it is set again on every
+        // frame for the textures this one creates.
+        sdl2::hint::set("SDL_RENDER_SCALE_QUALITY", self.scale_filter.hint());
+        self.canvas.set_draw_color(self.theme().background);
+        self.canvas.clear();
+        for (index, context) in contexts.iter().enumerate() {
+            let pane = self.pane_rect(index);
+            self.canvas.set_viewport(pane);
+            self.canvas
+                .set_clip_rect(Rect::new(0, 0, pane.width(), pane.height()));
+            match (self.view, context.automaton) {
+                (View::Stacked, Automaton::Life) => self.draw_stacked(context)?,
+                (View::Spiral, Automaton::Elementary) => self.draw_spiral(context)?,
+                _ => {
+                    let offset = self.camera.offset;
+                    let shift = self.scroll_offset * self.camera.cell_size;
+                    self.camera.offset.1 += shift.round() as i32;
+                    self.draw_background(context)?;
+                    self.draw_sim(context)?;
+                    self.draw_background_mask(context)?;
+                    self.draw_triangles(context)?;
+                    self.draw_light_cone(context)?;
+                    self.draw_grid_lines()?;
+                    self.draw_scanner(context)?;
+                    self.draw_bookmarks(context)?;
+                    self.camera.offset = offset;
+                }
+            }
+            self.draw_hud(context)?;
+        }
+        self.canvas.set_clip_rect(None);
+        self.canvas.set_viewport(None);
+        self.draw_pane_borders()?;
+
+        self.draw_minimap(&contexts[0])?;
+        self.draw_rates()?;
+        self.draw_inspector(contexts)?;
+        self.draw_status_bar(&contexts[0])?;
+        self.draw_help()?;
+        self.draw_library()?;
+        self.draw_crt()?;
+        self.draw_fade()?;
+        if std::mem::take(&mut self.screenshot_requested) {
+            self.save_screenshot(&contexts[0])?;
+        }
+        self.capture_frame(&contexts[0])?;
+        self.record_frame()?;
+        self.draw_recording_indicator()?;
+        Ok(())
+    }
+    /// Shows what was drawn last in the window.
+    pub fn present(&mut self) {
+        self.canvas.present();
+        self.measured_fps.add(Instant::now(), 1);
+    }
+
+    /// The area the whole grid covers in the window.
+    fn grid_rect(&self) -> Rect {
+        let top_left = self.camera.cell_rect(&Point(0, 0));
+        Rect::new(
+            top_left.x(),
+            top_left.y(),
+            self.camera.edge(GRID_X_SIZE as i32) as u32,
+            self.camera.edge(GRID_Y_SIZE as i32) as u32,
+        )
+    }
+
+    fn draw_background(&mut self, context: &SimContext) -> Result<(), Error> {
+        let theme = self.theme().clone();
+        self.canvas.set_draw_color(theme.dead_cell);
+        self.canvas
+            .fill_rect(self.grid_rect())
+            .map_err(Error::Sdl)?;
+        if self
+            .background
+            .as_ref()
+            .is_some_and(|background| background.mode == BackgroundMode::Behind)
+        {
+            self.draw_background_image(|_| true)?;
+        }
+        self.draw_paused_tint(context)
+    }
+
+    /// Reveals the background image through the live cells in mask mode.
+    fn draw_background_mask(&mut self, context: &SimContext) -> Result<(), Error> {
+        if self
+            .background
+            .as_ref()
+            .is_some_and(|background| background.mode == BackgroundMode::Mask)
+        {
+            self.draw_background_image(|(x, y)| context.points[x][y])?;
+        }
+        Ok(())
+    }
+
+    /// Draws the parts of the background image over the cells for which
+    /// `show` holds, in one piece when it holds for all of them.
+    fn draw_background_image(
+        &mut self,
+        show: impl Fn((usize, usize)) -> bool,
+    ) -> Result<(), Error> {
+        let Some(background) = &self.background else {
+            return Ok(());
+        };
+        let image = &background.image;
+        let texture_creator = self.canvas.texture_creator();
+        let mut texture = texture_creator
+            .create_texture_static(PixelFormatEnum::RGBA32, image.width, image.height)
+            .map_err(|e| e.to_string())
+            .map_err(Error::Sdl)?;
+        texture
+            .update(None, &image.pixels, image.pitch())
+            .map_err(|e| e.to_string())
+            .map_err(Error::Sdl)?;
+        texture.set_blend_mode(BlendMode::Blend);
+
+        if (0..GRID_X_SIZE as usize).all(|x| (0..GRID_Y_SIZE as usize).all(|y| show((x, y)))) {
+            return self
+                .canvas
+                .copy(&texture, None, self.grid_rect())
+                .map_err(Error::Sdl);
+        }
+        let (cell_width, cell_height) = (image.width / GRID_X_SIZE, image.height / GRID_Y_SIZE);
+        for x in 0..GRID_X_SIZE as usize {
+            for y in 0..GRID_Y_SIZE as usize {
+                if show((x, y)) {
+                    let source = Rect::new(
+                        (x as u32 * cell_width) as i32,
+                        (y as u32 * cell_height) as i32,
+                        cell_width,
+                        cell_height,
+                    );
+                    let cell = self.camera.cell_rect(&Point(x as i32, y as i32));
+                    self.canvas
+                        .copy(&texture, source, cell)
+                        .map_err(Error::Sdl)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn draw_paused_tint(&mut self, context: &SimContext) -> Result<(), Error> {
+        let theme = self.theme().clone();
+        if context.is_paused() {
+            self.canvas.set_blend_mode(BlendMode::Blend);
+            self.canvas.set_draw_color(theme.paused_tint);
+            self.canvas.fill_rect(None).map_err(Error::Sdl)?;
+            self.canvas.set_blend_mode(BlendMode::None);
+        }
+
+        Ok(())
+    }
+
+    /// Draws the recent generations of a 2D automaton as layers of little
+    /// blocks, the newest on top and the oldest at the bottom, faded the
+    /// most.
+    fn draw_stacked(&mut self, context: &SimContext) -> Result<(), Error> {
+        self.draw_paused_tint(context)?;
+        let theme = self.theme().clone();
+        let layers: Vec<&Cells> = std::iter::once(&context.points)
+            .chain(
+                context
+                    .history
+                    .iter()
+                    .rev()
+                    .filter_map(|entry| match entry {
+                        HistoryEntry::Grid(cells) => Some(cells.as_ref()),
+                        HistoryEntry::Cell { .. } | HistoryEntry::Scroll { .. } => None,
+                    }),
+            )
+            .take(STACK_DEPTH)
+            .collect();
+
+        // Moving one cell along x goes right and down, along y left and
+        // down, and one generation back goes down a layer.
+        let size = (self.camera.cell_size.round() as i32).max(1);
+        let (half_width, half_height) = ((size / 2).max(1), (size / 4).max(1));
+        let layer_height = size;
+        let (offset_x, offset_y) = self.camera.offset;
+        let origin = (
+            offset_x + GRID_Y_SIZE as i32 * half_width,
+            offset_y + half_height,
+        );
+
+        for (depth, cells) in layers.iter().enumerate().rev() {
+            let brightness = 1.0 - (1.0 - STACK_MIN_BRIGHTNESS) * depth as f32 / STACK_DEPTH as f32;
+            let top = coloring::lerp(theme.dead_cell, theme.live_cell, brightness);
+            let side = coloring::lerp(top, theme.background, 0.4);
+            let base_y = origin.1 + depth as i32 * layer_height;
+
+            let mut tops = Vec::new();
+            let mut sides = Vec::new();
+            for (x, column) in cells.iter().enumerate() {
+                for (y, &alive) in column.iter().enumerate() {
+                    if !alive {
+                        continue;
+                    }
+                    let (x, y) = (x as i32, y as i32);
+                    let screen_x = origin.0 + (x - y) * half_width;
+                    let screen_y = base_y + (x + y) * half_height;
+                    let width = 2 * half_width as u32;
+                    tops.push(Rect::new(
+                        screen_x - half_width,
+                        screen_y - half_height,
+                        width,
+                        2 * half_height as u32,
+                    ));
+                    sides.push(Rect::new(
+                        screen_x - half_width,
+                        screen_y + half_height,
+                        width,
+                        layer_height as u32,
+                    ));
+                }
+            }
+            // Blocks further back never cover the tops of blocks in front
+            // of them, so all sides can go first.
+            self.canvas.set_draw_color(side);
+            self.canvas.fill_rects(&sides).map_err(Error::Sdl)?;
+            self.canvas.set_draw_color(top);
+            self.canvas.fill_rects(&tops).map_err(Error::Sdl)?;
+        }
+        Ok(())
+    }
+
+    /// Draws the rows of an elementary automaton winding outwards from the
+    /// middle of the pane, by looking up the cell behind every pixel.
+    fn draw_spiral(&mut self, context: &SimContext) -> Result<(), Error> {
+        let theme = self.theme().clone();
+        let (width, height) = PANE_SIZE;
+        // At the default zoom the outermost turn touches the pane's edge.
+        let zoom = self.camera.cell_size / DOT_SIZE_IN_PXS as f32;
+        let ring =
+            zoom * width.min(height) as f32 / 2.0 / (GRID_Y_SIZE as f32 + SPIRAL_INNER_RINGS + 1.0);
+        let inner = SPIRAL_INNER_RINGS * ring;
+        let (offset_x, offset_y) = self.camera.offset;
+        let center = (
+            (width / 2) as f32 + offset_x as f32,
+            (height / 2) as f32 + offset_y as f32,
+        );
+
+        let mut pixels = Vec::with_capacity((width * height * 3) as usize);
+        for py in 0..height {
+            for px in 0..width {
+                let dx = px as f32 + 0.5 - center.0;
+                let dy = py as f32 + 0.5 - center.1;
+                let color = match view::spiral_cell(dx, dy, inner, ring) {
+                    Some((x, y)) if context.points[x][y] => theme.live_cell,
+                    Some(_) => theme.dead_cell,
+                    None => theme.background,
+                };
+                pixels.extend_from_slice(&[color.r, color.g, color.b]);
+            }
+        }
+
+        let texture_creator = self.canvas.texture_creator();
+        let mut texture = texture_creator
+            .create_texture_static(PixelFormatEnum::RGB24, width, height)
+            .map_err(|e| e.to_string())
+            .map_err(Error::Sdl)?;
+        texture
+            .update(None, &pixels, (width * 3) as usize)
+            .map_err(|e| e.to_string())
+            .map_err(Error::Sdl)?;
+        self.canvas.copy(&texture, None, None).map_err(Error::Sdl)?;
+        self.draw_paused_tint(context)
+    }
+
+    /// Post-processes everything drawn so far to look like an old CRT
+    /// monitor, working on window pixels so the scanlines stay one pixel
+    /// apart whatever the zoom.
+    fn draw_crt(&mut self) -> Result<(), Error> {
+        if !self.crt {
+            return Ok(());
+        }
+        self.canvas
+            .set_logical_size(0, 0)
+            .map_err(|e| e.to_string())
+            .map_err(Error::Sdl)?;
+        let (width, height) = self.canvas.output_size().map_err(Error::Sdl)?;
+
+        // The glow is the picture itself, added on top a few times shifted
+        // in every direction.
+        let pixels = self
+            .canvas
+            .read_pixels(None, PixelFormatEnum::RGB24)
+            .map_err(Error::Sdl)?;
+        let texture_creator = self.canvas.texture_creator();
+        let mut picture = texture_creator
+            .create_texture_static(PixelFormatEnum::RGB24, width, height)
+            .map_err(|e| e.to_string())
+            .map_err(Error::Sdl)?;
+        picture
+            .update(None, &pixels, (width * 3) as usize)
+            .map_err(|e| e.to_string())
+            .map_err(Error::Sdl)?;
+        picture.set_blend_mode(BlendMode::Add);
+        picture.set_alpha_mod(CRT_GLOW_STRENGTH);
+        for (dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+            let (dx, dy) = (dx * CRT_GLOW_RADIUS, dy * CRT_GLOW_RADIUS);
+            self.canvas
+                .copy(&picture, None, Rect::new(dx, dy, width, height))
+                .map_err(Error::Sdl)?;
+        }
+
+        self.canvas.set_blend_mode(BlendMode::Blend);
+        self.canvas
+            .set_draw_color(Color::RGBA(0, 0, 0, CRT_SCANLINE_DARKNESS));
+        let scanlines: Vec<Rect> = (0..height as i32)
+            .step_by(2)
+            .map(|y| Rect::new(0, y, width, 1))
+            .collect();
+        self.canvas.fill_rects(&scanlines).map_err(Error::Sdl)?;
+
+        // Nested outlines, each a little darker than the one inside it.
+        let depth = (width.min(height) as f32 * CRT_VIGNETTE_SIZE) as u32;
+        for inset in 0..depth {
+            let alpha = 1.0 - inset as f32 / depth as f32;
+            self.canvas
+                .set_draw_color(Color::RGBA(0, 0, 0, (alpha * alpha * 12.0) as u8));
+            self.canvas
+                .draw_rect(Rect::new(
+                    inset as i32,
+                    inset as i32,
+                    width.saturating_sub(2 * inset).max(1),
+                    height.saturating_sub(2 * inset).max(1),
+                ))
+                .map_err(Error::Sdl)?;
+        }
+        self.canvas.set_blend_mode(BlendMode::None);
+
+        self.scale_to_window()
+    }
+
+    /// Saves every row of `context` as a picture in `format`, independent
+    /// of the window and the zoom, `scale` pixels to a cell.
+    pub fn export_diagram(&self, context: &SimContext, scale: u32, format: DiagramFormat) {
+        let theme = self.theme();
+        let colors = (theme.live_cell, theme.dead_cell);
+        let path = self
+            .output
+            .path("diagram", context, format.extension(), SystemTime::now());
+        match screenshot::save_diagram(&path, format, context.diagram(), context, scale, colors) {
+            Ok(()) => eprintln!("Saved the diagram to {}", path.display()),
+            Err(e) => error!("{}", e),
+        }
+    }
+
+    /// Saves the center column of the elementary rule `context` runs, down
+    /// to the generation it has reached, as a text file in `format`.
+    pub fn save_center_column(&self, context: &SimContext, format: ColumnFormat) {
+        if context.automaton != Automaton::Elementary {
+            warn!("only elementary automata have a center column");
+            return;
+        }
+        let bits = center_column::center_column(context.rule, context.generation() as usize);
+        let path = self
+            .output
+            .path("center-column", context, "txt", SystemTime::now());
+        match fs::write(&path, center_column::format_column(&bits, format)) {
+            Ok(()) => eprintln!("Saved the center column to {}", path.display()),
+            Err(e) => error!("could not write '{}': {}", path.display(), e),
+        }
+    }
+
+    /// Copies every row of `contexts` to the clipboard as text art, one
+    /// automaton after the other.
+    pub fn copy_diagram(&self, contexts: &[SimContext]) {
+        let text = contexts
+            .iter()
+            .map(|context| screenshot::diagram_text(&context.diagram()))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let clipboard = self.canvas.window().subsystem().clipboard();
+        match clipboard.set_clipboard_text(&text) {
+            Ok(()) => eprintln!("Copied the diagram to the clipboard"),
+            Err(e) => error!("could not copy the diagram: {}", e),
+        }
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.video.is_some()
+    }
+
+    /// Starts recording every frame of `context` into a video in the
+    /// output directory, or finishes the video being recorded. Problems
+    /// with ffmpeg are reported without stopping anything else.
+    pub fn toggle_recording_video(&mut self, context: &SimContext) {
+        let result = match self.video.take() {
+            Some(video) => video.finish(),
+            None => self
+                .canvas
+                .output_size()
+                .map_err(Error::Sdl)
+                .and_then(|size| {
+                    let path = self.output.path(
+                        "recording",
+                        context,
+                        &self.video_format,
+                        SystemTime::now(),
+                    );
+                    eprintln!("Recording to {}", path.display());
+                    self.video = Some(VideoRecorder::start(&path, size, FRAMES_PER_SECOND)?);
+                    Ok(())
+                }),
+        };
+        if let Err(e) = result {
+            error!("{}", e);
+        }
+    }
+
+    /// Adds what has been drawn so far to the video being recorded. A
+    /// video keeps the size it started with, so resizing the window ends
+    /// it.
+    fn record_frame(&mut self) -> Result<(), Error> {
+        let Some(size) = self.video.as_ref().map(|video| video.size) else {
+            return Ok(());
+        };
+        self.canvas
+            .set_logical_size(0, 0)
+            .map_err(|e| e.to_string())
+            .map_err(Error::Sdl)?;
+        let result = match self.canvas.output_size().map_err(Error::Sdl)? {
+            output if output == size => self
+                .canvas
+                .read_pixels(None, PixelFormatEnum::RGB24)
+                .map_err(Error::Sdl)
+                .and_then(|pixels| {
+                    self.video
+                        .as_mut()
+                        .map_or(Ok(()), |v| v.write_frame(&pixels))
+                }),
+            _ => Err(Error::OutOfBounds(
+                "the window changed size, stopped recording".to_string(),
+            )),
+        };
+        self.scale_to_window()?;
+        if let Err(e) = result {
+            error!("{}", e);
+            if let Err(e) = self.video.take().map_or(Ok(()), VideoRecorder::finish) {
+                error!("{}", e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Marks the window while it is being recorded, after the frame has
+    /// gone into the video so the mark does not end up in it.
+    fn draw_recording_indicator(&mut self) -> Result<(), Error> {
+        if self.video.is_none() {
+            return Ok(());
+        }
+        let (width, _) = self.viewport();
+        let text_width = font::text_width("REC", HUD_TEXT_SCALE);
+        let dot = font::text_height(HUD_TEXT_SCALE);
+        let x = width as i32 - (text_width + dot + 3 * HUD_PADDING) as i32;
+        let y = HUD_PADDING as i32;
+        self.canvas.set_draw_color(RECORDING_COLOR);
+        self.canvas
+            .fill_rect(Rect::new(x, y, dot, dot))
+            .map_err(Error::Sdl)?;
+        font::draw_text(
+            &mut self.canvas,
+            "REC",
+            (x + (dot + HUD_PADDING) as i32, y),
+            HUD_TEXT_SCALE,
+            RECORDING_COLOR,
+        )
+    }
+
+    /// What has been drawn so far, at the window's full resolution.
+    fn read_frame(&mut self) -> Result<RgbaImage, Error> {
+        self.canvas
+            .set_logical_size(0, 0)
+            .map_err(|e| e.to_string())
+            .map_err(Error::Sdl)?;
+        let (width, height) = self.canvas.output_size().map_err(Error::Sdl)?;
+        let pixels = self
+            .canvas
+            .read_pixels(None, PixelFormatEnum::RGBA32)
+            .map_err(Error::Sdl)?;
+        self.scale_to_window()?;
+        Ok(RgbaImage {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    /// Hands what has been drawn so far to the frame capture whenever it is
+    /// due for the generation `context` is at, named after it so the
+    /// frames sort in order.
+    fn capture_frame(&mut self, context: &SimContext) -> Result<(), Error> {
+        let generation = context.generation();
+        if !self
+            .frame_capture
+            .as_mut()
+            .is_some_and(|capture| capture.is_due(generation))
+        {
+            return Ok(());
+        }
+        let image = self.read_frame()?;
+        let kind = format!("frame-{:08}", generation);
+        let path = self.output.path(&kind, context, "png", SystemTime::now());
+        if let Some(capture) = &self.frame_capture {
+            capture.save(path, image);
+        }
+        Ok(())
+    }
+
+    /// Saves what has been drawn so far, at the window's full resolution.
+    /// Failing to save is reported without stopping anything else.
+    fn save_screenshot(&mut self, context: &SimContext) -> Result<(), Error> {
+        let image = self.read_frame()?;
+        let path = self
+            .output
+            .path("screenshot", context, "png", SystemTime::now());
+        match image.save_png(&path) {
+            Ok(()) => eprintln!("Saved a screenshot to {}", path.display()),
+            Err(e) => error!("{}", e),
+        }
+        Ok(())
+    }
+
+    /// Separates the panes, which would otherwise run into each other
+    /// whenever the dead cells match the background.
+    fn draw_pane_borders(&mut self) -> Result<(), Error> {
+        let theme = self.theme();
+        let color = coloring::lerp(theme.background, theme.live_cell, 0.5);
+        self.canvas.set_draw_color(color);
+        let (columns, rows) = pane_layout(self.pane_count);
+        let (width, height) = (columns * PANE_SIZE.0, rows * PANE_SIZE.1);
+        for column in 1..columns {
+            let x = (column * PANE_SIZE.0) as i32;
+            self.canvas
+                .draw_line((x, 0), (x, height as i32 - 1))
+                .map_err(Error::Sdl)?;
+        }
+        for row in 1..rows {
+            let y = (row * PANE_SIZE.1) as i32;
+            self.canvas
+                .draw_line((0, y), (width as i32 - 1, y))
+                .map_err(Error::Sdl)?;
+        }
+        Ok(())
+    }
+
+    fn draw_fade(&mut self) -> Result<(), Error> {
+        if self.fade <= 0.0 {
+            return Ok(());
+        }
+        let Color { r, g, b, .. } = self.theme().background;
+        let alpha = (self.fade.min(1.0) * 255.0) as u8;
+
+        self.canvas.set_blend_mode(BlendMode::Blend);
+        self.canvas.set_draw_color(Color::RGBA(r, g, b, alpha));
+        self.canvas.fill_rect(None).map_err(Error::Sdl)?;
+        self.canvas.set_blend_mode(BlendMode::None);
+
+        Ok(())
+    }
+
+    fn draw_sim(&mut self, context: &SimContext) -> Result<(), Error> {
+        if self.coloring == CellColoring::Heatmap && context.automaton == Automaton::Life {
+            return self.draw_heatmap(context);
+        }
+        let theme = self.theme().clone();
+        let generation = context.generation();
+        for (x, col) in context.points.iter().enumerate() {
+            for (y, _row) in col.iter().enumerate() {
+                let current_point = context.points[x][y];
+                if self.coloring == CellColoring::Neighborhood {
+                    if let Some(case) = context.cases[x][y] {
+                        let color = coloring::case_color(case);
+                        self.canvas.set_draw_color(match current_point {
+                            true => color,
+                            false => coloring::lerp(theme.dead_cell, color, 0.25),
+                        });
+                        self.draw_dot(&Point(x as i32, y as i32))?;
+                        continue;
+                    }
+                }
+                match current_point {
+                    false => {}
+                    true => {
+                        let color = match self.coloring {
+                            CellColoring::Flat
+                            | CellColoring::Neighborhood
+                            | CellColoring::Heatmap => theme.live_cell,
+                            CellColoring::Age => {
+                                let age = generation.saturating_sub(context.row_generation(y));
+                                coloring::age_color(theme.live_cell, theme.dead_cell, age)
+                            }
+                            CellColoring::Gradient => {
+                                self.palette.sample(y as f32 / (GRID_Y_SIZE - 1) as f32)
+                            }
+                        };
+                        self.canvas.set_draw_color(color);
+                        self.draw_dot(&Point(x as i32, y as i32))?;
+                    }
+                };
+            }
+        }
+        Ok(())
+    }
+
+    /// Colors every cell by the share of generations it has been alive for,
+    /// relative to the busiest cell.
+    fn draw_heatmap(&mut self, context: &SimContext) -> Result<(), Error> {
+        let hottest = context
+            .heat
+            .iter()
+            .flatten()
+            .copied()
+            .max()
+            .unwrap_or(0)
+            .max(1);
+        for (x, col) in context.heat.iter().enumerate() {
+            for (y, &heat) in col.iter().enumerate() {
+                if heat == 0 {
+                    continue;
+                }
+                let color = self.palette.sample(heat as f32 / hottest as f32);
+                self.canvas.set_draw_color(color);
+                self.draw_dot(&Point(x as i32, y as i32))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Draws `lines` of text on a translucent panel with its top left
+    /// corner at `(x, y)`.
+    fn draw_panel(
+        &mut self,
+        lines: &[String],
+        (x, y): (i32, i32),
+        scale: u32,
+    ) -> Result<(), Error> {
+        let theme = self.theme().clone();
+        let line_height = font::text_height(scale) + HUD_PADDING;
+        let panel = Rect::new(
+            x,
+            y,
+            panel_width(lines, scale),
+            panel_height(lines.len(), scale),
+        );
+
+        let Color { r, g, b, .. } = theme.background;
+        self.canvas.set_blend_mode(BlendMode::Blend);
+        self.canvas.set_draw_color(Color::RGBA(r, g, b, 190));
+        self.canvas.fill_rect(panel).map_err(Error::Sdl)?;
+        self.canvas.set_blend_mode(BlendMode::None);
+
+        for (index, line) in lines.iter().enumerate() {
+            let position = (
+                x + HUD_PADDING as i32,
+                y + (HUD_PADDING + index as u32 * line_height) as i32,
+            );
+            font::draw_text(&mut self.canvas, line, position, scale, theme.live_cell)?;
+        }
+        Ok(())
+    }
+
+    fn draw_hud(&mut self, context: &SimContext) -> Result<(), Error> {
+        if !self.show_hud {
+            return Ok(());
+        }
+        let automaton = match context.automaton {
+            Automaton::Elementary => format!("RULE {}", context.rule),
+            Automaton::Life => "LIFE".to_string(),
+        };
+        let state = match context.state {
+            SimulationState::Playing => "PLAYING",
+            SimulationState::Paused => "PAUSED",
+        };
+        let line = format!(
+            "{}  GEN {}  POP {}  {}",
+            automaton,
+            context.generation(),
+            context.population(),
+            state
+        );
+        self.draw_panel(&[line], (0, 0), HUD_TEXT_SCALE)
+    }
+
+    /// Draws the measured frame and tick rates in the top left corner,
+    /// below the overlay if that is shown.
+    fn draw_rates(&mut self) -> Result<(), Error> {
+        if !self.show_rates {
+            return Ok(());
+        }
+        let now = Instant::now();
+        let line = format!(
+            "{:.0} FPS  {:.0} TPS",
+            self.measured_fps.rate(now),
+            self.measured_tps.rate(now)
+        );
+        let y = if self.show_hud {
+            panel_height(1, HUD_TEXT_SCALE)
+        } else {
+            0
+        };
+        self.draw_panel(&[line], (0, y as i32), HUD_TEXT_SCALE)
+    }
+
+    /// Draws the whole grid one pixel per cell, with the part that is
+    /// currently visible outlined.
+    fn draw_minimap(&mut self, context: &SimContext) -> Result<(), Error> {
+        let Some(minimap) = self.minimap_rect() else {
+            return Ok(());
+        };
+        let theme = self.theme().clone();
+        self.minimap
+            .update(&context.points, theme.live_cell, theme.dead_cell);
+
+        let texture_creator = self.canvas.texture_creator();
+        let mut texture = texture_creator
+            .create_texture_static(PixelFormatEnum::RGB24, GRID_X_SIZE, GRID_Y_SIZE)
+            .map_err(|e| e.to_string())
+            .map_err(Error::Sdl)?;
+        texture
+            .update(None, self.minimap.pixels(), self.minimap.pitch())
+            .map_err(|e| e.to_string())
+            .map_err(Error::Sdl)?;
+        self.canvas
+            .copy(&texture, None, minimap)
+            .map_err(Error::Sdl)?;
+
+        // The visible area in cells, drawn at one pixel per cell.
+        let (width, height) = PANE_SIZE;
+        let size = self.camera.cell_size;
+        let Point(left, top) = self.camera.cell_at((0, 0));
+        let visible = Rect::new(
+            minimap.x() + left,
+            minimap.y() + top,
+            ((width as f32 / size) as u32).max(1),
+            ((height as f32 / size) as u32).max(1),
+        );
+        let color = self.scanner_color.unwrap_or(theme.scanner);
+        self.canvas.set_draw_color(color);
+        if let Some(visible) = visible.intersection(minimap) {
+            self.canvas.draw_rect(visible).map_err(Error::Sdl)?;
+        }
+        Ok(())
+    }
+
+    /// Describes the cell under the mouse pointer next to it.
+    fn draw_inspector(&mut self, contexts: &[SimContext]) -> Result<(), Error> {
+        let Some(hover) = self.hover.filter(|_| self.show_inspector) else {
+            return Ok(());
+        };
+        let Some((index, local)) = self.pane_at(hover) else {
+            return Ok(());
+        };
+        let (Some(context), Point(x, y)) = (contexts.get(index), self.camera.cell_at(local)) else {
+            return Ok(());
+        };
+        if !(0..GRID_X_SIZE as i32).contains(&x) || !(0..GRID_Y_SIZE as i32).contains(&y) {
+            return Ok(());
+        }
+        let (x, y) = (x as usize, y as usize);
+
+        let alive = context.points[x][y];
+        let mut lines = vec![
+            format!("CELL {}, {}", x, y),
+            if alive { "ALIVE" } else { "DEAD" }.to_string(),
+        ];
+        match context.automaton {
+            Automaton::Elementary => {
+                if let Some(case) = context.neighborhood_at(x, y) {
+                    lines.push(format!("{:03b} -> {}", case, context.rule >> case & 1));
+                }
+            }
+            Automaton::Life => lines.push(format!(
+                "NEIGHBORS {}",
+                life::neighbors(&context.points, x, y)
+            )),
+        }
+
+        // Keep the whole panel inside the window, flipping it to the other
+        // side of the pointer near the edges.
+        let (width, height) = self.viewport();
+        let panel = (
+            panel_width(&lines, INSPECTOR_TEXT_SCALE) as i32,
+            panel_height(lines.len(), INSPECTOR_TEXT_SCALE) as i32,
+        );
+        let place = |pointer: i32, size: i32, limit: u32| {
+            if pointer + INSPECTOR_OFFSET + size <= limit as i32 {
+                pointer + INSPECTOR_OFFSET
+            } else {
+                (pointer - INSPECTOR_OFFSET - size).max(0)
+            }
+        };
+        let position = (
+            place(hover.0, panel.0, width),
+            place(hover.1, panel.1, height),
+        );
+        self.draw_panel(&lines, position, INSPECTOR_TEXT_SCALE)
+    }
+
+    /// Shows the help, or its next page when it does not fit into the
+    /// window at once, or hides it again after the last page.
+    pub fn next_help_page(&mut self) {
+        let pages = self.help.len().div_ceil(self.help_lines_per_page());
+        self.help_page = match self.help_page {
+            None => Some(0),
+            Some(page) if page + 1 < pages => Some(page + 1),
+            Some(_) => None,
+        };
+    }
+
+    /// How many key bindings fit on a page of the help, leaving a line to
+    /// say which page it is.
+    fn help_lines_per_page(&self) -> usize {
+        let (_, height) = self.viewport();
+        let lines =
+            height.saturating_sub(HUD_PADDING) / (font::text_height(HELP_TEXT_SCALE) + HUD_PADDING);
+        (lines as usize).saturating_sub(1).max(1)
+    }
+
+    /// Dims everything and lists the key bindings in the middle of it.
+    fn draw_help(&mut self) -> Result<(), Error> {
+        let Some(page) = self.help_page else {
+            return Ok(());
+        };
+        let (width, height) = self.viewport();
+        self.canvas.set_blend_mode(BlendMode::Blend);
+        self.canvas.set_draw_color(Color::RGBA(0, 0, 0, 160));
+        self.canvas
+            .fill_rect(Rect::new(0, 0, width, height))
+            .map_err(Error::Sdl)?;
+        self.canvas.set_blend_mode(BlendMode::None);
+
+        let key_width = self
+            .help
+            .iter()
+            .map(|(keys, _)| keys.len())
+            .max()
+            .unwrap_or(0);
+        let lines: Vec<String> = self
+            .help
+            .iter()
+            .map(|(keys, action)| format!("{:width$}  {}", keys, action, width = key_width))
+            .collect();
+        let per_page = self.help_lines_per_page();
+        let pages = lines.len().div_ceil(per_page);
+        let page = page.min(pages - 1);
+        let mut lines = lines[page * per_page..].to_vec();
+        lines.truncate(per_page);
+        if pages > 1 {
+            let next = if page + 1 < pages {
+                "next page"
+            } else {
+                "close"
+            };
+            let help = Command::ToggleHelp.description();
+            let key = self.help.iter().find(|(_, action)| *action == help);
+            let key = key.map_or("F1", |(key, _)| key.as_str());
+            lines.push(format!(
+                "Page {} of {}, {} to {}",
+                page + 1,
+                pages,
+                key,
+                next
+            ));
+        }
+        let x = (width as i32 - panel_width(&lines, HELP_TEXT_SCALE) as i32) / 2;
+        let y = (height as i32 - panel_height(lines.len(), HELP_TEXT_SCALE) as i32) / 2;
+        self.draw_panel(&lines, (x.max(0), y.max(0)), HELP_TEXT_SCALE)
+    }
+
+    /// Lists the patterns in the library with the picked one marked.
+    fn draw_library(&mut self) -> Result<(), Error> {
+        let Some(picked) = self.library else {
+            return Ok(());
+        };
+        let (width, height) = self.viewport();
+        self.canvas.set_blend_mode(BlendMode::Blend);
+        self.canvas.set_draw_color(Color::RGBA(0, 0, 0, 160));
+        self.canvas
+            .fill_rect(Rect::new(0, 0, width, height))
+            .map_err(Error::Sdl)?;
+        self.canvas.set_blend_mode(BlendMode::None);
+
+        let mut lines = vec![
+            "Up / Down to pick, Enter to insert".to_string(),
+            String::new(),
+        ];
+        lines.extend(LIBRARY.iter().enumerate().map(|(index, entry)| {
+            let marker = if index == picked { ">" } else { " " };
+            let automaton = match entry.automaton {
+                Automaton::Elementary => "elementary",
+                Automaton::Life => "life",
+            };
+            format!("{} {:30} {}", marker, entry.name, automaton)
+        }));
+        let x = (width as i32 - panel_width(&lines, HELP_TEXT_SCALE) as i32) / 2;
+        let y = (height as i32 - panel_height(lines.len(), HELP_TEXT_SCALE) as i32) / 2;
+        self.draw_panel(&lines, (x.max(0), y.max(0)), HELP_TEXT_SCALE)
+    }
+
+    /// Draws the strip under the grid with the settings that are not
+    /// visible from the cells themselves.
+    fn draw_status_bar(&mut self, context: &SimContext) -> Result<(), Error> {
+        let theme = self.theme().clone();
+        let (width, height) = self.viewport();
+        let bar = Rect::new(
+            0,
+            height as i32 - STATUS_BAR_HEIGHT_IN_PXS as i32,
+            width,
+            STATUS_BAR_HEIGHT_IN_PXS,
+        );
+        self.canvas.set_draw_color(theme.background);
+        self.canvas.fill_rect(bar).map_err(Error::Sdl)?;
+
+        let mode = match (context.automaton, context.step_mode) {
+            (Automaton::Life, _) => "LIFE".to_string(),
+            (Automaton::Elementary, StepMode::Cell) => {
+                format!("ELEMENTARY  {} CELLS/TICK", context.cells_per_tick)
+            }
+            (Automaton::Elementary, StepMode::Row) => "ELEMENTARY  ROWS".to_string(),
+        };
+        let status = format!(
+            "{}  SPEED {}  SEED {}",
+            mode, context.tick_rate, context.seed
+        );
+        self.notice = self
+            .notice
+            .take()
+            .filter(|(_, at)| at.elapsed() < NOTICE_DURATION);
+        let hint = match (&self.notice, &context.state) {
+            (Some((notice, _)), _) => notice.as_str(),
+            (None, SimulationState::Playing) => "F1: HELP  SPACE: PAUSE",
+            (None, SimulationState::Paused) => "F1: HELP  SPACE: PLAY",
+        };
+
+        let text_y = bar.y()
+            + (STATUS_BAR_HEIGHT_IN_PXS - font::text_height(STATUS_BAR_TEXT_SCALE)) as i32 / 2;
+        let color = theme.live_cell;
+        font::draw_text(
+            &mut self.canvas,
+            &status,
+            (HUD_PADDING as i32, text_y),
+            STATUS_BAR_TEXT_SCALE,
+            color,
+        )?;
+        let hint_x =
+            width as i32 - (font::text_width(hint, STATUS_BAR_TEXT_SCALE) + HUD_PADDING) as i32;
+        font::draw_text(
+            &mut self.canvas,
+            hint,
+            (hint_x, text_y),
+            STATUS_BAR_TEXT_SCALE,
+            color,
+        )
+    }
+
+    /// Draws faint lines between the cells, as long as the cells are large
+    /// enough on screen for the lines not to drown them.
+    fn draw_grid_lines(&mut self) -> Result<(), Error> {
+        let (scale_x, _) = self.canvas.scale();
+        let on_screen_cell_size = self.camera.cell_size * scale_x;
+        if !self.show_grid_lines || on_screen_cell_size < MIN_GRID_LINE_CELL_SIZE_IN_PXS {
+            return Ok(());
+        }
+
+        let theme = self.theme();
+        let color = coloring::lerp(theme.dead_cell, theme.live_cell, 0.15);
+        self.canvas.set_draw_color(color);
+
+        let grid = self.grid_rect();
+        for x in 0..=GRID_X_SIZE as i32 {
+            let line_x = grid.x() + self.camera.edge(x);
+            self.canvas
+                .draw_line((line_x, grid.top()), (line_x, grid.bottom()))
+                .map_err(Error::Sdl)?;
+        }
+        for y in 0..=GRID_Y_SIZE as i32 {
+            let line_y = grid.y() + self.camera.edge(y);
+            self.canvas
+                .draw_line((grid.left(), line_y), (grid.right(), line_y))
+                .map_err(Error::Sdl)?;
+        }
+
+        Ok(())
+    }
+
+    /// Tints the triangles of dead cells an elementary automaton leaves,
+    /// from the palette's start for the smallest to its end for the
+    /// largest.
+    fn draw_triangles(&mut self, context: &SimContext) -> Result<(), Error> {
+        if !self.show_triangles || context.automaton != Automaton::Elementary {
+            return Ok(());
+        }
+        let rows = context.computed_rows() as usize;
+        let triangles = triangles::find(&context.points, rows, MIN_TRIANGLE_WIDTH);
+        let widest = triangles.iter().map(|t| t.width).max().unwrap_or(0);
+
+        self.canvas.set_blend_mode(BlendMode::Blend);
+        for triangle in &triangles {
+            let size = match widest > MIN_TRIANGLE_WIDTH {
+                true => {
+                    (triangle.width - MIN_TRIANGLE_WIDTH) as f32
+                        / (widest - MIN_TRIANGLE_WIDTH) as f32
+                }
+                false => 1.0,
+            };
+            let color = self.palette.sample(size);
+            self.canvas
+                .set_draw_color(Color::RGBA(color.r, color.g, color.b, TRIANGLE_TINT_ALPHA));
+            for k in 0..triangle.height {
+                let y = (triangle.y + k) as i32;
+                let left = self.camera.cell_rect(&Point((triangle.x + k) as i32, y));
+                let right = self
+                    .camera
+                    .cell_rect(&Point((triangle.x + triangle.width - 1 - k) as i32, y));
+                self.canvas
+                    .fill_rect(left.union(right))
+                    .map_err(Error::Sdl)?;
+            }
+        }
+        self.canvas.set_blend_mode(BlendMode::None);
+        Ok(())
+    }
+
+    /// Shades the cells the chosen cell can influence in the rows below it
+    /// and the cells that influenced it in the rows above. Either spreads
+    /// by one cell to each side per row, as far as an elementary
+    /// automaton's neighborhood reaches.
+    fn draw_light_cone(&mut self, context: &SimContext) -> Result<(), Error> {
+        let Some(Point(x, y)) = self.light_cone else {
+            return Ok(());
+        };
+        if context.automaton != Automaton::Elementary {
+            return Ok(());
+        }
+        self.canvas.set_blend_mode(BlendMode::Blend);
+        for row in SEED_ROW as i32..GRID_Y_SIZE as i32 {
+            let reach = (row - y).abs();
+            self.canvas.set_draw_color(match row < y {
+                true => BACKWARD_CONE_COLOR,
+                false => FORWARD_CONE_COLOR,
+            });
+            let left = self.camera.cell_rect(&Point((x - reach).max(0), row));
+            let right = self
+                .camera
+                .cell_rect(&Point((x + reach).min(GRID_X_SIZE as i32 - 1), row));
+            self.canvas
+                .fill_rect(left.union(right))
+                .map_err(Error::Sdl)?;
+        }
+        self.canvas.set_blend_mode(BlendMode::None);
+        Ok(())
+    }
+
+    /// Outlines the bookmarked rows of an elementary automaton that are on
+    /// the grid and labels them at their right end, away from the overlay.
+    fn draw_bookmarks(&mut self, context: &SimContext) -> Result<(), Error> {
+        if context.automaton != Automaton::Elementary {
+            return Ok(());
+        }
+        let computed = context.computed_rows() as usize;
+        let rows: Vec<(usize, String)> = self
+            .bookmarks
+            .iter()
+            .filter_map(|bookmark| {
+                let y =
+                    (bookmark.generation + SEED_ROW as u64).checked_sub(context.scrolled_rows)?;
+                (y < computed as u64).then(|| (y as usize, bookmark.label.clone()))
+            })
+            .collect();
+        for (y, label) in rows {
+            let left = self.camera.cell_rect(&Point(0, y as i32));
+            let right = self
+                .camera
+                .cell_rect(&Point(GRID_X_SIZE as i32 - 1, y as i32));
+            self.canvas.set_draw_color(self.theme().scanner);
+            self.canvas
+                .draw_rect(left.union(right))
+                .map_err(Error::Sdl)?;
+            let lines = [label];
+            let x = right.right().min(self.canvas.viewport().width() as i32)
+                - panel_width(&lines, HUD_TEXT_SCALE) as i32;
+            self.draw_panel(&lines, (x, left.bottom()), HUD_TEXT_SCALE)?;
+        }
+        Ok(())
+    }
+
+    fn draw_scanner(&mut self, context: &SimContext) -> Result<(), Error> {
+        if !self.show_scanner
+            || context.step_mode == StepMode::Row
+            || context.automaton == Automaton::Life
+        {
+            return Ok(());
+        }
+        let color = self.scanner_color.unwrap_or(self.theme().scanner);
+        self.draw_scanner_trail(context, color)?;
+        self.canvas.set_draw_color(color);
+        for point in &context.scanner {
+            self.draw_dot(point)?;
+        }
+
+        Ok(())
+    }
+
+    /// Draws the cells the scanner just left behind, fading out with their
+    /// distance, so the direction it sweeps in stays visible.
+    fn draw_scanner_trail(&mut self, context: &SimContext, color: Color) -> Result<(), Error> {
+        let Some(tail) = context.scanner.last() else {
+            return Ok(());
+        };
+        let tail_index = tail.1 * GRID_X_SIZE as i32 + tail.0;
+
+        self.canvas.set_blend_mode(BlendMode::Blend);
+        for distance in 1..=self.scanner_trail as i32 {
+            let index = tail_index - distance;
+            if index < 0 {
+                break;
+            }
+            let fade = 1.0 - distance as f32 / (self.scanner_trail + 1) as f32;
+            let alpha = (color.a as f32 * fade * SCANNER_TRAIL_OPACITY) as u8;
+            self.canvas
+                .set_draw_color(Color::RGBA(color.r, color.g, color.b, alpha));
+            let point = Point(index % GRID_X_SIZE as i32, index / GRID_X_SIZE as i32);
+            self.draw_dot(&point)?;
+        }
+        self.canvas.set_blend_mode(BlendMode::None);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,105 @@
+use crate::options::Options;
+use crate::screenshot::{self, DiagramFormat};
+use crate::Error;
+use crate::{state_file, Automaton, SimContext, StepMode, GRID_X_SIZE, GRID_Y_SIZE};
+use serde::Serialize;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+const REPORT_VERSION: u32 = 1;
+
+/// How the run came about, as written to `run.toml`.
+#[derive(Serialize)]
+struct Run<'a> {
+    version: u32,
+    automaton: &'a str,
+    rule: u8,
+    seed: String,
+    generations: u64,
+    export_scale: u32,
+    /// The command line the run was started with, minus the program.
+    arguments: &'a [String],
+}
+
+/// Runs `context` for `--generations` and saves everything needed to
+/// reproduce and cite the run to `dir`: the diagram, how the run was
+/// started, the automaton before the first generation, the pattern it
+/// started from and the population of every generation.
+pub fn save(dir: &Path, mut context: SimContext, options: &Options) -> Result<(), Error> {
+    fs::create_dir_all(dir).map_err(|e| Error::io("create", dir, e))?;
+    let write = |name: &str, contents: &[u8]| {
+        let path = dir.join(name);
+        fs::write(&path, contents).map_err(|e| Error::io("write", path, e))
+    };
+
+    context.step_mode = StepMode::Row;
+    state_file::save(&dir.join("start.toml"), std::slice::from_ref(&context))?;
+    if let Some(pattern) = &options.pattern {
+        let rle = pattern.to_rle(&screenshot::golly_rule(&context));
+        write("pattern.rle", rle.as_bytes())?;
+    }
+
+    let mut stats = String::from("generation,population,density\n");
+    // The rows of the diagram are kept as they finish, since the grid's
+    // history only reaches so far back.
+    let mut rows = Vec::new();
+    let mut record = |context: &SimContext| {
+        let (population, cells) = match context.automaton {
+            Automaton::Elementary => {
+                let row = context.finished_row();
+                let population = row.iter().filter(|&&alive| alive).count();
+                rows.push(row);
+                (population, GRID_X_SIZE)
+            }
+            Automaton::Life => (context.population(), GRID_X_SIZE * GRID_Y_SIZE),
+        };
+        let density = population as f64 / cells as f64;
+        let _ = writeln!(
+            stats,
+            "{},{},{:.4}",
+            context.generation(),
+            population,
+            density
+        );
+    };
+    record(&context);
+    for _ in 0..options.generations {
+        context.endless |= context.is_finished();
+        context.advance();
+        record(&context);
+    }
+    write("stats.csv", stats.as_bytes())?;
+
+    if context.automaton == Automaton::Life {
+        rows = context.diagram();
+    }
+    let colors = (options.theme.live_cell, options.theme.dead_cell);
+    screenshot::save_diagram(
+        &dir.join("diagram.png"),
+        DiagramFormat::Png,
+        rows,
+        &context,
+        options.export_scale,
+        colors,
+    )?;
+
+    let run = Run {
+        version: REPORT_VERSION,
+        automaton: match context.automaton {
+            Automaton::Elementary => "elementary",
+            Automaton::Life => "life",
+        },
+        rule: context.rule,
+        seed: context.seed.to_string(),
+        generations: options.generations,
+        export_scale: options.export_scale,
+        arguments: &options.arguments,
+    };
+    write(
+        "run.toml",
+        toml::to_string(&run)
+            .map_err(|e| Error::Serialize(e.to_string()))?
+            .as_bytes(),
+    )
+}
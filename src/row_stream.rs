@@ -0,0 +1,102 @@
+use crate::screenshot;
+use crate::Error;
+use crate::{Automaton, SimContext, GRID_Y_SIZE, SEED_ROW};
+use std::io::{self, Write};
+use std::str::FromStr;
+
+/// How `--stream` writes the rows.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StreamFormat {
+    /// A line of `0`s and `1`s for every row.
+    Text,
+    /// The cells of every row packed eight to a byte, with the first cell
+    /// in the highest bit.
+    Bits,
+}
+
+impl FromStr for StreamFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(StreamFormat::Text),
+            "bin" => Ok(StreamFormat::Bits),
+            _ => Err(Error::Parse(format!(
+                "invalid stream format '{}', expected text or bin",
+                s
+            ))),
+        }
+    }
+}
+
+/// Follows the rows of an elementary automaton as they are completed.
+#[derive(Default)]
+pub struct RowCursor {
+    /// The generation of the row to hand out next.
+    next: u64,
+}
+
+impl RowCursor {
+    /// The rows `context` completed since the last call, with their
+    /// generations. Rows stepped back over come again once they are
+    /// computed anew, and starting over starts from the seed row.
+    pub fn completed(&mut self, context: &SimContext) -> Vec<(u64, Vec<bool>)> {
+        let mut rows = Vec::new();
+        if context.automaton != Automaton::Elementary {
+            return rows;
+        }
+        // The row the scanner is at has been completed, it reads from it.
+        let generation = context.generation();
+        self.next = self.next.min(generation + 1);
+        while self.next <= generation {
+            let y = (self.next + SEED_ROW as u64).checked_sub(context.scrolled_rows);
+            self.next += 1;
+            let Some(y) = y.filter(|&y| y < GRID_Y_SIZE as u64) else {
+                // Scrolled out before it could be handed out.
+                continue;
+            };
+            let row = context
+                .points
+                .iter()
+                .map(|column| column[y as usize])
+                .collect();
+            rows.push((self.next - 1, row));
+        }
+        rows
+    }
+}
+
+/// Writes the rows of an elementary automaton out as they are completed,
+/// for other programs to follow the evolution through a pipe.
+pub struct RowStream {
+    format: StreamFormat,
+    rows: RowCursor,
+}
+
+impl RowStream {
+    pub fn new(format: StreamFormat) -> RowStream {
+        RowStream {
+            format,
+            rows: RowCursor::default(),
+        }
+    }
+
+    /// Writes the rows `context` completed since the last call, each one
+    /// flushed on its own.
+    pub fn write(&mut self, context: &SimContext, out: &mut impl Write) -> io::Result<()> {
+        for (_, row) in self.rows.completed(context) {
+            match self.format {
+                StreamFormat::Text => {
+                    let line: String = row
+                        .iter()
+                        .map(|&alive| if alive { '1' } else { '0' })
+                        .collect();
+                    writeln!(out, "{}", line)?;
+                }
+                StreamFormat::Bits => out.write_all(&screenshot::pack_row(&row))?,
+            }
+            out.flush()?;
+        }
+        Ok(())
+    }
+}
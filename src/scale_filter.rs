@@ -0,0 +1,46 @@
+use crate::Error;
+use std::str::FromStr;
+
+/// How textures are sampled when they are drawn larger or smaller than
+/// they are.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ScaleFilter {
+    /// Every pixel becomes a crisp block, which suits the cells.
+    Nearest,
+    /// Pixels blend into their neighbors, which looks smoother on large
+    /// displays.
+    Linear,
+}
+
+impl ScaleFilter {
+    pub fn toggle(self) -> ScaleFilter {
+        match self {
+            ScaleFilter::Nearest => ScaleFilter::Linear,
+            ScaleFilter::Linear => ScaleFilter::Nearest,
+        }
+    }
+
+    /// The value of SDL's render scale quality hint, which textures pick
+    /// up when they are created.
+    pub fn hint(self) -> &'static str {
+        match self {
+            ScaleFilter::Nearest => "nearest",
+            ScaleFilter::Linear => "linear",
+        }
+    }
+}
+
+impl FromStr for ScaleFilter {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "nearest" => Ok(ScaleFilter::Nearest),
+            "linear" => Ok(ScaleFilter::Linear),
+            _ => Err(Error::Parse(format!(
+                "unknown scale filter '{}', expected nearest or linear",
+                s
+            ))),
+        }
+    }
+}
@@ -0,0 +1,501 @@
+use crate::color::Color;
+use crate::image::RgbaImage;
+use crate::pattern::Pattern;
+use crate::seed::Seed;
+use crate::Error;
+use crate::{Automaton, SimContext};
+use serde::Serialize;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The file formats a diagram can be exported in.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DiagramFormat {
+    Png,
+    /// Rectangles for runs of live cells, for print.
+    Svg,
+    /// A pattern for Golly and other Life software.
+    Rle,
+    /// A plaintext pattern, easy to edit by hand.
+    Cells,
+    /// Golly's macrocell format, small for regular patterns.
+    Macrocell,
+    /// A row of comma separated 0s and 1s for every row, for analysis.
+    Csv,
+    /// Eight cells to a byte after a short header, for huge runs.
+    Bits,
+    /// The configuration and the cells, for web pages and other tools.
+    Json,
+    /// A NumPy boolean array.
+    Npy,
+}
+
+impl DiagramFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            DiagramFormat::Png => "png",
+            DiagramFormat::Svg => "svg",
+            DiagramFormat::Rle => "rle",
+            DiagramFormat::Cells => "cells",
+            DiagramFormat::Macrocell => "mc",
+            DiagramFormat::Csv => "csv",
+            DiagramFormat::Bits => "bin",
+            DiagramFormat::Json => "json",
+            DiagramFormat::Npy => "npy",
+        }
+    }
+
+    /// The format the extension of `path` names.
+    pub fn of(path: &Path) -> Result<DiagramFormat, Error> {
+        path.extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or_default()
+            .parse()
+            .map_err(|e| Error::Parse(format!("cannot export to '{}', {}", path.display(), e)))
+    }
+}
+
+impl FromStr for DiagramFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "png" => Ok(DiagramFormat::Png),
+            "svg" => Ok(DiagramFormat::Svg),
+            "rle" => Ok(DiagramFormat::Rle),
+            "cells" => Ok(DiagramFormat::Cells),
+            "mc" => Ok(DiagramFormat::Macrocell),
+            "csv" => Ok(DiagramFormat::Csv),
+            "bin" => Ok(DiagramFormat::Bits),
+            "json" => Ok(DiagramFormat::Json),
+            "npy" => Ok(DiagramFormat::Npy),
+            _ => Err(Error::Parse(format!(
+                "unknown diagram format '{}', expected png, svg, rle, cells, mc, csv, bin, json or npy",
+                s
+            ))),
+        }
+    }
+}
+
+/// Saves `rows` of `context` to `path` in `format`, with `scale` pixels
+/// per cell for the formats that have pixels.
+pub fn save_diagram(
+    path: &Path,
+    format: DiagramFormat,
+    rows: Vec<Vec<bool>>,
+    context: &SimContext,
+    scale: u32,
+    (live, dead): (Color, Color),
+) -> Result<(), Error> {
+    let contents = match format {
+        DiagramFormat::Png => return diagram(&rows, scale, live, dead).save_png(path),
+        DiagramFormat::Svg => diagram_svg(&rows, scale, live, dead).into_bytes(),
+        DiagramFormat::Rle => Pattern { rows }.to_rle(&golly_rule(context)).into_bytes(),
+        DiagramFormat::Cells => Pattern { rows }.to_cells(&context.title()).into_bytes(),
+        DiagramFormat::Macrocell => Pattern { rows }
+            .to_macrocell(&golly_rule(context))
+            .into_bytes(),
+        DiagramFormat::Csv => diagram_csv(&rows).into_bytes(),
+        DiagramFormat::Bits => diagram_bits(&rows, context),
+        DiagramFormat::Json => diagram_json(rows, context)?.into_bytes(),
+        DiagramFormat::Npy => diagram_npy(&rows),
+    };
+    fs::write(path, contents).map_err(|e| Error::io("write", path, e))
+}
+
+/// The rule of `context` the way Golly writes it.
+pub fn golly_rule(context: &SimContext) -> String {
+    match context.automaton {
+        Automaton::Elementary => format!("W{}", context.rule),
+        Automaton::Life => "B3/S23".to_string(),
+    }
+}
+
+/// What the files the application names itself are called.
+pub const DEFAULT_NAME_TEMPLATE: &str = "{kind}-{timestamp}";
+const PLACEHOLDERS: [&str; 4] = ["{kind}", "{rule}", "{seed}", "{timestamp}"];
+
+/// Where the files the application names itself go and what they are
+/// called, a template with placeholders for the kind of file, the rule,
+/// the seed and the time.
+#[derive(Clone, Debug)]
+pub struct OutputNames {
+    pub dir: PathBuf,
+    template: String,
+}
+
+impl Default for OutputNames {
+    fn default() -> Self {
+        OutputNames {
+            dir: PathBuf::from("."),
+            template: DEFAULT_NAME_TEMPLATE.to_string(),
+        }
+    }
+}
+
+impl OutputNames {
+    pub fn set_template(&mut self, template: &str) -> Result<(), Error> {
+        let rest = PLACEHOLDERS
+            .iter()
+            .fold(template.to_string(), |rest, placeholder| {
+                rest.replace(placeholder, "")
+            });
+        if rest.contains(['{', '}', '/', '\\']) || template.is_empty() {
+            return Err(Error::Parse(format!(
+                "invalid name template '{}', expected a file name with {}",
+                template,
+                PLACEHOLDERS.join(", ")
+            )));
+        }
+        self.template = template.to_string();
+        Ok(())
+    }
+
+    /// A file to save a `kind` of picture of `context` taken at `now` to,
+    /// with `extension`. Pictures that would get the same name have a
+    /// number appended rather than overwriting each other.
+    pub fn path(
+        &self,
+        kind: &str,
+        context: &SimContext,
+        extension: &str,
+        now: SystemTime,
+    ) -> PathBuf {
+        let rule = match context.automaton {
+            Automaton::Elementary => context.rule.to_string(),
+            Automaton::Life => "life".to_string(),
+        };
+        let name = self
+            .template
+            .replace("{kind}", kind)
+            .replace("{rule}", &rule)
+            .replace("{seed}", &context.seed.to_string())
+            .replace("{timestamp}", &timestamp(now));
+        let mut path = self.dir.join(format!("{}.{}", name, extension));
+        let mut number = 2;
+        while path.exists() {
+            path = self.dir.join(format!("{}-{}.{}", name, number, extension));
+            number += 1;
+        }
+        path
+    }
+}
+
+/// Draws `rows` of cells, top to bottom, as a picture with `scale` by
+/// `scale` pixels per cell.
+pub fn diagram(rows: &[Vec<bool>], scale: u32, live: Color, dead: Color) -> RgbaImage {
+    let width = rows.first().map_or(0, Vec::len) as u32 * scale;
+    let height = rows.len() as u32 * scale;
+    let mut pixels = Vec::with_capacity(width as usize * height as usize * 4);
+    for row in rows {
+        let line: Vec<u8> = row
+            .iter()
+            .flat_map(|&alive| {
+                let color = if alive { live } else { dead };
+                [color.r, color.g, color.b, 255].repeat(scale as usize)
+            })
+            .collect();
+        for _ in 0..scale {
+            pixels.extend_from_slice(&line);
+        }
+    }
+    RgbaImage {
+        width,
+        height,
+        pixels,
+    }
+}
+
+/// Draws `rows` of cells as an SVG, with one rectangle for every run of
+/// live cells in a row to keep the file small.
+pub fn diagram_svg(rows: &[Vec<bool>], scale: u32, live: Color, dead: Color) -> String {
+    let hex = |color: Color| format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b);
+    let width = rows.first().map_or(0, Vec::len) as u32 * scale;
+    let height = rows.len() as u32 * scale;
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" \
+         viewBox=\"0 0 {0} {1}\" shape-rendering=\"crispEdges\">",
+        width, height
+    );
+    let _ = writeln!(
+        svg,
+        "<rect width=\"{}\" height=\"{}\" fill=\"{}\"/>",
+        width,
+        height,
+        hex(dead)
+    );
+    let _ = writeln!(svg, "<g fill=\"{}\">", hex(live));
+    for (y, row) in rows.iter().enumerate() {
+        let mut x = 0;
+        while x < row.len() {
+            if !row[x] {
+                x += 1;
+                continue;
+            }
+            let start = x;
+            while x < row.len() && row[x] {
+                x += 1;
+            }
+            let _ = writeln!(
+                svg,
+                "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"/>",
+                start as u32 * scale,
+                y as u32 * scale,
+                (x - start) as u32 * scale,
+                scale
+            );
+        }
+    }
+    svg.push_str("</g>\n</svg>\n");
+    svg
+}
+
+/// Draws `rows` of cells as lines of full blocks for live cells and
+/// spaces for dead ones, without the spaces at the end of a line.
+pub fn diagram_text(rows: &[Vec<bool>]) -> String {
+    let mut text = String::new();
+    for row in rows {
+        let line: String = row
+            .iter()
+            .map(|&alive| if alive { '\u{2588}' } else { ' ' })
+            .collect();
+        text.push_str(line.trim_end());
+        text.push('\n');
+    }
+    text
+}
+
+/// Writes `rows` of cells as lines of comma separated 1s for live and 0s
+/// for dead cells, which pandas or R read straight into a matrix.
+pub fn diagram_csv(rows: &[Vec<bool>]) -> String {
+    let mut csv = String::new();
+    for row in rows {
+        let cells: Vec<&str> = row
+            .iter()
+            .map(|&alive| if alive { "1" } else { "0" })
+            .collect();
+        csv.push_str(&cells.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+/// What every packed diagram starts with.
+const BITS_MAGIC: &[u8; 4] = b"CA1D";
+const BITS_VERSION: u8 = 1;
+
+/// Packs `rows` of cells eight to a byte after a 24 byte header, laid out
+/// in the README. Every row starts on a new byte, with its first cell in
+/// the highest bit.
+pub fn diagram_bits(rows: &[Vec<bool>], context: &SimContext) -> Vec<u8> {
+    let width = rows.first().map_or(0, Vec::len);
+    let (seed_kind, seed) = match context.seed {
+        Seed::Center => (0, 0),
+        Seed::Random(seed) => (1, seed),
+    };
+    let mut bits = Vec::with_capacity(24 + rows.len() * width.div_ceil(8));
+    bits.extend_from_slice(BITS_MAGIC);
+    bits.push(BITS_VERSION);
+    bits.push(match context.automaton {
+        Automaton::Elementary => 0,
+        Automaton::Life => 1,
+    });
+    bits.push(context.rule);
+    bits.push(seed_kind);
+    bits.extend_from_slice(&(width as u32).to_le_bytes());
+    bits.extend_from_slice(&(rows.len() as u32).to_le_bytes());
+    bits.extend_from_slice(&seed.to_le_bytes());
+    for row in rows {
+        bits.extend(pack_row(row));
+    }
+    bits
+}
+
+/// Packs a row of cells eight to a byte, with the first cell in the
+/// highest bit and the last byte padded with dead cells.
+pub fn pack_row(row: &[bool]) -> Vec<u8> {
+    row.chunks(8)
+        .map(|chunk| {
+            chunk
+                .iter()
+                .enumerate()
+                .fold(0u8, |byte, (i, &alive)| byte | ((alive as u8) << (7 - i)))
+        })
+        .collect()
+}
+
+const JSON_VERSION: u32 = 1;
+
+/// A diagram as JSON, in the schema the README lays out.
+#[derive(Serialize)]
+struct JsonDiagram {
+    version: u32,
+    automaton: &'static str,
+    rule: u8,
+    seed: String,
+    endless: bool,
+    generation: u64,
+    width: usize,
+    height: usize,
+    /// One array per row, `1` for live and `0` for dead cells.
+    cells: Vec<Vec<u8>>,
+}
+
+/// Writes `rows` of cells together with how `context` is set up as JSON.
+pub fn diagram_json(rows: Vec<Vec<bool>>, context: &SimContext) -> Result<String, Error> {
+    let diagram = JsonDiagram {
+        version: JSON_VERSION,
+        automaton: match context.automaton {
+            Automaton::Elementary => "elementary",
+            Automaton::Life => "life",
+        },
+        rule: context.rule,
+        seed: context.seed.to_string(),
+        endless: context.endless,
+        generation: context.generation(),
+        width: rows.first().map_or(0, Vec::len),
+        height: rows.len(),
+        cells: rows
+            .into_iter()
+            .map(|row| row.into_iter().map(u8::from).collect())
+            .collect(),
+    };
+    serde_json::to_string(&diagram).map_err(|e| Error::Serialize(e.to_string()))
+}
+
+/// Writes `rows` of cells as a version 1.0 `.npy` file holding a two
+/// dimensional boolean array, one byte per cell, that `np.load` reads as
+/// it is. See <https://numpy.org/doc/stable/reference/generated/numpy.lib.format.html>.
+pub fn diagram_npy(rows: &[Vec<bool>]) -> Vec<u8> {
+    let width = rows.first().map_or(0, Vec::len);
+    let mut header = format!(
+        "{{'descr': '|b1', 'fortran_order': False, 'shape': ({}, {}), }}",
+        rows.len(),
+        width
+    );
+    // The magic, the version and the header length take 10 bytes, and the
+    // data has to start at a multiple of 64 with the header ending in a
+    // newline.
+    let padding = 64 - (10 + header.len() + 1) % 64;
+    header.extend(std::iter::repeat_n(' ', padding % 64));
+    header.push('\n');
+
+    let mut npy = Vec::with_capacity(10 + header.len() + rows.len() * width);
+    npy.extend_from_slice(b"\x93NUMPY\x01\x00");
+    npy.extend_from_slice(&(header.len() as u16).to_le_bytes());
+    npy.extend_from_slice(header.as_bytes());
+    npy.extend(rows.iter().flatten().map(|&alive| u8::from(alive)));
+    npy
+}
+
+/// The time as `YYYYMMDD-HHMMSS` in UTC.
+fn timestamp(now: SystemTime) -> String {
+    let seconds = now.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let (days, time) = (seconds / 86_400, seconds % 86_400);
+    let (year, month, day) = civil_from_days(days as i64);
+    format!(
+        "{:04}{:02}{:02}-{:02}{:02}{:02}",
+        year,
+        month,
+        day,
+        time / 3600,
+        time / 60 % 60,
+        time % 60
+    )
+}
+
+/// The calendar date the given number of days after 1970-01-01 falls on,
+/// following Howard Hinnant's algorithm.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    } as u32;
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Two rows of ten cells, so that every row takes two bytes, the second
+    /// one padded.
+    fn rows() -> Vec<Vec<bool>> {
+        let row = |cells: &str| cells.chars().map(|c| c == '1').collect();
+        vec![row("1000000001"), row("0110000011")]
+    }
+
+    #[test]
+    fn bits_start_with_the_header() {
+        let context = SimContext::seeded(90, Seed::Random(0x0102_0304_0506_0708));
+        let bits = diagram_bits(&rows(), &context);
+        assert_eq!(bits.len(), 24 + 2 * 2);
+        assert_eq!(&bits[0..4], b"CA1D");
+        assert_eq!(bits[4..8], [1, 0, 90, 1]);
+        assert_eq!(bits[8..12], 10u32.to_le_bytes());
+        assert_eq!(bits[12..16], 2u32.to_le_bytes());
+        assert_eq!(bits[16..24], 0x0102_0304_0506_0708u64.to_le_bytes());
+        assert_eq!(
+            bits[24..],
+            [0b1000_0000, 0b0100_0000, 0b0110_0000, 0b1100_0000]
+        );
+    }
+
+    #[test]
+    fn bits_of_a_centered_seed_have_no_seed_number() {
+        let bits = diagram_bits(&rows(), &SimContext::new());
+        assert_eq!(bits[6..8], [30, 0]);
+        assert_eq!(bits[16..24], [0; 8]);
+    }
+
+    #[test]
+    fn json_holds_the_size_and_the_rows() {
+        let json = diagram_json(rows(), &SimContext::new()).unwrap();
+        let diagram: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(diagram["width"], 10);
+        assert_eq!(diagram["height"], 2);
+        assert_eq!(
+            diagram["cells"],
+            serde_json::json!([
+                [1, 0, 0, 0, 0, 0, 0, 0, 0, 1],
+                [0, 1, 1, 0, 0, 0, 0, 0, 1, 1]
+            ])
+        );
+        assert_eq!(diagram["rule"], 30);
+        assert_eq!(diagram["seed"], "center");
+    }
+
+    #[test]
+    fn npy_is_a_boolean_array_of_the_rows() {
+        let npy = diagram_npy(&rows());
+        assert_eq!(&npy[0..8], b"\x93NUMPY\x01\x00");
+        let header_len = u16::from_le_bytes([npy[8], npy[9]]) as usize;
+        // The data starts on a multiple of 64, right after the newline
+        // ending the header.
+        let data = 10 + header_len;
+        assert_eq!(data % 64, 0);
+        assert_eq!(npy[data - 1], b'\n');
+        let header = std::str::from_utf8(&npy[10..data]).unwrap();
+        assert!(header.starts_with("{'descr': '|b1', 'fortran_order': False, "));
+        assert!(header.contains("'shape': (2, 10)"));
+        // Row by row, a byte for every cell.
+        let cells: Vec<u8> = rows().iter().flatten().map(|&alive| alive as u8).collect();
+        assert_eq!(npy[data..], cells[..]);
+    }
+}
@@ -0,0 +1,244 @@
+//! User scripts in Rhai, given with `--script`, that define a rule of
+//! their own or react to every generation. Scripts run sandboxed: they
+//! cannot read files or load modules, and every call is cut off after a
+//! bounded number of operations.
+
+use crate::color::Color;
+#[cfg(feature = "window")]
+use crate::renderer::Renderer;
+use crate::theme;
+use crate::tick_rate::TickRate;
+use crate::Error;
+use crate::{SimContext, GRID_X_SIZE, GRID_Y_SIZE};
+use log::debug;
+#[cfg(feature = "window")]
+use log::error;
+use rhai::module_resolvers::DummyModuleResolver;
+use rhai::{Engine, EvalAltResult, Scope, AST};
+use std::cell::RefCell;
+use std::fs;
+use std::path::Path;
+use std::rc::Rc;
+
+/// How many operations a single call of a script function may take.
+const MAX_OPERATIONS: u64 = 1_000_000;
+/// How deep script functions may call each other.
+const MAX_CALL_LEVELS: usize = 32;
+/// The largest strings, arrays and maps a script may build.
+const MAX_SIZE: usize = 10_000;
+
+/// What `on_generation` asked for, applied once it returns.
+#[derive(Clone)]
+enum Action {
+    SetRule(u8),
+    SetTickRate(TickRate),
+    Faster,
+    Slower,
+    Pause,
+    Play,
+    SetColors(Color, Color),
+}
+
+/// The first automaton as `on_generation` sees it: where it is, and the
+/// changes asked of it.
+#[derive(Clone)]
+struct Sim {
+    generation: u64,
+    population: usize,
+    rule: u8,
+    paused: bool,
+    cells: Rc<Vec<Vec<bool>>>,
+    actions: Rc<RefCell<Vec<Action>>>,
+}
+
+impl Sim {
+    fn of(context: &SimContext) -> Sim {
+        Sim {
+            generation: context.generation(),
+            population: context.population(),
+            rule: context.rule,
+            paused: context.is_paused(),
+            cells: Rc::new(
+                context
+                    .points
+                    .iter()
+                    .map(|column| column.to_vec())
+                    .collect(),
+            ),
+            actions: Rc::default(),
+        }
+    }
+
+    fn push(&mut self, action: Action) {
+        self.actions.borrow_mut().push(action);
+    }
+}
+
+/// A compiled script and the hooks it defines.
+pub struct Script {
+    engine: Engine,
+    ast: AST,
+    rule: Option<u8>,
+    /// Whether `on_generation` is defined and has not failed yet.
+    hooked: bool,
+    last_generation: Option<u64>,
+}
+
+impl Script {
+    /// Compiles the script at `path` and works out its rule, if it
+    /// defines one.
+    pub fn load(path: &Path) -> Result<Script, Error> {
+        let source = fs::read_to_string(path).map_err(|e| Error::io("read", path, e))?;
+        let invalid =
+            |e: String| Error::Parse(format!("invalid script '{}': {}", path.display(), e));
+        let engine = sandboxed_engine();
+        let ast = engine.compile(source).map_err(|e| invalid(e.to_string()))?;
+        let defines = |name: &str, params: usize| {
+            ast.iter_functions()
+                .any(|function| function.name == name && function.params.len() == params)
+        };
+        let rule = if defines("rule", 3) {
+            let mut rule = 0;
+            for neighborhood in 0..8u8 {
+                let cell = |bit: u8| neighborhood >> bit & 1 == 1;
+                let alive: bool = engine
+                    .call_fn(&mut Scope::new(), &ast, "rule", (cell(2), cell(1), cell(0)))
+                    .map_err(|e| invalid(e.to_string()))?;
+                rule |= (alive as u8) << neighborhood;
+            }
+            debug!("The rule of the script comes down to rule {}", rule);
+            Some(rule)
+        } else {
+            None
+        };
+        let hooked = defines("on_generation", 1);
+        Ok(Script {
+            engine,
+            ast,
+            rule,
+            hooked,
+            last_generation: None,
+        })
+    }
+
+    /// The rule number `rule(left, center, right)` comes down to.
+    pub fn rule(&self) -> Option<u8> {
+        self.rule
+    }
+
+    /// Calls `on_generation` once the first automaton reaches a new
+    /// generation and applies what it asked for to all of them. Returns
+    /// whether anything changed. A script that fails stops being called.
+    #[cfg(feature = "window")]
+    pub fn react(&mut self, contexts: &mut [SimContext], renderer: &mut Renderer) -> bool {
+        let generation = contexts[0].generation();
+        if !self.hooked || self.last_generation == Some(generation) {
+            return false;
+        }
+        self.last_generation = Some(generation);
+        let sim = Sim::of(&contexts[0]);
+        let actions = Rc::clone(&sim.actions);
+        let result =
+            self.engine
+                .call_fn::<()>(&mut Scope::new(), &self.ast, "on_generation", (sim,));
+        if let Err(e) = result {
+            error!("the script stopped: {}", e);
+            self.hooked = false;
+        }
+        let actions = actions.take();
+        for action in &actions {
+            match *action {
+                Action::SetColors(live, dead) => {
+                    let mut theme = renderer.theme().clone();
+                    theme.live_cell = live;
+                    theme.dead_cell = dead;
+                    let name = theme.name.clone();
+                    renderer.replace_theme(&name, theme);
+                }
+                ref action => {
+                    for context in contexts.iter_mut() {
+                        apply(action, context);
+                    }
+                }
+            }
+        }
+        !actions.is_empty()
+    }
+}
+
+fn apply(action: &Action, context: &mut SimContext) {
+    match *action {
+        Action::SetRule(rule) => context.rule = rule,
+        Action::SetTickRate(tick_rate) => context.tick_rate = tick_rate,
+        Action::Faster => context.tick_rate = context.tick_rate.faster(),
+        Action::Slower => context.tick_rate = context.tick_rate.slower(),
+        Action::Pause if !context.is_paused() => context.toggle_pause(),
+        Action::Play if context.is_paused() => context.toggle_pause(),
+        Action::Pause | Action::Play | Action::SetColors(..) => {}
+    }
+}
+
+/// An engine without access to modules or `eval`, limited in how long it
+/// runs and how much it builds, with the `Sim` API registered.
+fn sandboxed_engine() -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .set_module_resolver(DummyModuleResolver::new())
+        .set_max_operations(MAX_OPERATIONS)
+        .set_max_call_levels(MAX_CALL_LEVELS)
+        .set_max_string_size(MAX_SIZE)
+        .set_max_array_size(MAX_SIZE)
+        .set_max_map_size(MAX_SIZE)
+        .disable_symbol("eval")
+        // Stdout is left to the rows `--stream` writes.
+        .on_print(|text| eprintln!("{}", text));
+    engine
+        .register_type_with_name::<Sim>("Sim")
+        .register_get("generation", |sim: &mut Sim| sim.generation as i64)
+        .register_get("population", |sim: &mut Sim| sim.population as i64)
+        .register_get("rule", |sim: &mut Sim| sim.rule as i64)
+        .register_get("paused", |sim: &mut Sim| sim.paused)
+        .register_get("width", |_: &mut Sim| GRID_X_SIZE as i64)
+        .register_get("height", |_: &mut Sim| GRID_Y_SIZE as i64)
+        .register_fn("cell", |sim: &mut Sim, x: i64, y: i64| {
+            sim.cells
+                .get(x as usize)
+                .and_then(|column| column.get(y as usize))
+                .is_some_and(|&alive| alive)
+        })
+        .register_fn(
+            "set_rule",
+            |sim: &mut Sim, rule: i64| -> Result<(), Box<EvalAltResult>> {
+                let rule = u8::try_from(rule)
+                    .map_err(|_| failure(Error::InvalidRule(rule.to_string())))?;
+                sim.push(Action::SetRule(rule));
+                Ok(())
+            },
+        )
+        .register_fn(
+            "set_tick_rate",
+            |sim: &mut Sim, tick_rate: &str| -> Result<(), Box<EvalAltResult>> {
+                sim.push(Action::SetTickRate(tick_rate.parse().map_err(failure)?));
+                Ok(())
+            },
+        )
+        .register_fn("faster", |sim: &mut Sim| sim.push(Action::Faster))
+        .register_fn("slower", |sim: &mut Sim| sim.push(Action::Slower))
+        .register_fn("pause", |sim: &mut Sim| sim.push(Action::Pause))
+        .register_fn("play", |sim: &mut Sim| sim.push(Action::Play))
+        .register_fn(
+            "set_colors",
+            |sim: &mut Sim, live: &str, dead: &str| -> Result<(), Box<EvalAltResult>> {
+                let live = theme::parse_color(live).map_err(failure)?;
+                let dead = theme::parse_color(dead).map_err(failure)?;
+                sim.push(Action::SetColors(live, dead));
+                Ok(())
+            },
+        );
+    engine
+}
+
+/// Fails a call from a script with the message of `error`.
+fn failure(error: Error) -> Box<EvalAltResult> {
+    error.to_string().into()
+}
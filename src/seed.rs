@@ -0,0 +1,111 @@
+//! How automata start, and the random generator scattering their cells.
+
+use crate::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// The initial row the automaton grows from.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Seed {
+    /// A single live cell in the middle of the row.
+    Center,
+    /// Every cell of the row drawn from a random generator with this seed.
+    Random(u64),
+}
+
+impl Seed {
+    /// The first row, `width` cells wide, which may be none.
+    pub fn cells(&self, width: usize) -> Vec<bool> {
+        let mut cells = vec![false; width];
+        match *self {
+            Seed::Center => {
+                if let Some(cell) = cells.get_mut(center(width)) {
+                    *cell = true;
+                }
+            }
+            Seed::Random(seed) => {
+                let mut rng = SplitMix64::new(seed);
+                for cell in cells.iter_mut() {
+                    *cell = rng.next_u64() & 1 == 1;
+                }
+            }
+        }
+        cells
+    }
+}
+
+/// The column of a row `width` cells wide that [`Seed::Center`] brings to
+/// life, the one on the right of the two middle ones of an even width.
+pub fn center(width: usize) -> usize {
+    width / 2
+}
+
+/// Parses `center` or a number to use as the random seed.
+impl FromStr for Seed {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "center" => Ok(Seed::Center),
+            _ => s.parse().map(Seed::Random).map_err(|_| {
+                Error::Parse(format!(
+                    "invalid seed '{}', expected 'center' or a number",
+                    s
+                ))
+            }),
+        }
+    }
+}
+
+impl fmt::Display for Seed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Seed::Center => write!(f, "center"),
+            Seed::Random(seed) => write!(f, "{}", seed),
+        }
+    }
+}
+
+/// A tiny, dependency free pseudo random generator. Good enough to scatter
+/// cells around, not meant for anything else.
+pub struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    /// A generator that always draws the same numbers for the same `seed`.
+    pub fn new(seed: u64) -> SplitMix64 {
+        SplitMix64 { state: seed }
+    }
+
+    /// Draws the next number.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn live(cells: &[bool]) -> Vec<usize> {
+        (0..cells.len()).filter(|&x| cells[x]).collect()
+    }
+
+    #[test]
+    fn center_seeds_the_middle_cell() {
+        assert_eq!(live(&Seed::Center.cells(1)), [0]);
+        assert_eq!(live(&Seed::Center.cells(2)), [1]);
+        assert_eq!(live(&Seed::Center.cells(101)), [50]);
+        assert_eq!(center(101), 50);
+    }
+
+    #[test]
+    fn center_seeds_nothing_on_an_empty_row() {
+        assert!(Seed::Center.cells(0).is_empty());
+    }
+}
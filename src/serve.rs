@@ -0,0 +1,334 @@
+//! A WebSocket server for `--serve`, so dashboards in a browser can mirror
+//! the first window: every row it completes goes out to every client, with
+//! its statistics whenever they change, and clients send commands back
+//! like `--control` takes them.
+
+use crate::command::Command;
+use crate::remote::{self, WakeUp};
+use crate::row_stream::RowCursor;
+use crate::screenshot;
+use crate::Error;
+use crate::{Automaton, SimContext};
+use log::{debug, info};
+use serde::Serialize;
+use std::io;
+use std::net::{TcpListener, TcpStream};
+use std::str::FromStr;
+use std::sync::mpsc::{self, Receiver, Sender, SyncSender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tungstenite::http::{header, StatusCode};
+use tungstenite::Message;
+
+/// How long a client waits for a command before sending what is queued.
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+/// How long a connection has to finish the handshake, and a client to take
+/// a message.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+/// How many messages wait for a client before it counts as stalled and
+/// is let go.
+const QUEUED_MESSAGES: usize = 1024;
+
+/// How `--serve` sends the rows.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ServeFormat {
+    /// A JSON message for every row, its cells as `0`s and `1`s.
+    Json,
+    /// A binary message for every row: the generation as 8 big endian
+    /// bytes, then the cells packed like `--stream bin` does.
+    Bits,
+}
+
+impl FromStr for ServeFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(ServeFormat::Json),
+            "bin" => Ok(ServeFormat::Bits),
+            _ => Err(Error::Parse(format!(
+                "invalid serve format '{}', expected json or bin",
+                s
+            ))),
+        }
+    }
+}
+
+/// Where the first automaton is.
+#[derive(Clone, PartialEq, Serialize)]
+struct Stats {
+    automaton: &'static str,
+    rule: u8,
+    generation: u64,
+    population: usize,
+    paused: bool,
+    tick_rate: String,
+}
+
+impl Stats {
+    fn of(context: &SimContext) -> Stats {
+        Stats {
+            automaton: match context.automaton {
+                Automaton::Elementary => "elementary",
+                Automaton::Life => "life",
+            },
+            rule: context.rule,
+            generation: context.generation(),
+            population: context.population(),
+            paused: context.is_paused(),
+            tick_rate: context.tick_rate.to_string(),
+        }
+    }
+}
+
+/// What clients are sent as text, told apart by `type`.
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum Update<'a> {
+    Row {
+        generation: u64,
+        cells: String,
+    },
+    Stats(&'a Stats),
+    /// The answer to a command, `error` is left out if it went fine.
+    Reply {
+        ok: bool,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+}
+
+impl Update<'_> {
+    fn message(&self) -> Message {
+        Message::text(serde_json::to_string(self).unwrap_or_default())
+    }
+}
+
+/// A connection, with the messages the main thread sends it.
+struct Client {
+    messages: SyncSender<Message>,
+    /// Whether it has been sent the statistics yet.
+    greeted: bool,
+}
+
+/// The server, answering clients in the background.
+pub struct Server {
+    format: ServeFormat,
+    clients: Arc<Mutex<Vec<Client>>>,
+    commands: Receiver<Command>,
+    rows: RowCursor,
+    stats: Option<Stats>,
+}
+
+impl Server {
+    /// Listens on `address` in the background, calling `wake_up` for
+    /// every command and every new client.
+    pub fn spawn(address: &str, format: ServeFormat, wake_up: WakeUp) -> Result<Server, Error> {
+        let listener = TcpListener::bind(address)
+            .map_err(|e| Error::Config(format!("could not listen on {}: {}", address, e)))?;
+        info!("Serving the first window on ws://{}", address);
+        let clients = Arc::new(Mutex::new(Vec::new()));
+        let (sender, commands) = mpsc::channel();
+        let accepted = Arc::clone(&clients);
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let (sender, wake_up, clients) =
+                    (sender.clone(), Arc::clone(&wake_up), Arc::clone(&accepted));
+                thread::spawn(move || serve(stream, &sender, &*wake_up, &clients));
+            }
+        });
+        Ok(Server {
+            format,
+            clients,
+            commands,
+            rows: RowCursor::default(),
+            stats: None,
+        })
+    }
+
+    /// The commands that came in since the last call.
+    pub fn commands(&self) -> Vec<Command> {
+        self.commands.try_iter().collect()
+    }
+
+    /// Sends every client the rows `context` completed since the last
+    /// call, then the statistics if the generation or a setting changed,
+    /// or the client is new.
+    pub fn publish(&mut self, context: &SimContext) {
+        let mut messages = Vec::new();
+        for (generation, row) in self.rows.completed(context) {
+            messages.push(match self.format {
+                ServeFormat::Json => Update::Row {
+                    generation,
+                    cells: row
+                        .iter()
+                        .map(|&alive| if alive { '1' } else { '0' })
+                        .collect(),
+                }
+                .message(),
+                ServeFormat::Bits => {
+                    let mut bytes = generation.to_be_bytes().to_vec();
+                    bytes.extend(screenshot::pack_row(&row));
+                    Message::binary(bytes)
+                }
+            });
+        }
+        let stats = Stats::of(context);
+        // In between generations the population changes with every cell.
+        let changed = self.stats.as_ref().is_none_or(|old| {
+            Stats {
+                population: stats.population,
+                ..old.clone()
+            } != stats
+        });
+        let stats_message = Update::Stats(&stats).message();
+        let mut clients = self.clients.lock().unwrap_or_else(|e| e.into_inner());
+        // A client that has gone lets go of its messages, one that stalled
+        // is let go of.
+        clients.retain_mut(|client| {
+            let mut sent = messages
+                .iter()
+                .all(|message| client.messages.try_send(message.clone()).is_ok());
+            if changed || !client.greeted {
+                sent &= client.messages.try_send(stats_message.clone()).is_ok();
+                client.greeted = true;
+            }
+            sent
+        });
+        self.stats = Some(stats);
+    }
+}
+
+/// Talks to the client on `stream` until it goes, passing its commands on
+/// and sending it what the main thread queues.
+fn serve(
+    stream: TcpStream,
+    sender: &Sender<Command>,
+    wake_up: &(dyn Fn() + Send + Sync),
+    clients: &Mutex<Vec<Client>>,
+) {
+    let peer = stream
+        .peer_addr()
+        .map(|peer| peer.to_string())
+        .unwrap_or_default();
+    if stream
+        .set_read_timeout(Some(HANDSHAKE_TIMEOUT))
+        .and_then(|()| stream.set_write_timeout(Some(HANDSHAKE_TIMEOUT)))
+        .is_err()
+    {
+        return;
+    }
+    let mut socket = match tungstenite::accept_hdr(stream, check_origin) {
+        Ok(socket) => socket,
+        Err(e) => {
+            debug!("{} is no WebSocket client: {}", peer, e);
+            return;
+        }
+    };
+    if socket
+        .get_ref()
+        .set_read_timeout(Some(POLL_INTERVAL))
+        .is_err()
+    {
+        return;
+    }
+    info!("{} connected", peer);
+    let (messages, queued) = mpsc::sync_channel(QUEUED_MESSAGES);
+    clients
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .push(Client {
+            messages,
+            greeted: false,
+        });
+    wake_up();
+    'serving: loop {
+        for message in queued.try_iter() {
+            if socket.send(message).is_err() {
+                break 'serving;
+            }
+        }
+        let reply = match socket.read() {
+            Ok(Message::Text(line)) => match remote::parse(&line) {
+                Ok(None) => continue,
+                Ok(Some(command)) => {
+                    debug!("Command {:?} from {}", command, peer);
+                    if sender.send(command).is_err() {
+                        break;
+                    }
+                    wake_up();
+                    Update::Reply {
+                        ok: true,
+                        error: None,
+                    }
+                }
+                Err(e) => Update::Reply {
+                    ok: false,
+                    error: Some(e.to_string()),
+                },
+            },
+            Ok(Message::Close(_)) => break,
+            Ok(_) => continue,
+            Err(tungstenite::Error::Io(e))
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) =>
+            {
+                continue
+            }
+            Err(_) => break,
+        };
+        if socket.send(reply.message()).is_err() {
+            break;
+        }
+    }
+    info!("{} disconnected", peer);
+}
+
+/// Turns browsers away unless the page that connects comes from this
+/// machine, as any site the user visits could drive the window otherwise.
+/// Clients outside a browser send no origin.
+// tungstenite decides what the refusal looks like.
+#[allow(clippy::result_large_err)]
+fn check_origin(request: &Request, response: Response) -> Result<Response, ErrorResponse> {
+    let origin = request.headers().get(header::ORIGIN);
+    match origin.map(|origin| origin.to_str().map(is_local)) {
+        None | Some(Ok(true)) => Ok(response),
+        _ => {
+            let mut refusal = ErrorResponse::new(Some("origin not allowed".to_string()));
+            *refusal.status_mut() = StatusCode::FORBIDDEN;
+            Err(refusal)
+        }
+    }
+}
+
+/// Whether `origin`, like `http://localhost:8000`, is a page on this
+/// machine.
+fn is_local(origin: &str) -> bool {
+    let Some((_, host)) = origin.split_once("://") else {
+        return false;
+    };
+    let host = match host.strip_prefix('[') {
+        Some(address) => address.split(']').next(),
+        None => host.split([':', '/']).next(),
+    };
+    matches!(host, Some("localhost" | "127.0.0.1" | "::1"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_local_origins_are_local() {
+        assert!(is_local("http://localhost:8000"));
+        assert!(is_local("http://127.0.0.1"));
+        assert!(is_local("http://[::1]:8030"));
+        assert!(!is_local("https://example.com"));
+        assert!(!is_local("http://localhost.example.com"));
+        assert!(!is_local("null"));
+    }
+}
@@ -0,0 +1,444 @@
+use crate::bookmarks;
+use crate::center_column::ColumnFormat;
+use crate::command::{Command, Key, KeyMap};
+use crate::gif_export::GifRecorder;
+use crate::kiosk::Kiosk;
+use crate::library::LIBRARY;
+use crate::macro_recorder::MacroRecorder;
+use crate::presets::Preset;
+use crate::renderer::{Direction, Renderer};
+use crate::row_stream::RowStream;
+use crate::screenshot::DiagramFormat;
+use crate::script::Script;
+use crate::stdin_rows::StdinRows;
+use crate::Error;
+use crate::{state_file, SimContext, DEFAULT_STATE_FILE};
+use log::{debug, error, info, warn};
+use sdl2::event::{Event, WindowEvent};
+use sdl2::keyboard::{Keycode, Mod};
+use sdl2::mouse::MouseButton;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// How long a first quit request waits for its confirmation.
+const QUIT_CONFIRMATION_TIMEOUT: Duration = Duration::from_secs(2);
+const TITLE_UPDATE_INTERVAL: Duration = Duration::from_secs(1);
+const OPACITY_STEP: f32 = 0.1;
+
+/// What a window asks of the event loop driving all of them.
+pub enum Request {
+    Close,
+    OpenWindow,
+    ReloadConfig,
+}
+
+/// A window together with the automata it shows and everything about
+/// playing them that other windows should not share.
+pub struct Session {
+    pub renderer: Renderer,
+    pub contexts: Vec<SimContext>,
+    pub kiosk: Option<Kiosk>,
+    /// What the Escape key does.
+    pub escape: Command,
+    pub keys: KeyMap,
+    /// The presets to cycle through, and the one last switched to.
+    pub presets: Vec<Preset>,
+    pub preset: Option<usize>,
+    pub confirm_quit: bool,
+    /// The pixels per cell diagrams are exported with.
+    pub export_scale: u32,
+    pub diagram_format: DiagramFormat,
+    pub column_format: ColumnFormat,
+    /// Where the automata are saved to and loaded from.
+    pub state_file: PathBuf,
+    /// Records the first automaton's generations into an animated GIF.
+    pub gif: Option<GifRecorder>,
+    /// Writes the first automaton's rows to stdout as they are completed.
+    pub stream: Option<RowStream>,
+    /// Shows rows piped in on stdin in the first automaton instead of
+    /// computing them, until stdin is closed.
+    pub stdin: Option<StdinRows>,
+    /// Reacts to the first automaton's generations.
+    pub script: Option<Script>,
+    recorder: MacroRecorder,
+    direction: Direction,
+    commands: Vec<Command>,
+    needs_redraw: bool,
+    quit_requested_at: Option<Instant>,
+    title_updated_at: Option<Instant>,
+    /// The frame of the last scroll in endless mode and how many rows had
+    /// scrolled out by then.
+    last_scroll: Option<(u64, u64)>,
+    /// The frames between the last two scrolls.
+    scroll_interval: u64,
+}
+
+impl Session {
+    pub fn new(renderer: Renderer, contexts: Vec<SimContext>) -> Session {
+        Session {
+            renderer,
+            contexts,
+            kiosk: None,
+            escape: Command::Quit,
+            keys: KeyMap::default(),
+            presets: Vec::new(),
+            preset: None,
+            confirm_quit: false,
+            export_scale: 1,
+            diagram_format: DiagramFormat::Png,
+            column_format: ColumnFormat::Bits,
+            state_file: PathBuf::from(DEFAULT_STATE_FILE),
+            gif: None,
+            stream: None,
+            stdin: None,
+            script: None,
+            recorder: MacroRecorder::default(),
+            direction: Direction::Forward,
+            commands: Vec::new(),
+            needs_redraw: true,
+            quit_requested_at: None,
+            title_updated_at: None,
+            last_scroll: None,
+            scroll_interval: 0,
+        }
+    }
+
+    pub fn window_id(&self) -> u32 {
+        self.renderer.window_id()
+    }
+
+    /// Whether nothing would change until the next input.
+    pub fn is_idle(&self) -> bool {
+        !self.needs_redraw
+            && self.contexts.iter().all(SimContext::is_paused)
+            && !self.recorder.is_playing()
+            && self.kiosk.is_none()
+            && !self.renderer.is_recording()
+    }
+
+    pub fn request_redraw(&mut self) {
+        self.needs_redraw = true;
+    }
+
+    /// Handles an event sent to this session's window. `mouse` is where
+    /// the pointer is within the window.
+    pub fn handle_event(&mut self, event: Event, frame: u64, mouse: (i32, i32)) {
+        let renderer = &mut self.renderer;
+        match event {
+            Event::Window {
+                win_event: WindowEvent::Leave,
+                ..
+            } => {
+                renderer.hover = None;
+                self.needs_redraw = true;
+            }
+            Event::Window { .. } => self.needs_redraw = true,
+            Event::MouseMotion {
+                mousestate,
+                xrel,
+                yrel,
+                ..
+            } if mousestate.middle() => {
+                renderer.pan(xrel, yrel);
+                self.needs_redraw = true;
+            }
+            Event::MouseMotion { x, y, .. } => {
+                renderer.hover = Some((x, y));
+                self.needs_redraw |= renderer.show_inspector;
+            }
+            Event::MouseButtonDown {
+                mouse_btn: MouseButton::Left,
+                x,
+                y,
+                ..
+            } => self.needs_redraw |= renderer.click_minimap(x, y) || renderer.click_cell(x, y),
+            Event::MouseButtonDown {
+                mouse_btn: MouseButton::Right,
+                ..
+            } => {
+                renderer.light_cone = None;
+                self.needs_redraw = true;
+            }
+            Event::MouseWheel { y, .. } => {
+                renderer.zoom_at(y, mouse);
+                self.needs_redraw = true;
+            }
+            Event::KeyDown {
+                keycode: Some(keycode),
+                keymod,
+                ..
+            } => {
+                let ctrl = keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD);
+                let command = match keycode {
+                    Keycode::Escape => Some(self.escape),
+                    keycode => {
+                        Key::from_name(&keycode.name()).and_then(|key| self.keys.command(key, ctrl))
+                    }
+                };
+                if let Some(command) = command {
+                    self.queue(frame, command);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Gives the window `command` as if its key was pressed.
+    pub fn queue(&mut self, frame: u64, command: Command) {
+        self.recorder.record(frame, command);
+        self.commands.push(command);
+    }
+
+    /// Applies the commands given since the last frame, advances the
+    /// automata and redraws the window if anything changed.
+    pub fn update(&mut self, frame: u64) -> Result<Vec<Request>, Error> {
+        let mut requests = Vec::new();
+        let mut commands = std::mem::take(&mut self.commands);
+        commands.extend(self.recorder.replay(frame));
+
+        let paused = self.contexts.iter().all(SimContext::is_paused);
+        self.needs_redraw |=
+            !commands.is_empty() || !paused || self.kiosk.is_some() || self.renderer.is_recording();
+        if let Some(requested_at) = self.quit_requested_at {
+            if requested_at.elapsed() > QUIT_CONFIRMATION_TIMEOUT {
+                self.quit_requested_at = None;
+                self.title_updated_at = None;
+            }
+        }
+
+        let renderer = &mut self.renderer;
+        for command in commands {
+            debug!("{:?}", command);
+            match command {
+                Command::Quit if !self.confirm_quit || self.quit_requested_at.is_some() => {
+                    // A screenshot asked for just before is only taken
+                    // when the next frame is drawn, which there is none of.
+                    if renderer.screenshot_requested {
+                        renderer.draw(&self.contexts)?;
+                    }
+                    requests.push(Request::Close);
+                    return Ok(requests);
+                }
+                Command::Quit => {
+                    self.quit_requested_at = Some(Instant::now());
+                    renderer.set_title("Press quit again to exit")?;
+                }
+                Command::NewWindow => requests.push(Request::OpenWindow),
+                Command::ReloadConfig => requests.push(Request::ReloadConfig),
+                Command::ToggleRecording => self.recorder.toggle_recording(frame),
+                Command::PlayMacro => self.recorder.play(frame),
+                Command::ToggleDirection => {
+                    self.direction = match self.direction {
+                        Direction::Forward => Direction::Backward,
+                        Direction::Backward => Direction::Forward,
+                    }
+                }
+                Command::ZoomIn => renderer.zoom_in(),
+                Command::ZoomOut => renderer.zoom_out(),
+                Command::ZoomToFit => renderer.zoom_to_fit(&self.contexts[0]),
+                Command::Pan(dx, dy) => renderer.pan_cells(dx, dy),
+                Command::ToggleFullscreen => renderer.toggle_fullscreen()?,
+                Command::ToggleBorderless => renderer.toggle_borderless(),
+                Command::ToggleAlwaysOnTop => renderer.toggle_always_on_top(),
+                Command::MoreOpaque => renderer.change_opacity(OPACITY_STEP),
+                Command::LessOpaque => renderer.change_opacity(-OPACITY_STEP),
+                Command::NextTheme => renderer.next_theme(),
+                Command::InvertColors => renderer.invert_colors(),
+                Command::ToggleDarkLight => renderer.toggle_dark_light(),
+                Command::NextCellColoring => renderer.coloring = renderer.coloring.next(),
+                Command::NextView => renderer.view = renderer.view.next(),
+                Command::ToggleGridLines => renderer.show_grid_lines = !renderer.show_grid_lines,
+                Command::ToggleScanner => renderer.show_scanner = !renderer.show_scanner,
+                Command::ToggleHud => renderer.show_hud = !renderer.show_hud,
+                Command::ToggleRates => renderer.show_rates = !renderer.show_rates,
+                Command::ToggleHelp => renderer.next_help_page(),
+                Command::ToggleInspector => renderer.show_inspector = !renderer.show_inspector,
+                Command::ToggleCrt => renderer.crt = !renderer.crt,
+                Command::ToggleTriangles => renderer.show_triangles = !renderer.show_triangles,
+                Command::Screenshot => renderer.screenshot_requested = true,
+                Command::ToggleVideo => renderer.toggle_recording_video(&self.contexts[0]),
+                Command::SaveState => match state_file::save(&self.state_file, &self.contexts)
+                    .and_then(|()| bookmarks::save(&self.state_file, &renderer.bookmarks))
+                {
+                    Ok(()) => eprintln!("Saved the state to {}", self.state_file.display()),
+                    Err(e) => error!("{}", e),
+                },
+                Command::LoadState => match state_file::load(&self.state_file) {
+                    Ok(contexts) => {
+                        info!("Loaded the state from {}", self.state_file.display());
+                        renderer.set_pane_count(contexts.len())?;
+                        self.contexts = contexts;
+                        self.last_scroll = None;
+                        match bookmarks::load(&self.state_file) {
+                            Ok(bookmarks) => renderer.bookmarks = bookmarks,
+                            Err(e) => error!("{}", e),
+                        }
+                    }
+                    Err(e) => error!("{}", e),
+                },
+                Command::ToggleBookmark => {
+                    bookmarks::toggle(&mut renderer.bookmarks, self.contexts[0].generation())
+                }
+                Command::ExportDiagram => {
+                    for context in &self.contexts {
+                        renderer.export_diagram(context, self.export_scale, self.diagram_format);
+                    }
+                }
+                Command::CopyDiagram => renderer.copy_diagram(&self.contexts),
+                Command::SaveCenterColumn => {
+                    renderer.save_center_column(&self.contexts[0], self.column_format)
+                }
+                Command::ToggleLibrary => {
+                    renderer.library = match renderer.library {
+                        Some(_) => None,
+                        None => Some(0),
+                    }
+                }
+                Command::PickPattern(by) => {
+                    if let Some(picked) = &mut renderer.library {
+                        let count = LIBRARY.len() as i32;
+                        *picked = (*picked as i32 + by).rem_euclid(count) as usize;
+                    }
+                }
+                Command::InsertPattern => {
+                    if let Some(picked) = renderer.library.take() {
+                        for context in &mut self.contexts {
+                            context.insert(&LIBRARY[picked]);
+                        }
+                        self.last_scroll = None;
+                    }
+                }
+                Command::NextPreset if self.presets.is_empty() => {
+                    warn!("there are no presets in the config file")
+                }
+                Command::NextPreset => {
+                    let index = self
+                        .preset
+                        .map_or(0, |index| (index + 1) % self.presets.len());
+                    let preset = self.presets[index].clone();
+                    for context in &mut self.contexts {
+                        if preset.rule.is_some() || preset.seed.is_some() {
+                            context.reset(
+                                preset.rule.unwrap_or(context.rule),
+                                preset.seed.unwrap_or(context.seed),
+                            );
+                        }
+                        context.tick_rate = preset.tick_rate.unwrap_or(context.tick_rate);
+                    }
+                    if let Some(theme) = preset.theme {
+                        renderer.set_theme(theme);
+                    }
+                    info!("Switched to preset '{}'", preset.name);
+                    self.preset = Some(index);
+                    self.last_scroll = None;
+                    self.title_updated_at = None;
+                }
+                Command::ToggleScaleFilter => {
+                    renderer.scale_filter = renderer.scale_filter.toggle()
+                }
+                command => {
+                    for context in &mut self.contexts {
+                        command.apply_to(context);
+                    }
+                }
+            }
+        }
+
+        let ticks = self.contexts[0].tick_rate.ticks_on_frame(frame);
+        if let Some(stdin) = &mut self.stdin {
+            // Every tick shows a row piped in instead of computing one.
+            let context = &mut self.contexts[0];
+            if !context.is_paused() && !stdin.show(context, ticks) {
+                info!("stdin is closed, computing the rows again");
+                self.stdin = None;
+            }
+        } else {
+            for context in &mut self.contexts {
+                for _ in 0..ticks {
+                    match self.direction {
+                        Direction::Forward => context.next_tick(),
+                        Direction::Backward => context.previous_tick(),
+                    }
+                }
+            }
+        }
+        if !self.contexts[0].is_paused() {
+            renderer.measured_tps.add(Instant::now(), ticks);
+        }
+        if let Some(script) = &mut self.script {
+            self.needs_redraw |= script.react(&mut self.contexts, &mut self.renderer);
+        }
+        if let Some(gif) = &mut self.gif {
+            let context = &self.contexts[0];
+            gif.capture(context.generation(), &context.points)?;
+        }
+        if let Some(stream) = &mut self.stream {
+            match stream.write(&self.contexts[0], &mut std::io::stdout().lock()) {
+                Ok(()) => {}
+                // Whatever read the rows has gone, the window stays open.
+                Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => self.stream = None,
+                Err(e) => {
+                    error!("could not stream the rows: {}", e);
+                    self.stream = None;
+                }
+            }
+        }
+        let scroll_offset = self.scroll_offset(frame);
+        let renderer = &mut self.renderer;
+        renderer.scroll_offset = scroll_offset;
+
+        if let Some(kiosk) = &mut self.kiosk {
+            let now = Instant::now();
+            if let Some(preset) = kiosk.update(now) {
+                info!("Kiosk mode shows rule {} from {}", preset.rule, preset.seed);
+                for context in &mut self.contexts {
+                    context.reset(preset.rule, preset.seed);
+                }
+            }
+            renderer.fade = kiosk.fade(now);
+        }
+        let title_due = self
+            .title_updated_at
+            .is_none_or(|at| at.elapsed() >= TITLE_UPDATE_INTERVAL);
+        if title_due && self.quit_requested_at.is_none() {
+            let mut titles: Vec<String> = self.contexts.iter().map(SimContext::title).collect();
+            if let Some(index) = self.preset {
+                titles.insert(0, self.presets[index].name.clone());
+            }
+            renderer.set_title(&titles.join(" | "))?;
+            self.title_updated_at = Some(Instant::now());
+        }
+        if self.needs_redraw {
+            renderer.draw(&self.contexts)?;
+            renderer.present();
+            self.needs_redraw = false;
+        }
+        Ok(requests)
+    }
+
+    /// How far behind the picture should lag to smooth out the last
+    /// scroll, running from a whole row right after it to none by the
+    /// time the next one is due.
+    fn scroll_offset(&mut self, frame: u64) -> f32 {
+        let context = &self.contexts[0];
+        let scrolled = context.scrolled_rows;
+        match self.last_scroll {
+            Some((_, rows)) if rows == scrolled => {}
+            Some((at, rows)) if rows < scrolled => {
+                self.scroll_interval = (frame - at) / (scrolled - rows);
+                self.last_scroll = Some((frame, scrolled));
+            }
+            // The first scroll, or stepping back past one.
+            _ => {
+                self.scroll_interval = 0;
+                self.last_scroll = Some((frame, scrolled));
+            }
+        }
+        if !context.endless || context.is_paused() || self.scroll_interval <= 1 {
+            return 0.0;
+        }
+        let (at, _) = self.last_scroll.unwrap_or((frame, 0));
+        let progress = (frame - at) as f32 / self.scroll_interval as f32;
+        1.0 - progress.min(1.0)
+    }
+}
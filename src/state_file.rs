@@ -0,0 +1,394 @@
+use crate::seed::Seed;
+use crate::Error;
+use crate::{
+    Automaton, Boundary, Point, SimContext, SimulationState, StepMode, GRID_X_SIZE, GRID_Y_SIZE,
+};
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// How old the automata saved on exit may get before resuming them makes
+/// no more sense than starting over.
+const AUTOSAVE_MAX_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+const LIVE: char = 'o';
+const DEAD: char = '.';
+
+/// The automata of a window, as written to a TOML file. The undo history
+/// is left out, so a loaded automaton cannot step back past where it was
+/// saved.
+#[derive(Serialize, Deserialize)]
+struct StateFile {
+    automata: Vec<AutomatonState>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct AutomatonState {
+    automaton: String,
+    rule: u8,
+    seed: String,
+    playing: bool,
+    step_mode: String,
+    cells_per_tick: u32,
+    tick_rate: String,
+    /// What the ends of a row see, left out by files from before it was
+    /// saved, which all used the helix.
+    #[serde(default)]
+    boundary: Option<String>,
+    endless: bool,
+    scrolled_rows: u64,
+    grid_generation: u64,
+    scanner: Vec<(i32, i32)>,
+    /// One string per row, `o` for live and `.` for dead cells.
+    cells: Vec<String>,
+    /// One string per row, the neighborhood that produced every cell or
+    /// `.` for cells that were never computed.
+    cases: Vec<String>,
+    /// Per row, how many generations of Game of Life every cell has been
+    /// alive for. Empty when nobody kept count.
+    #[serde(default)]
+    heat: Vec<Vec<u32>>,
+}
+
+pub fn save(path: &Path, contexts: &[SimContext]) -> Result<(), Error> {
+    let file = StateFile {
+        automata: contexts.iter().map(AutomatonState::of).collect(),
+    };
+    let contents = toml::to_string(&file).map_err(|e| Error::Serialize(e.to_string()))?;
+    fs::write(path, contents).map_err(|e| Error::io("write", path, e))
+}
+
+pub fn load(path: &Path) -> Result<Vec<SimContext>, Error> {
+    let contents = fs::read_to_string(path).map_err(|e| Error::io("read", path, e))?;
+    let file: StateFile = toml::from_str(&contents)
+        .map_err(|e| Error::Parse(format!("invalid state '{}': {}", path.display(), e)))?;
+    if !(1..=4).contains(&file.automata.len()) {
+        return Err(Error::Parse(format!(
+            "invalid state '{}': expected one to four automata",
+            path.display()
+        )));
+    }
+    file.automata
+        .iter()
+        .map(|state| {
+            state
+                .restore()
+                .map_err(|e| Error::Parse(format!("invalid state '{}': {}", path.display(), e)))
+        })
+        .collect()
+}
+
+/// This application's directory in the platform's directory for
+/// application data, where everything kept between runs goes. `None` if
+/// there is no such directory.
+pub fn data_dir() -> Option<PathBuf> {
+    let var = |name: &str| env::var_os(name).filter(|value| !value.is_empty());
+    let data_dir = if cfg!(target_os = "windows") {
+        PathBuf::from(var("APPDATA")?)
+    } else if cfg!(target_os = "macos") {
+        PathBuf::from(var("HOME")?).join("Library/Application Support")
+    } else {
+        match var("XDG_DATA_HOME") {
+            Some(dir) => PathBuf::from(dir),
+            None => PathBuf::from(var("HOME")?).join(".local/share"),
+        }
+    };
+    Some(data_dir.join("cellular_automata"))
+}
+
+/// Where the automata are saved to on exit.
+pub fn autosave_path() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join("autosave.toml"))
+}
+
+pub fn autosave(contexts: &[SimContext]) -> Result<(), Error> {
+    let path =
+        autosave_path().ok_or_else(|| Error::Config("nowhere to autosave to".to_string()))?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| Error::io("create", dir, e))?;
+    }
+    save(&path, contexts)
+}
+
+/// The automata saved on the last exit, unless there are none or they
+/// have grown stale.
+pub fn resume() -> Result<Vec<SimContext>, Error> {
+    let path =
+        autosave_path().ok_or_else(|| Error::Config("nowhere to resume from".to_string()))?;
+    resume_from(&path)
+}
+
+fn resume_from(path: &Path) -> Result<Vec<SimContext>, Error> {
+    let modified = fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .map_err(|_| Error::Config("no saved session to resume".to_string()))?;
+    let age = SystemTime::now()
+        .duration_since(modified)
+        .unwrap_or_default();
+    if age > AUTOSAVE_MAX_AGE {
+        return Err(Error::Config(format!(
+            "the saved session is {} days old",
+            age.as_secs() / (24 * 60 * 60)
+        )));
+    }
+    load(path)
+}
+
+impl AutomatonState {
+    fn of(context: &SimContext) -> AutomatonState {
+        let rows = |cell: &dyn Fn(usize, usize) -> char| {
+            (0..GRID_Y_SIZE as usize)
+                .map(|y| (0..GRID_X_SIZE as usize).map(|x| cell(x, y)).collect())
+                .collect()
+        };
+        let heated = context.heat.iter().flatten().any(|&heat| heat > 0);
+        AutomatonState {
+            automaton: match context.automaton {
+                Automaton::Elementary => "elementary",
+                Automaton::Life => "life",
+            }
+            .to_string(),
+            rule: context.rule,
+            seed: context.seed.to_string(),
+            playing: !context.is_paused(),
+            step_mode: match context.step_mode {
+                StepMode::Cell => "cell",
+                StepMode::Row => "row",
+            }
+            .to_string(),
+            cells_per_tick: context.cells_per_tick,
+            tick_rate: context.tick_rate.to_string(),
+            boundary: Some(
+                match context.boundary {
+                    Boundary::Helix => "helix",
+                    Boundary::Dead => "dead",
+                    Boundary::Wrap => "wrap",
+                }
+                .to_string(),
+            ),
+            endless: context.endless,
+            scrolled_rows: context.scrolled_rows,
+            grid_generation: context.grid_generation,
+            scanner: context.scanner.iter().map(|&Point(x, y)| (x, y)).collect(),
+            cells: rows(&|x, y| if context.points[x][y] { LIVE } else { DEAD }),
+            cases: rows(&|x, y| match context.cases[x][y] {
+                Some(case) => char::from(b'0' + case),
+                None => DEAD,
+            }),
+            heat: match heated {
+                true => (0..GRID_Y_SIZE as usize)
+                    .map(|y| context.heat.iter().map(|column| column[y]).collect())
+                    .collect(),
+                false => Vec::new(),
+            },
+        }
+    }
+
+    fn restore(&self) -> Result<SimContext, Error> {
+        let mut context = SimContext::new();
+        context.automaton = self.automaton.parse()?;
+        context.rule = self.rule;
+        context.seed = self.seed.parse::<Seed>()?;
+        context.state = match self.playing {
+            true => SimulationState::Playing,
+            false => SimulationState::Paused,
+        };
+        context.step_mode = match self.step_mode.as_str() {
+            "cell" => StepMode::Cell,
+            "row" => StepMode::Row,
+            other => return Err(Error::Parse(format!("unknown step mode '{}'", other))),
+        };
+        context.cells_per_tick = self.cells_per_tick.clamp(1, GRID_X_SIZE);
+        context.tick_rate = self.tick_rate.parse()?;
+        context.boundary = match self.boundary.as_deref() {
+            None | Some("helix") => Boundary::Helix,
+            Some("dead") => Boundary::Dead,
+            Some("wrap") => Boundary::Wrap,
+            Some(other) => return Err(Error::Parse(format!("unknown boundary '{}'", other))),
+        };
+        context.endless = self.endless;
+        context.scrolled_rows = self.scrolled_rows;
+        context.grid_generation = self.grid_generation;
+
+        let in_grid = |&(x, y): &(i32, i32)| {
+            (0..GRID_X_SIZE as i32).contains(&x) && (0..GRID_Y_SIZE as i32).contains(&y)
+        };
+        if self.scanner.len() != context.scanner.len() || !self.scanner.iter().all(in_grid) {
+            return Err(Error::OutOfBounds(
+                "the scanner is not on the grid".to_string(),
+            ));
+        }
+        context.scanner = self.scanner.iter().map(|&(x, y)| Point(x, y)).collect();
+        if !follows_the_rows(&context.scanner) {
+            return Err(Error::Parse(format!(
+                "the scanner {:?} does not cover consecutive cells",
+                self.scanner
+            )));
+        }
+
+        for (y, row) in grid_rows(&self.cells)?.into_iter().enumerate() {
+            for (x, &cell) in row.iter().enumerate() {
+                context.points[x][y] = cell == LIVE as u8;
+            }
+        }
+        for (y, row) in grid_rows(&self.cases)?.into_iter().enumerate() {
+            for (x, &case) in row.iter().enumerate() {
+                context.cases[x][y] = (b'0'..=b'7').contains(&case).then(|| case - b'0');
+            }
+        }
+        for (y, row) in self.heat.iter().take(GRID_Y_SIZE as usize).enumerate() {
+            for (x, &heat) in row.iter().take(GRID_X_SIZE as usize).enumerate() {
+                context.heat[x][y] = heat;
+            }
+        }
+        Ok(context)
+    }
+}
+
+/// Whether `scanner` runs back from its head over consecutive cells, from
+/// the start of a row on to the end of the row above, or is where a fresh
+/// automaton starts it and leaves it after the first cell.
+fn follows_the_rows(scanner: &[Point]) -> bool {
+    let mut fresh = SimContext::new();
+    let same = |other: &[Point]| {
+        other
+            .iter()
+            .zip(scanner)
+            .all(|(a, b)| (a.0, a.1) == (b.0, b.1))
+    };
+    if same(&fresh.scanner) {
+        return true;
+    }
+    fresh.move_scanner();
+    if same(&fresh.scanner) {
+        return true;
+    }
+    let index = |&Point(x, y): &Point| y as i64 * GRID_X_SIZE as i64 + x as i64;
+    scanner
+        .windows(2)
+        .all(|pair| index(&pair[0]) == index(&pair[1]) + 1)
+}
+
+/// The characters of rows that cover the grid exactly.
+fn grid_rows(rows: &[String]) -> Result<Vec<&[u8]>, Error> {
+    let fits = rows.len() == GRID_Y_SIZE as usize
+        && rows.iter().all(|row| row.len() == GRID_X_SIZE as usize);
+    match fits {
+        true => Ok(rows.iter().map(|row| row.as_bytes()).collect()),
+        false => Err(Error::OutOfBounds(format!(
+            "expected {}x{} cells",
+            GRID_X_SIZE, GRID_Y_SIZE
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A file of its own in the temporary directory, for tests that run at
+    /// the same time.
+    fn scratch(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("rule30-state-{}-{}.toml", std::process::id(), name))
+    }
+
+    fn assert_same(a: &SimContext, b: &SimContext) {
+        assert_eq!(a.points, b.points);
+        assert_eq!(a.cases, b.cases);
+        let points = |context: &SimContext| -> Vec<(i32, i32)> {
+            context.scanner.iter().map(|&Point(x, y)| (x, y)).collect()
+        };
+        assert_eq!(points(a), points(b));
+        assert_eq!((a.rule, a.seed, a.endless), (b.rule, b.seed, b.endless));
+        assert_eq!(a.generation(), b.generation());
+    }
+
+    #[test]
+    fn round_trips_automata() {
+        let mut midway = SimContext::seeded(110, Seed::Random(7));
+        for _ in 0..1234 {
+            midway.step();
+        }
+        // Far enough to have scrolled, with the scanner waiting at the
+        // start of a row.
+        let mut scrolled = SimContext::new();
+        scrolled.rows().take(150).for_each(drop);
+        let contexts = vec![SimContext::new(), midway, scrolled];
+
+        let path = scratch("round-trip");
+        save(&path, &contexts).unwrap();
+        let loaded = load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.len(), contexts.len());
+        for (a, b) in contexts.iter().zip(&loaded) {
+            assert_same(a, b);
+        }
+    }
+
+    #[test]
+    fn rejects_corrupt_files() {
+        let path = scratch("corrupt");
+        save(&path, &[SimContext::new()]).unwrap();
+        let saved: toml::Table = toml::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        let corrupt = |key: &str, value: toml::Value| {
+            let mut file = saved.clone();
+            let automata = file["automata"].as_array_mut().unwrap();
+            automata[0]
+                .as_table_mut()
+                .unwrap()
+                .insert(key.into(), value);
+            fs::write(&path, toml::to_string(&file).unwrap()).unwrap();
+            load(&path)
+        };
+        let scanner = |cells: [(i64, i64); 3]| {
+            let cells = cells.iter().map(|&(x, y)| toml::Value::from(vec![x, y]));
+            toml::Value::Array(cells.collect())
+        };
+        let results = [
+            // Off the grid, not next to each other and the wrong way round.
+            corrupt("scanner", scanner([(2, 200), (1, 200), (0, 200)])),
+            corrupt("scanner", scanner([(5, 3), (4, 3), (2, 3)])),
+            corrupt("scanner", scanner([(3, 3), (4, 3), (5, 3)])),
+            corrupt("cells", toml::Value::Array(Vec::new())),
+            corrupt("step_mode", "sideways".into()),
+        ];
+        fs::write(&path, "automata = [[[").unwrap();
+        let garbage = load(&path);
+        fs::remove_file(&path).unwrap();
+        for result in results.into_iter().chain([garbage]) {
+            assert!(matches!(result, Err(Error::Parse(_))));
+        }
+    }
+
+    #[test]
+    fn accepts_consecutive_scanners() {
+        let point = |(x, y)| Point(x, y);
+        let last = GRID_X_SIZE as i32 - 1;
+        assert!(follows_the_rows(&[(6, 3), (5, 3), (4, 3)].map(point)));
+        assert!(follows_the_rows(
+            &[(0, 4), (last, 3), (last - 1, 3)].map(point)
+        ));
+        assert!(!follows_the_rows(
+            &[(0, 3), (last, 3), (last - 1, 3)].map(point)
+        ));
+    }
+
+    #[test]
+    fn does_not_resume_stale_sessions() {
+        let path = scratch("stale");
+        save(&path, &[SimContext::new()]).unwrap();
+        let age = |days: u64| {
+            let modified = SystemTime::now() - Duration::from_secs(days * 24 * 60 * 60);
+            let file = fs::File::options().write(true).open(&path).unwrap();
+            file.set_modified(modified).unwrap();
+            resume_from(&path)
+        };
+        let recent = age(29);
+        let stale = age(31);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(recent.unwrap().len(), 1);
+        assert!(matches!(stale, Err(Error::Config(_))));
+    }
+}
@@ -0,0 +1,109 @@
+use crate::pattern::Pattern;
+use crate::row_stream::StreamFormat;
+use crate::{SimContext, SimulationState, GRID_X_SIZE};
+use log::error;
+use std::io::{self, BufRead, Read};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+/// How many rows may wait to be shown before reading stdin holds off,
+/// so a fast producer cannot pile up rows without end.
+const QUEUED_ROWS: usize = 1024;
+
+/// Shows rows piped in on stdin instead of computing them, as a viewer
+/// for automata run elsewhere. They are read from a thread of their own.
+pub struct StdinRows {
+    rows: Receiver<Vec<bool>>,
+    /// Whether the first row, which takes the place of the seed, came in.
+    started: bool,
+}
+
+impl StdinRows {
+    /// Starts reading rows in `format`, `width` cells to a row for packed
+    /// ones, and clears the seed of `context` to show them in. It plays
+    /// right away, the tick rate setting how many rows show every frame.
+    pub fn spawn(format: StreamFormat, width: usize, context: &mut SimContext) -> StdinRows {
+        let (sender, rows) = mpsc::sync_channel(QUEUED_ROWS);
+        thread::spawn(move || {
+            let stdin = io::stdin().lock();
+            let result = match format {
+                StreamFormat::Text => stdin.lines().try_for_each(|line| {
+                    let row = line?.trim_end().chars().map(|c| c == '1').collect();
+                    sender.send(row).map_err(io::Error::other)
+                }),
+                StreamFormat::Bits => read_packed(stdin, width, |row| {
+                    sender.send(row).map_err(io::Error::other)
+                }),
+            };
+            if let Err(e) = result {
+                error!("could not read the rows from stdin: {}", e);
+            }
+        });
+        context.place(&Pattern {
+            rows: vec![vec![false; GRID_X_SIZE as usize]],
+        });
+        context.endless = true;
+        context.state = SimulationState::Playing;
+        StdinRows {
+            rows,
+            started: false,
+        }
+    }
+
+    /// Shows up to `limit` of the rows that came in below each other,
+    /// centered and cut to the width of the grid. `false` once stdin is
+    /// closed and every row has been shown.
+    pub fn show(&mut self, context: &mut SimContext, limit: u32) -> bool {
+        for _ in 0..limit {
+            match self.rows.try_recv() {
+                Ok(row) => {
+                    let row = fitted(&row);
+                    if self.started {
+                        context.push_row(&row);
+                    } else {
+                        context.place(&Pattern { rows: vec![row] });
+                        self.started = true;
+                    }
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => return false,
+            }
+        }
+        true
+    }
+}
+
+/// Reads rows of `width` cells packed eight to a byte until stdin ends,
+/// every row starting on a new byte with its first cell in the highest bit.
+fn read_packed(
+    mut input: impl Read,
+    width: usize,
+    mut row: impl FnMut(Vec<bool>) -> io::Result<()>,
+) -> io::Result<()> {
+    let mut bytes = vec![0; width.div_ceil(8)];
+    loop {
+        match input.read_exact(&mut bytes) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(()),
+            Err(e) => return Err(e),
+        }
+        row((0..width)
+            .map(|x| bytes[x / 8] >> (7 - x % 8) & 1 == 1)
+            .collect())?;
+    }
+}
+
+/// `row` centered on a row of the grid's width, with its ends cut off when
+/// it is wider.
+fn fitted(row: &[bool]) -> Vec<bool> {
+    let width = GRID_X_SIZE as usize;
+    let mut fitted = vec![false; width];
+    if row.len() <= width {
+        let left = (width - row.len()) / 2;
+        fitted[left..left + row.len()].copy_from_slice(row);
+    } else {
+        let left = (row.len() - width) / 2;
+        fitted.copy_from_slice(&row[left..left + width]);
+    }
+    fitted
+}
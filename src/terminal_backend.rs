@@ -0,0 +1,236 @@
+use crate::backend::Backend;
+use crate::color::Color;
+use crate::command::{Command, Key, KeyMap};
+use crate::options::Options;
+use crate::{Automaton, Error, Point, SimContext, GRID_X_SIZE, GRID_Y_SIZE};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{self, Print, SetBackgroundColor, SetForegroundColor};
+use crossterm::{cursor, execute, queue, terminal};
+use std::io::{self, Stdout, Write};
+use std::time::Duration;
+
+/// Plays the automata in the terminal for [`crate::backend::run`], two
+/// rows of cells to every line of half blocks, and takes the same keys as
+/// the window, applying them to every automaton.
+pub struct TerminalBackend {
+    stdout: Stdout,
+    contexts: Vec<SimContext>,
+    keys: KeyMap,
+    escape: Command,
+    live: Color,
+    dead: Color,
+    /// The color of the scanner, `None` while it is hidden.
+    scanner: Option<Color>,
+    /// The lines drawn last, to only rewrite those that changed.
+    lines: Vec<Vec<u8>>,
+    shown: Vec<Vec<u8>>,
+}
+
+impl TerminalBackend {
+    /// Takes over the terminal to show `contexts` side by side.
+    pub fn new(options: &Options, contexts: Vec<SimContext>) -> Result<TerminalBackend, Error> {
+        let mut stdout = io::stdout();
+        terminal::enable_raw_mode().map_err(terminal_error)?;
+        execute!(stdout, terminal::EnterAlternateScreen, cursor::Hide).map_err(terminal_error)?;
+        let theme = &options.theme;
+        Ok(TerminalBackend {
+            stdout,
+            contexts,
+            keys: options.keys.clone(),
+            escape: options.escape,
+            live: theme.live_cell,
+            dead: theme.dead_cell,
+            scanner: match options.hide_scanner {
+                true => None,
+                false => Some(options.scanner_color.unwrap_or(theme.scanner)),
+            },
+            lines: Vec::new(),
+            shown: Vec::new(),
+        })
+    }
+
+    /// Draws the cells, without showing them yet.
+    fn draw_cells(&mut self) -> Result<(), Error> {
+        let (columns, lines) = terminal::size().map_err(terminal_error)?;
+        // The last line is the status line.
+        let lines = lines.saturating_sub(1) as usize;
+        let rows = (lines * 2).min(GRID_Y_SIZE as usize);
+        let mut drawn = vec![Vec::new(); lines + 1];
+        for (index, context) in self.contexts.iter().enumerate() {
+            let left = index * (GRID_X_SIZE as usize + 1);
+            let width = (GRID_X_SIZE as usize).min((columns as usize).saturating_sub(left));
+            if width == 0 {
+                break;
+            }
+            let top = first_row(context, rows);
+            for (line, bytes) in drawn.iter_mut().take(rows.div_ceil(2)).enumerate() {
+                let mut colors = None;
+                queue!(bytes, cursor::MoveTo(left as u16, line as u16)).map_err(terminal_error)?;
+                for x in 0..width {
+                    let y = top + 2 * line;
+                    let upper = self.color(context, x, y);
+                    let lower = match y + 1 < top + rows {
+                        true => self.color(context, x, y + 1),
+                        false => self.dead,
+                    };
+                    if colors != Some((upper, lower)) {
+                        queue!(
+                            bytes,
+                            SetForegroundColor(terminal_color(upper)),
+                            SetBackgroundColor(terminal_color(lower))
+                        )
+                        .map_err(terminal_error)?;
+                        colors = Some((upper, lower));
+                    }
+                    queue!(bytes, Print('▀')).map_err(terminal_error)?;
+                }
+                queue!(bytes, style::ResetColor).map_err(terminal_error)?;
+            }
+            let title: String = context.title().chars().take(width).collect();
+            queue!(
+                drawn[lines],
+                cursor::MoveTo(left as u16, lines as u16),
+                Print(format!("{:<width$}", title, width = width))
+            )
+            .map_err(terminal_error)?;
+        }
+        self.lines = drawn;
+        Ok(())
+    }
+
+    /// Shows what was drawn last.
+    fn present(&mut self) -> Result<(), Error> {
+        if self.shown.len() != self.lines.len() {
+            // The terminal was resized, and what was drawn is out of place.
+            queue!(self.stdout, terminal::Clear(terminal::ClearType::All))
+                .map_err(terminal_error)?;
+            self.shown.clear();
+        }
+        for (index, line) in self.lines.iter().enumerate() {
+            if self.shown.get(index) != Some(line) {
+                self.stdout.write_all(line).map_err(terminal_error)?;
+            }
+        }
+        self.shown = std::mem::take(&mut self.lines);
+        self.stdout.flush().map_err(terminal_error)
+    }
+
+    /// What the key gives, by the key bindings of the window. Ctrl+C
+    /// quits, as it does everywhere else in a terminal.
+    fn command(&self, key: KeyEvent) -> Option<Command> {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        if ctrl && key.code == KeyCode::Char('c') {
+            return Some(Command::Quit);
+        }
+        if key.code == KeyCode::Esc {
+            return Some(self.escape);
+        }
+        self.keys.command(key_of(key.code)?, ctrl)
+    }
+
+    fn color(&self, context: &SimContext, x: usize, y: usize) -> Color {
+        let scanned = context.automaton == Automaton::Elementary
+            && context
+                .scanner
+                .iter()
+                .any(|&Point(px, py)| (px, py) == (x as i32, y as i32));
+        match self.scanner {
+            Some(scanner) if scanned => scanner,
+            _ if context.points[x][y] => self.live,
+            _ => self.dead,
+        }
+    }
+}
+
+impl Backend for TerminalBackend {
+    fn handle_input(&mut self, _frame: u64) -> Result<bool, Error> {
+        while event::poll(Duration::ZERO).unwrap_or(false) {
+            let key = match event::read() {
+                Ok(Event::Key(key)) if key.kind != KeyEventKind::Release => key,
+                Ok(_) => continue,
+                Err(_) => return Ok(false),
+            };
+            match self.command(key) {
+                Some(Command::Quit) => return Ok(false),
+                Some(command) => {
+                    for context in &mut self.contexts {
+                        command.apply_to(context);
+                    }
+                }
+                None => {}
+            }
+        }
+        Ok(true)
+    }
+
+    fn update(&mut self, frame: u64) -> Result<bool, Error> {
+        for context in &mut self.contexts {
+            context.play_frame(frame);
+        }
+        self.draw_cells()?;
+        self.present()?;
+        Ok(true)
+    }
+}
+
+/// Leaves the terminal the way it was found, also when quitting on an
+/// error.
+impl Drop for TerminalBackend {
+    fn drop(&mut self) {
+        let _ = execute!(
+            self.stdout,
+            style::ResetColor,
+            cursor::Show,
+            terminal::LeaveAlternateScreen
+        );
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// The first of `rows` rows to show of the grid, following the scanner
+/// down when not all of them fit.
+fn first_row(context: &SimContext, rows: usize) -> usize {
+    let last = match (context.automaton, context.scanner.first()) {
+        (Automaton::Elementary, Some(&Point(_, y))) => y.max(0) as usize,
+        _ => 0,
+    };
+    (last + 1)
+        .saturating_sub(rows)
+        .min(GRID_Y_SIZE as usize - rows)
+}
+
+/// The key of the window a terminal key stands for.
+fn key_of(code: KeyCode) -> Option<Key> {
+    let name = match code {
+        KeyCode::Char(' ') => "Space".to_string(),
+        KeyCode::Char(c) => c.to_string(),
+        KeyCode::Left => "Left".to_string(),
+        KeyCode::Right => "Right".to_string(),
+        KeyCode::Up => "Up".to_string(),
+        KeyCode::Down => "Down".to_string(),
+        KeyCode::Enter => "Return".to_string(),
+        KeyCode::Backspace => "Backspace".to_string(),
+        KeyCode::Tab => "Tab".to_string(),
+        KeyCode::Home => "Home".to_string(),
+        KeyCode::End => "End".to_string(),
+        KeyCode::PageUp => "PageUp".to_string(),
+        KeyCode::PageDown => "PageDown".to_string(),
+        KeyCode::Insert => "Insert".to_string(),
+        KeyCode::Delete => "Delete".to_string(),
+        KeyCode::F(n) => format!("F{}", n),
+        _ => return None,
+    };
+    Key::from_name(&name)
+}
+
+fn terminal_color(color: Color) -> style::Color {
+    style::Color::Rgb {
+        r: color.r,
+        g: color.g,
+        b: color.b,
+    }
+}
+
+fn terminal_error(e: io::Error) -> Error {
+    Error::io("draw to", "the terminal", e)
+}
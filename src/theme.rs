@@ -0,0 +1,225 @@
+use crate::appearance;
+use crate::color::Color;
+use crate::coloring;
+use crate::image::RgbaImage;
+use crate::Error;
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+/// The colors everything is drawn with.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Theme {
+    pub name: String,
+    /// Fills the window around the grid.
+    pub background: Color,
+    pub live_cell: Color,
+    pub dead_cell: Color,
+    pub scanner: Color,
+    /// Blended over the background and dead cells while paused.
+    pub paused_tint: Color,
+}
+
+/// A theme as written in a TOML file, with `#rrggbb` or `#rrggbbaa` colors.
+#[derive(Deserialize)]
+struct ThemeFile {
+    name: Option<String>,
+    background: String,
+    live_cell: String,
+    dead_cell: Option<String>,
+    scanner: String,
+    paused_tint: String,
+}
+
+impl Theme {
+    pub fn built_in() -> Vec<Theme> {
+        vec![
+            Theme {
+                name: "classic".to_string(),
+                background: Color::RGB(0, 0, 0),
+                live_cell: Color::WHITE,
+                dead_cell: Color::RGB(0, 0, 0),
+                scanner: Color::YELLOW,
+                paused_tint: Color::RGBA(255, 255, 255, 30),
+            },
+            Theme {
+                name: "paper".to_string(),
+                background: Color::RGB(220, 218, 210),
+                live_cell: Color::RGB(20, 20, 20),
+                dead_cell: Color::RGB(245, 243, 235),
+                scanner: Color::RGB(200, 40, 40),
+                paused_tint: Color::RGBA(0, 0, 0, 25),
+            },
+            // The colorblind themes stay clear of red against green and
+            // keep the scanner apart from the cells in hue and brightness,
+            // using colors from the Okabe-Ito palette.
+            Theme {
+                name: "colorblind".to_string(),
+                background: Color::RGB(0, 0, 0),
+                live_cell: Color::RGB(240, 240, 240),
+                dead_cell: Color::RGB(0, 0, 0),
+                scanner: Color::RGB(0, 114, 178),
+                paused_tint: Color::RGBA(255, 255, 255, 30),
+            },
+            Theme {
+                name: "colorblind-light".to_string(),
+                background: Color::RGB(220, 220, 220),
+                live_cell: Color::RGB(0, 0, 0),
+                dead_cell: Color::RGB(255, 255, 255),
+                scanner: Color::RGB(230, 159, 0),
+                paused_tint: Color::RGBA(0, 0, 0, 25),
+            },
+            Theme {
+                name: "amber".to_string(),
+                background: Color::RGB(10, 6, 0),
+                live_cell: Color::RGB(255, 176, 0),
+                dead_cell: Color::RGB(20, 12, 0),
+                scanner: Color::RGB(255, 240, 200),
+                paused_tint: Color::RGBA(255, 176, 0, 20),
+            },
+            Theme {
+                name: "matrix".to_string(),
+                background: Color::RGB(0, 0, 0),
+                live_cell: Color::RGB(0, 230, 64),
+                dead_cell: Color::RGB(0, 16, 4),
+                scanner: Color::RGB(200, 255, 200),
+                paused_tint: Color::RGBA(0, 255, 64, 20),
+            },
+            Theme {
+                name: "ocean".to_string(),
+                background: Color::RGB(4, 18, 38),
+                live_cell: Color::RGB(120, 210, 255),
+                dead_cell: Color::RGB(8, 30, 60),
+                scanner: Color::RGB(255, 120, 80),
+                paused_tint: Color::RGBA(255, 255, 255, 20),
+            },
+        ]
+    }
+
+    /// Whether the theme is meant for a dark desktop, judged by the
+    /// perceived brightness of its background.
+    pub fn is_dark(&self) -> bool {
+        let Color { r, g, b, .. } = self.background;
+        let brightness = 0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32;
+        brightness < 128.0
+    }
+
+    /// The built-in theme matching the system's dark or light preference.
+    pub fn system_default() -> Theme {
+        let name = match appearance::prefers_dark() {
+            Some(false) => "paper",
+            _ => "classic",
+        };
+        Self::find(name).expect("built-in theme")
+    }
+
+    /// The same theme with every color inverted, for example black on white
+    /// instead of white on black.
+    pub fn inverted(&self) -> Theme {
+        let invert =
+            |color: Color| Color::RGBA(255 - color.r, 255 - color.g, 255 - color.b, color.a);
+        Theme {
+            name: self.name.clone(),
+            background: invert(self.background),
+            live_cell: invert(self.live_cell),
+            dead_cell: invert(self.dead_cell),
+            scanner: invert(self.scanner),
+            paused_tint: invert(self.paused_tint),
+        }
+    }
+
+    /// Looks up a built-in theme by name, or otherwise loads a theme file.
+    pub fn is_built_in(name: &str) -> bool {
+        Self::built_in().iter().any(|theme| theme.name == name)
+    }
+
+    pub fn find(name_or_path: &str) -> Result<Theme, Error> {
+        match Self::built_in()
+            .into_iter()
+            .find(|theme| theme.name == name_or_path)
+        {
+            Some(theme) => Ok(theme),
+            None => Self::load(Path::new(name_or_path)),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Theme, Error> {
+        let contents = fs::read_to_string(path).map_err(|e| Error::io("read theme", path, e))?;
+        let file: ThemeFile = toml::from_str(&contents)
+            .map_err(|e| Error::Parse(format!("invalid theme '{}': {}", path.display(), e)))?;
+
+        let background = parse_color(&file.background)?;
+        Ok(Theme {
+            name: file.name.unwrap_or_else(|| path.display().to_string()),
+            background,
+            live_cell: parse_color(&file.live_cell)?,
+            dead_cell: match file.dead_cell {
+                Some(color) => parse_color(&color)?,
+                None => background,
+            },
+            scanner: parse_color(&file.scanner)?,
+            paused_tint: parse_color(&file.paused_tint)?,
+        })
+    }
+}
+
+/// How many of an image's most common colors are considered for a theme.
+const IMAGE_THEME_CANDIDATES: usize = 8;
+
+impl Theme {
+    /// Builds a theme from the dominant colors of a PNG image: the most
+    /// common one becomes the background, the one standing out most from
+    /// it the live cells and the one standing out from both the scanner.
+    pub fn from_image(path: &Path) -> Result<Theme, Error> {
+        let colors = RgbaImage::load_png(path)?.dominant_colors(IMAGE_THEME_CANDIDATES);
+        let Some(&background) = colors.first() else {
+            return Err(Error::Parse(format!(
+                "'{}' has no opaque pixels",
+                path.display()
+            )));
+        };
+
+        let distance = |a: (u8, u8, u8), b: (u8, u8, u8)| {
+            let d = |x: u8, y: u8| (x as i32 - y as i32).pow(2);
+            d(a.0, b.0) + d(a.1, b.1) + d(a.2, b.2)
+        };
+        let most_distinct = |from: &dyn Fn((u8, u8, u8)) -> i32| {
+            colors.iter().copied().max_by_key(|&color| from(color))
+        };
+        let live = most_distinct(&|color| distance(color, background)).unwrap_or(background);
+        let scanner =
+            most_distinct(&|color| distance(color, background).min(distance(color, live)))
+                .unwrap_or(live);
+
+        let color = |(r, g, b): (u8, u8, u8)| Color::RGB(r, g, b);
+        Ok(Theme {
+            name: path.display().to_string(),
+            background: color(background),
+            live_cell: color(live),
+            dead_cell: coloring::lerp(color(background), color(live), 0.08),
+            scanner: color(scanner),
+            paused_tint: Color::RGBA(live.0, live.1, live.2, 30),
+        })
+    }
+}
+
+/// Parses `#rrggbb` or `#rrggbbaa`.
+pub fn parse_color(s: &str) -> Result<Color, Error> {
+    let invalid = || {
+        Error::Parse(format!(
+            "invalid color '{}', expected #rrggbb or #rrggbbaa",
+            s
+        ))
+    };
+    let hex = s.strip_prefix('#').ok_or_else(invalid)?;
+    if !matches!(hex.len(), 6 | 8) || !hex.is_ascii() {
+        return Err(invalid());
+    }
+
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| invalid());
+    let alpha = match hex.len() {
+        8 => channel(6)?,
+        _ => 255,
+    };
+    Ok(Color::RGBA(channel(0)?, channel(2)?, channel(4)?, alpha))
+}
@@ -0,0 +1,96 @@
+//! How fast automata play.
+
+use crate::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// The most ticks on a frame, and the most frames for a tick.
+pub const MAX_TICK_RATE: u32 = 60;
+
+/// How often the simulation ticks relative to the rendered frames, from
+/// one tick every `MAX_TICK_RATE` frames up to `MAX_TICK_RATE` ticks on
+/// every frame.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TickRate {
+    /// One tick every so many frames.
+    FramesPerTick(u32),
+    /// So many ticks on every frame.
+    TicksPerFrame(u32),
+}
+
+impl TickRate {
+    /// The next faster rate, up to `MAX_TICK_RATE` ticks a frame.
+    pub fn faster(self) -> TickRate {
+        match self {
+            TickRate::FramesPerTick(frames) if frames <= 2 => TickRate::TicksPerFrame(1),
+            TickRate::FramesPerTick(frames) => TickRate::FramesPerTick(frames - 1),
+            TickRate::TicksPerFrame(ticks) => {
+                TickRate::TicksPerFrame((ticks + 1).min(MAX_TICK_RATE))
+            }
+        }
+    }
+
+    /// The next slower rate, down to a tick every `MAX_TICK_RATE` frames.
+    pub fn slower(self) -> TickRate {
+        match self {
+            TickRate::TicksPerFrame(ticks) if ticks <= 1 => TickRate::FramesPerTick(2),
+            TickRate::TicksPerFrame(ticks) => TickRate::TicksPerFrame(ticks - 1),
+            TickRate::FramesPerTick(frames) => {
+                TickRate::FramesPerTick((frames + 1).min(MAX_TICK_RATE))
+            }
+        }
+    }
+
+    /// The number of ticks to run on the given frame.
+    pub fn ticks_on_frame(&self, frame: u64) -> u32 {
+        match *self {
+            TickRate::FramesPerTick(frames) => frame.is_multiple_of(frames as u64) as u32,
+            TickRate::TicksPerFrame(ticks) => ticks,
+        }
+    }
+}
+
+impl Default for TickRate {
+    fn default() -> Self {
+        TickRate::TicksPerFrame(1)
+    }
+}
+
+/// Parses `K` as K ticks every frame and `1/K` as one tick every K frames.
+impl FromStr for TickRate {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = |value: &str| -> Result<u32, Error> {
+            match value.trim().parse::<u32>() {
+                Ok(value @ 1..=MAX_TICK_RATE) => Ok(value),
+                _ => Err(Error::Parse(format!(
+                    "invalid tick rate '{}', expected K or 1/K with K between 1 and {}",
+                    s, MAX_TICK_RATE
+                ))),
+            }
+        };
+
+        match s.split_once('/') {
+            Some(("1", frames)) => match parse(frames)? {
+                1 => Ok(TickRate::TicksPerFrame(1)),
+                frames => Ok(TickRate::FramesPerTick(frames)),
+            },
+            Some(_) => Err(Error::Parse(format!(
+                "invalid tick rate '{}', the numerator must be 1",
+                s
+            ))),
+            None => Ok(TickRate::TicksPerFrame(parse(s)?)),
+        }
+    }
+}
+
+/// Formats the rate the way it is parsed.
+impl fmt::Display for TickRate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TickRate::FramesPerTick(frames) => write!(f, "1/{}", frames),
+            TickRate::TicksPerFrame(ticks) => write!(f, "{}", ticks),
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use crate::color::Color;
+use crate::screenshot;
+use crate::Error;
+use log::debug;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const INDEX_FILE: &str = "index.json";
+const INDEX_VERSION: u32 = 1;
+
+/// Saves a diagram too large for a single picture as PNG tiles with an
+/// index, row by row as they come in. Only the band of rows the tiles
+/// under construction cover is ever kept.
+pub struct TiledExport {
+    dir: PathBuf,
+    scale: u32,
+    colors: (Color, Color),
+    /// How many cells wide and high a tile is, the tiles at the right and
+    /// bottom edge may be smaller.
+    tile_cells: usize,
+    band: Vec<Vec<bool>>,
+    index: TileIndex,
+}
+
+/// The index the tiles are saved with, in the schema the README lays out.
+#[derive(Serialize)]
+struct TileIndex {
+    version: u32,
+    rule: u8,
+    scale: u32,
+    tile_size: u32,
+    width: u64,
+    height: u64,
+    /// The file names of the tiles, one array per row of tiles.
+    tiles: Vec<Vec<String>>,
+}
+
+impl TiledExport {
+    /// Starts saving tiles of at most `tile_size` pixels on a side to
+    /// `dir`, `scale` pixels to a cell.
+    pub fn create(
+        dir: &Path,
+        rule: u8,
+        scale: u32,
+        tile_size: u32,
+        colors: (Color, Color),
+    ) -> Result<TiledExport, Error> {
+        fs::create_dir_all(dir).map_err(|e| Error::io("create", dir, e))?;
+        let tile_cells = (tile_size / scale).max(1) as usize;
+        Ok(TiledExport {
+            dir: dir.to_path_buf(),
+            scale,
+            colors,
+            tile_cells,
+            band: Vec::with_capacity(tile_cells),
+            index: TileIndex {
+                version: INDEX_VERSION,
+                rule,
+                scale,
+                tile_size: tile_cells as u32 * scale,
+                width: 0,
+                height: 0,
+                tiles: Vec::new(),
+            },
+        })
+    }
+
+    pub fn push_row(&mut self, row: Vec<bool>) -> Result<(), Error> {
+        self.band.push(row);
+        if self.band.len() == self.tile_cells {
+            self.save_band()?;
+        }
+        Ok(())
+    }
+
+    /// Saves the tiles of the rows pushed since the last full band and
+    /// the index.
+    pub fn finish(mut self) -> Result<PathBuf, Error> {
+        if !self.band.is_empty() {
+            self.save_band()?;
+        }
+        let path = self.dir.join(INDEX_FILE);
+        let index = serde_json::to_string_pretty(&self.index)
+            .map_err(|e| Error::Serialize(e.to_string()))?;
+        fs::write(&path, index).map_err(|e| Error::io("write", &path, e))?;
+        Ok(path)
+    }
+
+    fn save_band(&mut self) -> Result<(), Error> {
+        let (live, dead) = self.colors;
+        let width = self.band[0].len();
+        let row_of_tiles = self.index.tiles.len();
+        let mut names = Vec::new();
+        for (column, left) in (0..width).step_by(self.tile_cells).enumerate() {
+            let right = (left + self.tile_cells).min(width);
+            let cells: Vec<Vec<bool>> = self
+                .band
+                .iter()
+                .map(|row| row[left..right].to_vec())
+                .collect();
+            let name = format!("tile-{}-{}.png", row_of_tiles, column);
+            screenshot::diagram(&cells, self.scale, live, dead).save_png(&self.dir.join(&name))?;
+            debug!("Saved {}", name);
+            names.push(name);
+        }
+        self.index.width = width as u64 * self.scale as u64;
+        self.index.height += self.band.len() as u64 * self.scale as u64;
+        self.index.tiles.push(names);
+        self.band.clear();
+        Ok(())
+    }
+}
@@ -0,0 +1,61 @@
+use crate::{Cells, GRID_X_SIZE, SEED_ROW};
+
+/// A triangle of dead cells as elementary automata like Rule 30 leave
+/// them: a run of dead cells that shrinks by one cell on each side with
+/// every row below it.
+pub struct Triangle {
+    /// The leftmost cell of the top row.
+    pub x: usize,
+    pub y: usize,
+    /// The length of the top row, in cells.
+    pub width: usize,
+    /// The number of rows, fewer than a complete triangle has when it
+    /// runs into the last computed row.
+    pub height: usize,
+}
+
+/// Finds the triangles at least `min_width` cells wide in the first
+/// `rows` rows. Runs touching the edge of the grid are left out, they are
+/// the empty space around the pattern rather than a part of it.
+pub fn find(cells: &Cells, rows: usize, min_width: usize) -> Vec<Triangle> {
+    let dead = |x: usize, y: usize| !cells[x][y];
+    let all_dead = |left: usize, right: usize, y: usize| (left..=right).all(|x| dead(x, y));
+    let width = GRID_X_SIZE as usize;
+
+    let mut triangles = Vec::new();
+    for y in SEED_ROW..rows {
+        let mut x = 0;
+        while x < width {
+            if !dead(x, y) {
+                x += 1;
+                continue;
+            }
+            let left = x;
+            while x < width && dead(x, y) {
+                x += 1;
+            }
+            let right = x - 1;
+
+            if left == 0 || right + 1 == width || right + 1 - left < min_width {
+                continue;
+            }
+            // A run that the row above extends on both sides is the lower
+            // part of a larger triangle.
+            if y > SEED_ROW && all_dead(left - 1, right + 1, y - 1) {
+                continue;
+            }
+            let height = (0..)
+                .take_while(|&k| {
+                    2 * k <= right - left && y + k < rows && all_dead(left + k, right - k, y + k)
+                })
+                .count();
+            triangles.push(Triangle {
+                x: left,
+                y,
+                width: right + 1 - left,
+                height,
+            });
+        }
+    }
+    triangles
+}
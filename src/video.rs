@@ -0,0 +1,71 @@
+use crate::Error;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, Command, Stdio};
+
+/// Streams raw RGB frames to an `ffmpeg` process, which encodes them into
+/// whatever format the file name asks for.
+pub struct VideoRecorder {
+    child: Child,
+    stdin: ChildStdin,
+    path: PathBuf,
+    pub size: (u32, u32),
+}
+
+impl VideoRecorder {
+    pub fn start(path: &Path, size: (u32, u32), fps: u32) -> Result<VideoRecorder, Error> {
+        let mut child = Command::new("ffmpeg")
+            .args(["-loglevel", "error", "-y", "-f", "rawvideo"])
+            .args(["-pixel_format", "rgb24"])
+            .args(["-video_size", &format!("{}x{}", size.0, size.1)])
+            .args(["-framerate", &fps.to_string(), "-i", "-"])
+            // Most players only take 4:2:0, which needs an even size.
+            .args([
+                "-vf",
+                "pad=ceil(iw/2)*2:ceil(ih/2)*2",
+                "-pix_fmt",
+                "yuv420p",
+            ])
+            .arg(path)
+            .stdin(Stdio::piped())
+            .spawn()
+            .map_err(|e| Error::io("start ffmpeg for", path, e))?;
+        let stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| Error::io("write", path, io::ErrorKind::BrokenPipe.into()))?;
+        Ok(VideoRecorder {
+            child,
+            stdin,
+            path: path.to_path_buf(),
+            size,
+        })
+    }
+
+    /// Adds a frame of `size`, three bytes per pixel, row by row.
+    pub fn write_frame(&mut self, pixels: &[u8]) -> Result<(), Error> {
+        self.stdin
+            .write_all(pixels)
+            .map_err(|e| Error::io("write", &self.path, e))
+    }
+
+    /// Ends the video and waits for ffmpeg to write the rest of it.
+    pub fn finish(self) -> Result<(), Error> {
+        let VideoRecorder {
+            mut child,
+            stdin,
+            path,
+            ..
+        } = self;
+        drop(stdin);
+        match child.wait() {
+            Ok(status) if status.success() => Ok(()),
+            Ok(status) => Err(Error::io(
+                "write",
+                path,
+                io::Error::other(format!("ffmpeg failed with {}", status)),
+            )),
+            Err(e) => Err(Error::io("write", path, e)),
+        }
+    }
+}
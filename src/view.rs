@@ -0,0 +1,40 @@
+use crate::{GRID_X_SIZE, GRID_Y_SIZE};
+use std::f32::consts::TAU;
+
+/// How the automaton is laid out on screen.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum View {
+    /// The grid as it is, one square per cell.
+    Flat,
+    /// For 2D automata, the recent generations stacked on top of each
+    /// other in an isometric projection, the newest one on top.
+    Stacked,
+    /// For elementary automata, columns turned into angles and generations
+    /// into the distance from the middle, so the rows wind into a spiral.
+    Spiral,
+}
+
+impl View {
+    pub fn next(self) -> View {
+        match self {
+            View::Flat => View::Stacked,
+            View::Stacked => View::Spiral,
+            View::Spiral => View::Flat,
+        }
+    }
+}
+
+/// The cell of the spiral at `(dx, dy)` from its middle, where every turn
+/// is `ring` wide and the seed row starts at `inner`. One turn holds a
+/// complete row, which starts at angle zero on the right and runs clockwise.
+pub fn spiral_cell(dx: f32, dy: f32, inner: f32, ring: f32) -> Option<(usize, usize)> {
+    let turn = dy.atan2(dx).rem_euclid(TAU) / TAU;
+    let radius = (dx * dx + dy * dy).sqrt();
+    // The radius grows by a ring over a turn: r = inner + (y + turn) * ring.
+    let y = ((radius - inner) / ring - turn).floor();
+    if y < 0.0 || y >= GRID_Y_SIZE as f32 {
+        return None;
+    }
+    let x = ((turn * GRID_X_SIZE as f32) as usize).min(GRID_X_SIZE as usize - 1);
+    Some((x, y as usize))
+}
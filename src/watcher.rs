@@ -0,0 +1,44 @@
+use std::fs;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// How often the watched file is looked at.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Watches a file for changes from a thread of its own, which stops once
+/// the watcher is dropped.
+pub struct FileWatcher {
+    pub path: PathBuf,
+    changes: Receiver<()>,
+}
+
+impl FileWatcher {
+    pub fn spawn(path: PathBuf) -> FileWatcher {
+        let (sender, changes) = mpsc::channel();
+        let watched = path.clone();
+        thread::spawn(move || {
+            let modified = || fs::metadata(&watched).and_then(|m| m.modified()).ok();
+            let mut last: Option<SystemTime> = modified();
+            loop {
+                thread::sleep(POLL_INTERVAL);
+                let current = modified();
+                // A file that is missing for a moment, as editors that
+                // save by replacing it leave it, is not a change yet.
+                if current.is_some() && current != last {
+                    last = current;
+                    if sender.send(()).is_err() {
+                        break;
+                    }
+                }
+            }
+        });
+        FileWatcher { path, changes }
+    }
+
+    /// Whether the file changed since this was last asked.
+    pub fn changed(&self) -> bool {
+        self.changes.try_iter().count() > 0
+    }
+}
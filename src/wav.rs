@@ -0,0 +1,108 @@
+use crate::Error;
+use std::f32::consts::TAU;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+const SAMPLE_RATE: u32 = 44_100;
+/// The pitch the first column sounds at, the others rise evenly from it.
+const LOWEST_PITCH: f32 = 110.0;
+/// How many octaves the columns span from the first to the last.
+const OCTAVES: f32 = 4.0;
+/// How long every row fades in and out, to keep rows from clicking.
+const FADE: Duration = Duration::from_millis(5);
+
+/// Turns `rows` into sound, one after the other for `row_duration` each.
+/// Every live cell sounds a sine at the pitch of its column, from low on
+/// the left to high on the right. Rows with more live cells come out
+/// louder, though not by as much as there are more of them, and the
+/// loudest moment is as loud as 16 bits go.
+pub fn sonify(rows: &[Vec<bool>], row_duration: Duration) -> Vec<i16> {
+    let width = rows.first().map_or(0, Vec::len);
+    let pitches: Vec<f32> = (0..width)
+        .map(|x| LOWEST_PITCH * 2f32.powf(OCTAVES * x as f32 / width.max(2) as f32 - 1.0))
+        .collect();
+    let row_samples = (row_duration.as_secs_f32() * SAMPLE_RATE as f32) as usize;
+    let fade_samples = ((FADE.as_secs_f32() * SAMPLE_RATE as f32) as usize).min(row_samples / 2);
+    // Carried over from row to row, so a cell alive in both goes on
+    // sounding smoothly.
+    let mut phases = vec![0f32; width];
+    let mut samples = Vec::with_capacity(rows.len() * row_samples);
+    for row in rows {
+        let live: Vec<usize> = (0..width).filter(|&x| row[x]).collect();
+        let volume = 1.0 / (live.len() as f32).sqrt().max(1.0);
+        for i in 0..row_samples {
+            let envelope =
+                (i.min(row_samples - 1 - i) as f32 / fade_samples.max(1) as f32).min(1.0);
+            let mut sample = 0.0;
+            for &x in &live {
+                sample += (phases[x] * TAU).sin();
+            }
+            for (phase, pitch) in phases.iter_mut().zip(&pitches) {
+                *phase = (*phase + pitch / SAMPLE_RATE as f32).fract();
+            }
+            samples.push(sample * volume * envelope);
+        }
+    }
+    let peak = samples
+        .iter()
+        .fold(0f32, |peak, sample| peak.max(sample.abs()));
+    let gain = i16::MAX as f32 / peak.max(1.0);
+    samples
+        .into_iter()
+        .map(|sample| (sample * gain) as i16)
+        .collect()
+}
+
+/// Saves 16 bit mono `samples` as a WAV file.
+pub fn save(path: &Path, samples: &[i16]) -> Result<(), Error> {
+    fs::write(path, to_wav(samples)).map_err(|e| Error::io("write", path, e))
+}
+
+/// Lays out 16 bit mono `samples` as a WAV file, a `RIFF` header with a
+/// `fmt ` and a `data` chunk.
+fn to_wav(samples: &[i16]) -> Vec<u8> {
+    let data_size = samples.len() as u32 * 2;
+    let mut wav = Vec::with_capacity(44 + data_size as usize);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_size).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    // Uncompressed PCM, one channel.
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    wav.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes());
+    wav.extend_from_slice(&2u16.to_le_bytes());
+    wav.extend_from_slice(&16u16.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_size.to_le_bytes());
+    for sample in samples {
+        wav.extend_from_slice(&sample.to_le_bytes());
+    }
+    wav
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunk_sizes_add_up() {
+        let rows = vec![vec![true, false, true], vec![false, true, false]];
+        let samples = sonify(&rows, Duration::from_millis(10));
+        assert_eq!(samples.len(), 2 * 441);
+        let wav = to_wav(&samples);
+        let field = |at: usize| u32::from_le_bytes(wav[at..at + 4].try_into().unwrap());
+        assert_eq!(wav.len(), 44 + 2 * samples.len());
+        assert_eq!(&wav[0..4], b"RIFF");
+        // Everything after the RIFF size.
+        assert_eq!(field(4) as usize, wav.len() - 8);
+        assert_eq!(&wav[8..16], b"WAVEfmt ");
+        assert_eq!(field(16), 16);
+        assert_eq!(field(24), SAMPLE_RATE);
+        assert_eq!(&wav[36..40], b"data");
+        assert_eq!(field(40) as usize, 2 * samples.len());
+        assert_eq!(wav[44..46], samples[0].to_le_bytes());
+    }
+}
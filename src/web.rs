@@ -0,0 +1,109 @@
+//! A front-end for web pages, built for `wasm32-unknown-unknown` with the
+//! `web` feature. The page paces the frames, calling
+//! [`WebAutomaton::frame`] from `requestAnimationFrame`.
+
+use crate::seed::Seed;
+use crate::{Automaton, SimContext, StepMode, GRID_X_SIZE, GRID_Y_SIZE};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::Clamped;
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, ImageData};
+
+const LIVE_CELL: [u8; 4] = [255, 255, 255, 255];
+const DEAD_CELL: [u8; 4] = [0, 0, 0, 255];
+const SCANNER: [u8; 4] = [255, 255, 0, 255];
+
+/// An automaton drawn into a canvas, a pixel for every cell. CSS scales
+/// the canvas up, `image-rendering: pixelated` keeps the cells crisp.
+#[wasm_bindgen]
+pub struct WebAutomaton {
+    context: SimContext,
+    canvas: CanvasRenderingContext2d,
+    frame: u64,
+    pixels: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl WebAutomaton {
+    /// Grows `rule` from `seed`, `center` or a number, in `canvas`, which
+    /// gets the size of the grid.
+    #[wasm_bindgen(constructor)]
+    pub fn new(canvas: HtmlCanvasElement, rule: u8, seed: &str) -> Result<WebAutomaton, JsError> {
+        let seed: Seed = seed.parse()?;
+        canvas.set_width(GRID_X_SIZE);
+        canvas.set_height(GRID_Y_SIZE);
+        let canvas = canvas
+            .get_context("2d")
+            .ok()
+            .flatten()
+            .and_then(|context| context.dyn_into::<CanvasRenderingContext2d>().ok())
+            .ok_or_else(|| JsError::new("the canvas has no 2d context"))?;
+        Ok(WebAutomaton {
+            context: SimContext::seeded(rule, seed),
+            canvas,
+            frame: 0,
+            pixels: vec![0; (GRID_X_SIZE * GRID_Y_SIZE * 4) as usize],
+        })
+    }
+
+    /// Ticks as the tick rate asks for and draws the grid.
+    pub fn frame(&mut self) -> Result<(), JsValue> {
+        self.context.play_frame(self.frame);
+        self.frame += 1;
+        self.draw()
+    }
+
+    /// Does what the key, as `KeyboardEvent.key` names it, does in the
+    /// window: Space pauses, the arrows step, `+` and `-` change the
+    /// speed, Tab the step mode, `[` and `]` the rule and `e` endless
+    /// mode. Returns whether the key did anything, for the page to
+    /// prevent its default.
+    pub fn key(&mut self, key: &str) -> bool {
+        let context = &mut self.context;
+        match key {
+            " " => context.toggle_pause(),
+            "ArrowRight" => context.advance(),
+            "ArrowLeft" => context.rewind(),
+            "+" | "=" => context.tick_rate = context.tick_rate.faster(),
+            "-" => context.tick_rate = context.tick_rate.slower(),
+            "Tab" => context.toggle_step_mode(),
+            "]" => context.reset(context.rule.wrapping_add(1), context.seed),
+            "[" => context.reset(context.rule.wrapping_sub(1), context.seed),
+            "e" => context.endless = !context.endless,
+            _ => return false,
+        }
+        true
+    }
+
+    /// Starts over from the seed with `rule`.
+    pub fn set_rule(&mut self, rule: u8) {
+        self.context.reset(rule, self.context.seed);
+    }
+
+    /// The title the window would show, like `Rule 30 — gen 12 — playing`.
+    pub fn title(&self) -> String {
+        self.context.title()
+    }
+
+    fn draw(&mut self) -> Result<(), JsValue> {
+        let context = &self.context;
+        for (x, column) in context.points.iter().enumerate() {
+            for (y, &live) in column.iter().enumerate() {
+                let index = (y * GRID_X_SIZE as usize + x) * 4;
+                let color = if live { LIVE_CELL } else { DEAD_CELL };
+                self.pixels[index..index + 4].copy_from_slice(&color);
+            }
+        }
+        if context.automaton == Automaton::Elementary && context.step_mode == StepMode::Cell {
+            for point in &context.scanner {
+                let index = (point.1 as usize * GRID_X_SIZE as usize + point.0 as usize) * 4;
+                self.pixels[index..index + 4].copy_from_slice(&SCANNER);
+            }
+        }
+        let image = ImageData::new_with_u8_clamped_array_and_sh(
+            Clamped(&self.pixels),
+            GRID_X_SIZE,
+            GRID_Y_SIZE,
+        )?;
+        self.canvas.put_image_data(&image, 0.0, 0.0)
+    }
+}
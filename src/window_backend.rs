@@ -0,0 +1,520 @@
+use crate::backend::Backend;
+use crate::frame_capture::FrameCapture;
+use crate::gif_export::GifRecorder;
+use crate::kiosk::{self, Kiosk};
+use crate::options::{self, Options};
+use crate::remote::{Remote, WakeUp};
+use crate::renderer::Renderer;
+use crate::row_stream::RowStream;
+use crate::seed::SplitMix64;
+use crate::serve::Server;
+use crate::session::{Request, Session};
+use crate::state_file;
+use crate::stdin_rows::StdinRows;
+use crate::theme::Theme;
+use crate::watcher::FileWatcher;
+use crate::window_geometry::WindowGeometry;
+use crate::{bookmarks, configured, Error, SimContext, SimulationState};
+use log::{error, info, warn};
+use sdl2::event::{Event, EventSender, WindowEvent};
+use sdl2::{EventPump, Sdl, VideoSubsystem};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+const WINDOW_TITLE: &str = "Cellular automata";
+/// How far the mouse may drift before the screensaver gives up.
+const SCREENSAVER_MOUSE_TOLERANCE: i32 = 10;
+/// How long a paused, idle window waits for input before redrawing anyway.
+const IDLE_REDRAW_INTERVAL_MS: u32 = 500;
+/// The DPI that window sizes are worked out for.
+const BASELINE_DPI: f32 = 96.0;
+
+/// The windows with every control, overlay and export, for
+/// [`crate::backend::run`]. Each window steps its own automata.
+pub struct WindowBackend {
+    // The windows only work while SDL stays initialized.
+    _sdl_context: Sdl,
+    video_subsystem: VideoSubsystem,
+    event_pump: EventPump,
+    options: Options,
+    /// The arguments given, for reloading the configuration, which they
+    /// win over.
+    arguments: Vec<String>,
+    sessions: Vec<Session>,
+    rng: SplitMix64,
+    theme_watcher: Option<FileWatcher>,
+    pattern_watcher: Option<FileWatcher>,
+    /// Only the first window started from the pattern, the others run
+    /// presets of their own.
+    pattern_window: u32,
+    // Remote commands go to the first window, or the one left over if it
+    // closed, and the server mirrors that one.
+    remote: Option<Remote>,
+    server: Option<Server>,
+    /// How far the mouse moved in the screensaver.
+    mouse_travel: i32,
+}
+
+impl WindowBackend {
+    /// Opens the window for `contexts`, and the ones `--window` asks for
+    /// beside it. `loaded_from` is the state file the automata come from,
+    /// whose bookmarks come along.
+    pub fn open(
+        mut options: Options,
+        arguments: Vec<String>,
+        contexts: Vec<SimContext>,
+        loaded_from: Option<PathBuf>,
+    ) -> Result<WindowBackend, Error> {
+        let sdl_context = sdl2::init().map_err(Error::Sdl)?;
+        let video_subsystem = sdl_context.video().map_err(Error::Sdl)?;
+        let mut session = open_window(&video_subsystem, &options, contexts)?;
+        if !options.screensaver {
+            if let Some(geometry) = WindowGeometry::load() {
+                session
+                    .renderer
+                    .restore_geometry(geometry, options.scale.is_some())?;
+            }
+        }
+        let time_seed = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_nanos() as u64);
+        if let Some(path) = &loaded_from {
+            match bookmarks::load(path) {
+                Ok(bookmarks) => session.renderer.bookmarks = bookmarks,
+                Err(e) => warn!("{}", e),
+            }
+        }
+        if options.screensaver {
+            sdl_context.mouse().show_cursor(false);
+            session.kiosk = Some(Kiosk::shuffled(options.dwell, time_seed));
+        } else if options.kiosk {
+            session.kiosk = Some(Kiosk::new(options.playlist.clone(), options.dwell));
+        }
+        session.stream = options.stream.map(RowStream::new);
+        session.script = options.script.take();
+        if options.stdin {
+            let context = &mut session.contexts[0];
+            session.stdin = Some(StdinRows::spawn(
+                options.stdin_format,
+                options.stdin_width,
+                context,
+            ));
+        }
+        if let Some(every) = options.capture_every {
+            session.renderer.frame_capture = Some(FrameCapture::start(every));
+        }
+        if let Some(path) = &options.export_gif {
+            let theme = session.renderer.theme();
+            session.gif = Some(GifRecorder::create(
+                path,
+                options.export_scale,
+                (theme.live_cell, theme.dead_cell),
+                options.gif_every,
+                options.gif_delay_ms,
+            )?);
+        }
+        if let Some(kiosk) = &session.kiosk {
+            let preset = kiosk.current();
+            for context in &mut session.contexts {
+                context.reset(preset.rule, preset.seed);
+                context.state = SimulationState::Playing;
+            }
+        }
+        let mut sessions = vec![session];
+        for preset in &options.windows {
+            let context = SimContext::seeded(preset.rule, preset.seed);
+            let context = configured(context, &options);
+            sessions.push(open_window(&video_subsystem, &options, vec![context])?);
+        }
+        let event_subsystem = sdl_context.event().map_err(Error::Sdl)?;
+        let remote = match &options.control {
+            Some(source) => Some(Remote::spawn(
+                source,
+                wake_up(event_subsystem.event_sender()),
+            )?),
+            None => None,
+        };
+        let server = match &options.serve {
+            Some(address) => Some(Server::spawn(
+                address,
+                options.serve_format,
+                wake_up(event_subsystem.event_sender()),
+            )?),
+            None => None,
+        };
+        Ok(WindowBackend {
+            event_pump: sdl_context.event_pump().map_err(Error::Sdl)?,
+            _sdl_context: sdl_context,
+            video_subsystem,
+            theme_watcher: options.theme_file.clone().map(FileWatcher::spawn),
+            pattern_watcher: options.pattern_file.clone().map(FileWatcher::spawn),
+            pattern_window: sessions[0].window_id(),
+            options,
+            arguments,
+            sessions,
+            rng: SplitMix64::new(time_seed),
+            remote,
+            server,
+            mouse_travel: 0,
+        })
+    }
+
+    /// Quits, with the first window standing for all of them.
+    fn quit(&self) -> Result<bool, Error> {
+        if let Some(session) = self.sessions.first() {
+            autosave(session, &self.options);
+        }
+        Ok(false)
+    }
+
+    /// Closes the window at `index`. The last one to close leaves its
+    /// automata behind for `--resume` and its geometry for the next run.
+    fn close_window(&mut self, index: usize) {
+        let session = self.sessions.remove(index);
+        info!("Closed a window, {} left", self.sessions.len());
+        if self.sessions.is_empty() {
+            autosave(&session, &self.options);
+        }
+    }
+
+    /// Swaps the theme loaded from `path` for its new contents in every
+    /// window and in the ones opened later. A theme that does not load, for
+    /// example because it is only half written, leaves the old one in place.
+    fn reload_theme(&mut self, path: &Path) {
+        match Theme::load(path) {
+            Ok(theme) => {
+                info!("Reloaded the theme from {}", path.display());
+                for session in self.sessions.iter_mut() {
+                    session
+                        .renderer
+                        .replace_theme(&self.options.theme.name, theme.clone());
+                    session.request_redraw();
+                }
+                self.options.theme = theme;
+            }
+            Err(e) => error!("{}", e),
+        }
+    }
+
+    /// Reads the configuration file and the arguments again and applies the
+    /// theme, tick rate and keys that changed to every window and the ones
+    /// opened later, reporting what changed in the log and the status bar. A
+    /// configuration that does not load leaves everything as it is.
+    fn reload_config(&mut self) {
+        let (options, sessions) = (&mut self.options, &mut self.sessions);
+        let fresh = match Options::parse(self.arguments.clone()) {
+            Ok(Some(fresh)) => fresh,
+            Ok(None) => return,
+            Err(e) => {
+                error!("{}", e);
+                for session in sessions.iter_mut() {
+                    session.renderer.show_notice("Could not reload the config");
+                }
+                return;
+            }
+        };
+        let mut changed = Vec::new();
+        if fresh.theme != options.theme {
+            info!("Theme {}", fresh.theme.name);
+            changed.push("theme");
+            for session in sessions.iter_mut() {
+                let renderer = &mut session.renderer;
+                if fresh.theme.name == options.theme.name {
+                    renderer.replace_theme(&options.theme.name, fresh.theme.clone());
+                }
+                renderer.set_theme(fresh.theme.clone());
+            }
+            options.theme = fresh.theme;
+        }
+        if fresh.tick_rate != options.tick_rate {
+            info!("Tick rate {}", fresh.tick_rate);
+            changed.push("tick rate");
+            for session in sessions.iter_mut() {
+                for context in &mut session.contexts {
+                    context.tick_rate = fresh.tick_rate;
+                }
+            }
+            options.tick_rate = fresh.tick_rate;
+        }
+        if fresh.keys != options.keys || fresh.escape != options.escape {
+            info!("Rebound the keys");
+            changed.push("keys");
+            for session in sessions.iter_mut() {
+                session.keys = fresh.keys.clone();
+                session.escape = fresh.escape;
+                session.renderer.help = fresh.keys.help(fresh.escape);
+            }
+            options.keys = fresh.keys;
+            options.escape = fresh.escape;
+        }
+        let notice = match changed.is_empty() {
+            true => "Config unchanged".to_string(),
+            false => format!("Reloaded: {}", changed.join(", ")),
+        };
+        info!("{}", notice);
+        for session in sessions.iter_mut() {
+            session.renderer.show_notice(&notice);
+            session.request_redraw();
+        }
+    }
+}
+
+impl Backend for WindowBackend {
+    /// Waits for input while every window is idle, and redraws them if
+    /// none comes.
+    fn handle_input(&mut self, frame: u64) -> Result<bool, Error> {
+        let mut events = Vec::new();
+        if self.sessions.iter().all(Session::is_idle) {
+            match self.event_pump.wait_event_timeout(IDLE_REDRAW_INTERVAL_MS) {
+                Some(event) => events.push(event),
+                None => self.sessions.iter_mut().for_each(Session::request_redraw),
+            }
+        }
+        events.extend(self.event_pump.poll_iter());
+
+        if let Some(path) = changed(&self.theme_watcher) {
+            self.reload_theme(&path);
+        }
+        if let Some(path) = changed(&self.pattern_watcher) {
+            let pattern_window = self.pattern_window;
+            let session = self
+                .sessions
+                .iter_mut()
+                .find(|session| session.window_id() == pattern_window);
+            if let Some(session) = session {
+                reload_pattern(&path, session);
+            }
+        }
+        for event in events {
+            if self.options.screensaver {
+                match event {
+                    Event::KeyDown { .. } | Event::MouseButtonDown { .. } => return self.quit(),
+                    Event::MouseMotion { xrel, yrel, .. } => {
+                        self.mouse_travel += xrel.abs() + yrel.abs();
+                        if self.mouse_travel > SCREENSAVER_MOUSE_TOLERANCE {
+                            return self.quit();
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            match event {
+                Event::Quit { .. } => return self.quit(),
+                Event::Window {
+                    win_event: WindowEvent::Close,
+                    window_id,
+                    ..
+                } => {
+                    if let Some(index) = self
+                        .sessions
+                        .iter()
+                        .position(|session| session.window_id() == window_id)
+                    {
+                        self.close_window(index);
+                    }
+                }
+                event => {
+                    let window_id = event.get_window_id();
+                    if let Some(session) = self
+                        .sessions
+                        .iter_mut()
+                        .find(|session| Some(session.window_id()) == window_id)
+                    {
+                        let mouse = self.event_pump.mouse_state();
+                        session.handle_event(event, frame, (mouse.x(), mouse.y()));
+                    }
+                }
+            }
+        }
+        if let Some(session) = self.sessions.first_mut() {
+            let remote_commands = self.remote.as_ref().map(Remote::commands);
+            let served_commands = self.server.as_ref().map(Server::commands);
+            for command in remote_commands
+                .unwrap_or_default()
+                .into_iter()
+                .chain(served_commands.unwrap_or_default())
+            {
+                session.queue(frame, command);
+            }
+        }
+        Ok(!self.sessions.is_empty())
+    }
+
+    /// Steps and draws every window, then closes and opens the windows
+    /// they asked for.
+    fn update(&mut self, frame: u64) -> Result<bool, Error> {
+        let mut closed = Vec::new();
+        let mut opened = 0;
+        let mut reload = false;
+        for (index, session) in self.sessions.iter_mut().enumerate() {
+            for request in session.update(frame)? {
+                match request {
+                    Request::Close => closed.push(index),
+                    Request::OpenWindow => opened += 1,
+                    Request::ReloadConfig => reload = true,
+                }
+            }
+        }
+        if reload {
+            self.reload_config();
+        }
+        for index in closed.into_iter().rev() {
+            self.close_window(index);
+        }
+        for _ in 0..opened {
+            let preset = kiosk::random_preset(&mut self.rng);
+            let context = SimContext::seeded(preset.rule, preset.seed);
+            let context = configured(context, &self.options);
+            let session = open_window(&self.video_subsystem, &self.options, vec![context])?;
+            self.sessions.push(session);
+        }
+        if self.sessions.is_empty() {
+            return Ok(false);
+        }
+        if let Some(server) = &mut self.server {
+            server.publish(&self.sessions[0].contexts[0]);
+        }
+        Ok(true)
+    }
+}
+
+/// Pushes an event for no window through `events`, which only ends the
+/// wait for one.
+fn wake_up(events: EventSender) -> WakeUp {
+    Arc::new(move || {
+        let _ = events.push_event(Event::User {
+            timestamp: 0,
+            window_id: 0,
+            type_: sdl2::sys::SDL_EventType::SDL_USEREVENT as u32,
+            code: 0,
+            data1: std::ptr::null_mut(),
+            data2: std::ptr::null_mut(),
+        });
+    })
+}
+
+/// The path of the file `watcher` watches, if it changed.
+fn changed(watcher: &Option<FileWatcher>) -> Option<PathBuf> {
+    watcher
+        .as_ref()
+        .filter(|watcher| watcher.changed())
+        .map(|watcher| watcher.path.clone())
+}
+
+fn autosave(session: &Session, options: &Options) {
+    if options.kiosk || options.screensaver {
+        return;
+    }
+    if let Err(e) = state_file::autosave(&session.contexts) {
+        error!("{}", e);
+    }
+    if let Some(path) = state_file::autosave_path() {
+        if let Err(e) = bookmarks::save(&path, &session.renderer.bookmarks) {
+            error!("{}", e);
+        }
+    }
+    if let Err(e) = session.renderer.geometry().save() {
+        error!("{}", e);
+    }
+}
+
+/// Starts the automata of `session` over from the pattern in `path`,
+/// keeping them playing or paused, so editing the pattern previews it.
+fn reload_pattern(path: &Path, session: &mut Session) {
+    match options::load_pattern(path) {
+        Ok(pattern) => {
+            info!("Reloaded the pattern from {}", path.display());
+            for context in &mut session.contexts {
+                context.place(&pattern);
+            }
+            session.request_redraw();
+        }
+        Err(e) => error!("{}", e),
+    }
+}
+
+/// How much to enlarge a window of `size` so it does not come out tiny on
+/// a high DPI display, without growing past the screen. macOS already
+/// measures windows in points, so nothing needs enlarging there.
+fn display_scale(video_subsystem: &VideoSubsystem, size: (u32, u32)) -> f32 {
+    if cfg!(target_os = "macos") {
+        return 1.0;
+    }
+    let Ok((_, horizontal_dpi, _)) = video_subsystem.display_dpi(0) else {
+        return 1.0;
+    };
+    // Stick to quarter steps, which keep the cells evenly sized.
+    let mut scale = ((horizontal_dpi / BASELINE_DPI) * 4.0).round() / 4.0;
+    if let Ok(bounds) = video_subsystem.display_usable_bounds(0) {
+        let fits =
+            (bounds.width() as f32 / size.0 as f32).min(bounds.height() as f32 / size.1 as f32);
+        scale = scale.min(fits);
+    }
+    scale.max(1.0)
+}
+
+/// Opens a window showing `contexts` side by side, set up as the options
+/// ask for.
+fn open_window(
+    video_subsystem: &VideoSubsystem,
+    options: &Options,
+    contexts: Vec<SimContext>,
+) -> Result<Session, Error> {
+    let (width, height) = Renderer::size_for(contexts.len());
+    let scale = options
+        .scale
+        .unwrap_or_else(|| display_scale(video_subsystem, (width, height)));
+    let (width, height) = (
+        (width as f32 * scale).round() as u32,
+        (height as f32 * scale).round() as u32,
+    );
+    let mut window_builder = video_subsystem.window(WINDOW_TITLE, width, height);
+    window_builder
+        .position_centered()
+        .resizable()
+        .allow_highdpi()
+        .opengl();
+    if options.screensaver {
+        window_builder.fullscreen_desktop();
+    }
+    if options.borderless {
+        window_builder.borderless();
+    }
+    if options.always_on_top {
+        window_builder.always_on_top();
+    }
+    let window = window_builder
+        .build()
+        .map_err(|e| e.to_string())
+        .map_err(Error::Sdl)?;
+
+    let mut renderer = Renderer::new(window, options.theme.clone())?;
+    renderer.set_pane_count(contexts.len())?;
+    renderer.palette = options.palette;
+    renderer.show_scanner = !options.hide_scanner;
+    renderer.scanner_color = options.scanner_color;
+    renderer.scanner_trail = options.scanner_trail;
+    renderer.crt = options.crt;
+    renderer.scale_filter = options.scale_filter;
+    renderer.output = options.output.clone();
+    renderer.video_format = options.video_format.clone();
+    renderer.background = options.background.clone();
+    renderer.help = options.keys.help(options.escape);
+
+    let rules: Vec<String> = contexts
+        .iter()
+        .map(|context| context.rule.to_string())
+        .collect();
+    info!("Opened a window for rule {}", rules.join(", "));
+    let mut session = Session::new(renderer, contexts);
+    session.escape = options.escape;
+    session.keys = options.keys.clone();
+    session.presets = options.presets.clone();
+    session.preset = options.preset;
+    session.confirm_quit = options.confirm_quit;
+    session.export_scale = options.export_scale;
+    session.diagram_format = options.diagram_format;
+    session.column_format = options.column_format;
+    session.state_file = options.state_file.clone();
+    Ok(session)
+}
@@ -0,0 +1,85 @@
+use crate::state_file;
+use crate::Error;
+use sdl2::rect::{Point, Rect};
+use sdl2::video::{FullscreenType, Window};
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+const FILE_NAME: &str = "window.toml";
+
+/// Where the window was, how large and whether it was fullscreen, kept
+/// between runs in the data directory.
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+pub struct WindowGeometry {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub fullscreen: bool,
+}
+
+impl WindowGeometry {
+    /// The geometry of `window`. A fullscreen window covers the whole
+    /// display, so it keeps the position and size saved before, for
+    /// leaving fullscreen next time.
+    pub fn of(window: &Window) -> WindowGeometry {
+        let fullscreen = window.fullscreen_state() != FullscreenType::Off;
+        if let Some(saved) = WindowGeometry::load().filter(|_| fullscreen) {
+            return WindowGeometry {
+                fullscreen,
+                ..saved
+            };
+        }
+        let (x, y) = window.position();
+        let (width, height) = window.size();
+        WindowGeometry {
+            x,
+            y,
+            width,
+            height,
+            fullscreen,
+        }
+    }
+
+    /// Moves and resizes `window` to where it was, or only moves it with
+    /// `keep_size`. A position on a display that is gone by now is left
+    /// alone, so the window does not end up out of sight.
+    pub fn restore(&self, window: &mut Window, keep_size: bool) -> Result<(), Error> {
+        if !keep_size {
+            window
+                .set_size(self.width, self.height)
+                .map_err(|e| Error::Sdl(e.to_string()))?;
+        }
+        let video = window.subsystem();
+        let on_screen = (0..video.num_video_displays().map_err(Error::Sdl)?).any(|display| {
+            video
+                .display_bounds(display)
+                .is_ok_and(|bounds: Rect| bounds.contains_point(Point::new(self.x, self.y)))
+        });
+        if on_screen {
+            window.set_position(self.x.into(), self.y.into());
+        }
+        if self.fullscreen {
+            window
+                .set_fullscreen(FullscreenType::Desktop)
+                .map_err(Error::Sdl)?;
+        }
+        Ok(())
+    }
+
+    /// The geometry saved last time, if any. A file that does not parse
+    /// is as good as none.
+    pub fn load() -> Option<WindowGeometry> {
+        let contents = fs::read_to_string(state_file::data_dir()?.join(FILE_NAME)).ok()?;
+        toml::from_str(&contents).ok()
+    }
+
+    pub fn save(&self) -> Result<(), Error> {
+        let dir = state_file::data_dir()
+            .ok_or_else(|| Error::Config("nowhere to save the window to".to_string()))?;
+        fs::create_dir_all(&dir).map_err(|e| Error::io("create", &dir, e))?;
+        let path = dir.join(FILE_NAME);
+        let contents = toml::to_string(self).map_err(|e| Error::Serialize(e.to_string()))?;
+        fs::write(&path, contents).map_err(|e| Error::io("write", path, e))
+    }
+}
@@ -0,0 +1,75 @@
+//! Exporting `--generations N` gives the seed row and the N rows after it,
+//! however far past the grid they go.
+
+use std::fs;
+use std::process::Command;
+
+use cellular_automata::{SimContext, StepMode};
+
+/// Runs the binary in a scratch directory of its own and returns what it
+/// wrote to `name`.
+fn export(name: &str, generations: u64) -> String {
+    let dir = std::env::temp_dir().join(format!("rule30-export-{}-{}", std::process::id(), name));
+    fs::create_dir_all(&dir).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_cellular_automata"))
+        .args(["--export", name, "--generations", &generations.to_string()])
+        .current_dir(&dir)
+        // Keep the configuration of whoever runs this out.
+        .env("HOME", &dir)
+        .env("XDG_CONFIG_HOME", &dir)
+        .env("XDG_DATA_HOME", &dir)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let contents = fs::read_to_string(dir.join(name)).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    contents
+}
+
+#[test]
+fn generations_export_one_row_more() {
+    let json = export("diagram.json", 10);
+    assert!(json.contains("\"height\":11"), "{}", json);
+    assert!(json.contains("\"generation\":10"), "{}", json);
+    assert_eq!(export("diagram.csv", 5000).lines().count(), 5001);
+}
+
+#[test]
+fn diagram_starts_at_the_seed_row() {
+    let mut context = SimContext::new();
+    assert_eq!(context.diagram(), vec![context.finished_row()]);
+    context.step_mode = StepMode::Row;
+    for _ in 0..10 {
+        context.advance();
+    }
+    let diagram = context.diagram();
+    assert_eq!(diagram.len(), 11);
+    assert_eq!(diagram[0].iter().filter(|&&alive| alive).count(), 1);
+    assert_eq!(diagram.last(), Some(&context.finished_row()));
+}
+
+#[test]
+fn export_matches_the_rows() {
+    // Far enough past the grid for the history the window keeps to run
+    // out long before.
+    let generations = 3000;
+    let csv = export("diagram.csv", generations);
+    let expected: Vec<String> = SimContext::new()
+        .rows()
+        .take(generations as usize + 1)
+        .map(|row| {
+            let cells: Vec<&str> = row
+                .iter()
+                .map(|&alive| if alive { "1" } else { "0" })
+                .collect();
+            cells.join(",")
+        })
+        .collect();
+    let exported: Vec<&str> = csv.lines().collect();
+    assert_eq!(exported.len(), expected.len());
+    assert!(exported.iter().eq(expected.iter()));
+}
@@ -0,0 +1,40 @@
+//! Quitting right after asking for a screenshot still saves it, although
+//! no frame is drawn after the quit.
+
+use std::fs;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+#[test]
+fn screenshot_before_quit_is_saved() {
+    let dir = std::env::temp_dir().join(format!("rule30-quit-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_cellular_automata"))
+        .args(["--control", "stdin"])
+        .current_dir(&dir)
+        // Keep the configuration and the autosave of whoever runs this out.
+        .env("HOME", &dir)
+        .env("XDG_CONFIG_HOME", &dir)
+        .env("XDG_DATA_HOME", &dir)
+        .env("SDL_VIDEODRIVER", "offscreen")
+        .stdin(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    // Both arrive before the next frame.
+    write!(child.stdin.take().unwrap(), "screenshot\nquit\n").unwrap();
+    let output = child.wait_with_output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{}", stderr);
+    assert!(stderr.contains("Saved a screenshot"), "{}", stderr);
+
+    let screenshots = fs::read_dir(&dir)
+        .unwrap()
+        .filter(|entry| {
+            let name = entry.as_ref().unwrap().file_name();
+            name.to_string_lossy().starts_with("screenshot-")
+        })
+        .count();
+    assert_eq!(screenshots, 1);
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,56 @@
+//! `--stream` shares stdout with nothing else: what the window saves is
+//! reported on stderr, so the rows can be piped into other programs.
+
+use std::fs;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn stream_leaves_stdout_to_the_rows() {
+    let dir = std::env::temp_dir().join(format!("rule30-stream-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_cellular_automata"))
+        .args([
+            "--stream",
+            "text",
+            "--control",
+            "stdin",
+            "--tick-rate",
+            "60",
+        ])
+        .current_dir(&dir)
+        // Keep the configuration and the autosave of whoever runs this out.
+        .env("HOME", &dir)
+        .env("XDG_CONFIG_HOME", &dir)
+        .env("XDG_DATA_HOME", &dir)
+        .env("SDL_VIDEODRIVER", "offscreen")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    for command in ["pause", "screenshot", "quit"] {
+        writeln!(stdin, "{}", command).unwrap();
+        // Rows keep coming in between, once the window plays.
+        thread::sleep(Duration::from_millis(300));
+    }
+    let output = child.wait_with_output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{}", stderr);
+    assert!(stderr.contains("Saved a screenshot"), "{}", stderr);
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let rows: Vec<&str> = stdout.lines().collect();
+    assert!(rows.len() >= 3, "{:?}", rows);
+    for row in rows {
+        assert!(
+            row.len() == 101 && row.chars().all(|c| c == '0' || c == '1'),
+            "not a row: {:?}",
+            row
+        );
+    }
+    fs::remove_dir_all(&dir).unwrap();
+}